@@ -345,6 +345,22 @@ class LeftJustifiedHeading(TextElement):
         text.justify = "left"
         yield Text(f"{' ' * (self.level - 1)}→ {text}")
 
+# The types of section whose items are wrapped as either a flat list or a map of groups
+LISTING_TYPES = {"events", "daily_notes", "tickles", "person_dates", "tasks", "stacks", "waitings"}
+def unwrap_listing(ty, items, view_name: str):
+    """
+    Unwraps the items of a section of the given type if they're a listing, which Polaris outputs
+    as either `{"flat": [...]}` or `{"grouped": {...}}`. Grouped views aren't displayed yet, so
+    those are rejected. Any other type of section is returned as it is.
+    """
+
+    if ty not in LISTING_TYPES:
+        return items
+    if "grouped" in items:
+        raise ValueError(f"view {view_name} groups its {ty}, which cannot be displayed yet")
+
+    return items["flat"]
+
 def build_dashboards(json: dict, current_date: date):
     """
     Converts the given action item data from Polaris into a series of displayable objects. This
@@ -357,7 +373,9 @@ def build_dashboards(json: dict, current_date: date):
 
     dashboards = []
 
-    for view_name_str, view_data_map in json.items():
+    for view_name_str, sections in json.items():
+        # Each view is a list of tagged sections, one for each type of data in it
+        view_data_map = {section["type"]: unwrap_listing(section["type"], section["items"], view_name_str) for section in sections}
         view_name_parts = view_name_str.split("__", 1)
         view_name = view_name_parts[0]
         view_pos = view_name_parts[1] if len(view_name_parts) > 1 else None
@@ -477,7 +495,7 @@ if __name__ == "__main__":
     import sys
 
     current_date = datetime.strptime(sys.argv[1], "%Y-%m-%d") if len(sys.argv) > 1 else datetime.now().date()
-    dashboards = build_dashboards(json.load(sys.stdin), current_date)
+    dashboards = build_dashboards(json.load(sys.stdin)["views"], current_date)
 
     layout = build_layout(dashboards)
     rich_print(layout)
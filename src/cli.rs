@@ -1,37 +1,124 @@
-use crate::views::{AllViews, View};
+use crate::extractors::STALE_WAIT_DAYS;
+#[cfg(feature = "goals")]
+use crate::parse::PathTemplate;
+use crate::views::{AllViews, DateRange, View};
 use anyhow::{bail, Context, Error};
-use clap::{Parser, ValueEnum};
-use serde::Deserialize;
-use std::{collections::HashMap, ops::Deref, path::PathBuf, str::FromStr};
+use chrono::{Duration, NaiveDate, NaiveTime, Weekday};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Deserializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Deref,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 /// Polaris, the ultimate scheduling tool.
+///
+/// Every option can also be set with a `POLARIS_*` environment variable (e.g. `POLARIS_STARLING`),
+/// which will override the config file, but not flags given on the command line.
+///
+/// Wherever a command takes the ID of an item, a unique prefix of it, or an alias from the config
+/// file, can be given instead.
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[command(flatten)]
     view_options: ViewOptions,
 
-    /// Completion keywords to recognise and exclude from the action items.
-    #[arg(long, default_values_t = vec!["DONE".to_string(), "CONT".to_string(), "PROB".to_string()])]
+    /// The path to a JSON configuration file, which can set any of the options below (CLI flags
+    /// will override it). If not given, `polaris/config.json` in the user's configuration
+    /// directory will be used if it exists.
+    #[arg(long, env = "POLARIS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Completion keywords to recognise and exclude from the action items. `CANCELLED` is always
+    /// treated as one of these.
+    #[arg(long, env = "POLARIS_DONE_KEYWORDS", value_delimiter = ',', default_values_t = vec!["DONE".to_string(), "CONT".to_string(), "PROB".to_string()])]
     pub done_keywords: Vec<String>,
-    /// The address of the Starling endpoint from which to fetch action items.
-    #[arg(long = "starling", default_value = "localhost:3000")]
+    /// The address of the Starling endpoint from which to fetch action items. This can be a full
+    /// URL (e.g. `https://starling.example.com`), otherwise plain HTTP is used.
+    #[arg(
+        long = "starling",
+        env = "POLARIS_STARLING",
+        default_value = "localhost:3000"
+    )]
     pub starling_address: String,
+    /// A token to send as a bearer token with every request to Starling, for setups where it sits
+    /// behind an authenticating proxy.
+    #[arg(long, env = "POLARIS_STARLING_TOKEN", hide_env_values = true)]
+    pub starling_token: Option<String>,
+    /// The number of seconds each request to Starling can take before it fails.
+    #[arg(long, env = "POLARIS_STARLING_TIMEOUT", default_value_t = 30)]
+    pub starling_timeout: u64,
+    /// How many times to retry fetches from Starling which fail in a way that might be temporary
+    /// (e.g. while it restarts), waiting twice as long before each retry (starting from a second,
+    /// and up to thirty seconds). This can be at most ten. Changes to items are never retried.
+    #[arg(long, env = "POLARIS_STARLING_RETRIES", default_value_t = 3)]
+    pub starling_retries: u32,
+    /// A PEM file with a CA certificate to check the certificate of an HTTPS Starling instance
+    /// against, instead of the usual roots (e.g. for a self-signed certificate).
+    #[cfg(feature = "tls")]
+    #[arg(long, env = "POLARIS_STARLING_CA")]
+    pub starling_ca: Option<PathBuf>,
+    /// Skips checking the certificate of an HTTPS Starling instance entirely. This should only
+    /// ever be used for testing.
+    #[cfg(feature = "tls")]
+    #[arg(long, env = "POLARIS_STARLING_INSECURE")]
+    pub starling_insecure: bool,
+    /// Where to read action items from: `starling`, or `dir:` followed by a directory of Markdown
+    /// and Org files to parse directly, without a Starling instance (e.g. `dir:/home/me/notes`).
+    /// Items can't be changed, and goals can't be extracted, without Starling.
+    #[arg(long, env = "POLARIS_SOURCE", default_value = "starling")]
+    pub source: String,
     /// Which encoding to output.
-    #[arg(short, long, default_value = "json")]
+    #[arg(short, long, env = "POLARIS_ENCODING", default_value = "json")]
     pub encoding: Encoding,
+    /// Skips any items which can't be processed (e.g. because of a malformed property), reporting
+    /// them in the `errors` of the output, rather than failing entirely.
+    #[arg(long, env = "POLARIS_LENIENT")]
+    pub lenient: bool,
+    /// Exits with a distinct code (5) if every view is empty, so scripts can tell when there's
+    /// nothing to show. The output is still produced as usual.
+    #[arg(long, env = "POLARIS_FAIL_IF_EMPTY")]
+    pub fail_if_empty: bool,
+    /// A state file recording the items emitted on the last run that used it. If given, views of
+    /// items (like events, tasks, and stacks) will only contain those that are new or have changed
+    /// since that run, and the file will be updated afterwards. Other views (like agendas) are
+    /// always produced in full. If the file doesn't exist, everything will be treated as new.
+    #[arg(long, env = "POLARIS_CHANGED_SINCE")]
+    pub changed_since: Option<PathBuf>,
     /// The amount of time to add after the last date in the views to guide when to stop expanding
     /// repeating timestamps. If there are no date filters, this will be added to the present date.
     /// It should be large enough to account for the longest person date notification times in
-    /// particular.
-    #[arg(long, default_value = "8w")]
+    /// particular. Buffers for specific types of items can be set under `repeat_buffers` in the
+    /// config file, but giving this explicitly overrides all of them.
+    #[arg(long, env = "POLARIS_REPEAT_BUFFER", default_value = "8w")]
     pub repeat_buffer: RepeatBuffer,
+    /// The day of the week weeks start on, in weekly views, weekly totals, and the default
+    /// weekly goals (which are read from the day before).
+    #[arg(long, env = "POLARIS_WEEK_START", default_value_t = Weekday::Mon)]
+    pub week_start: Weekday,
+    /// The path of daily journal files (relative to the Starling root) goals are read from, with
+    /// `strftime` placeholders for the date (e.g. `journals/%Y/%m/%d.md`).
+    #[cfg(feature = "goals")]
+    #[arg(
+        long,
+        env = "POLARIS_JOURNAL_TEMPLATE",
+        default_value = "journals/%Y/%m/%d.md"
+    )]
+    pub journal_template: PathTemplate,
 }
 impl Cli {
     /// Extracts the views from the options, which may involve reading a JSON definition of them.
     /// If the user has requested help on the views, this will return `Ok(None)`, and the caller
     /// should exit the process (help is printed automatically). This will group the views by data
     /// type, and work out the latest date among them.
-    pub fn parse_views(&mut self) -> Result<Option<AllViews>, Error> {
+    pub fn parse_views(&mut self, week_start: Weekday) -> Result<Option<AllViews>, Error> {
         // First, get a vector of views, all with different data types
         let views_vec = if let Some(views_help) = &self.view_options.views_help {
             NamedView::try_parse_from(
@@ -46,81 +133,423 @@ impl Cli {
         } else if !self.view_options.views.is_empty() {
             Ok::<_, Error>(std::mem::take(&mut self.view_options.views))
         } else if let Some(json_path) = &self.view_options.views_json {
-            let json_contents = std::fs::read_to_string(json_path)
-                .with_context(|| "failed to read json views file")?;
-            let views: HashMap<String, JsonView> = serde_json::from_str(&json_contents)
-                .with_context(|| "failed to parse json views file")?;
-            let views_vec = views
-                .into_iter()
-                .flat_map(|(name, view)| {
-                    let vec = match view {
-                        JsonView::Single(view) => vec![view],
-                        JsonView::Multiple(v) => v,
-                    };
-                    vec.into_iter().map(move |view| NamedView {
-                        name: name.clone(),
-                        view,
-                    })
-                })
-                .collect();
-            Ok(views_vec)
+            return read_json_views(json_path, week_start).map(Some);
         } else {
             // We're guaranteed to have one of them set by `clap`'s parsing rules
             unreachable!()
         }?;
 
-        // Now organise them by data type
-        let mut all_views = AllViews {
-            events: Vec::new(),
-            daily_notes: Vec::new(),
-            tickles: Vec::new(),
-            dates: Vec::new(),
-            waits: Vec::new(),
-            stacks: Vec::new(),
-            tasks: Vec::new(),
-            target_contexts: Vec::new(),
-            #[cfg(feature = "goals")]
-            goals: Vec::new(),
+        organise_views(views_vec, week_start).map(Some)
+    }
+}
 
-            last_date: None,
-        };
-        for named_view in views_vec {
-            // Validate the view, which will also return the last date in it
-            let last_date = named_view
-                .view
-                .validate()
-                .with_context(|| format!("failed to validate view `{}`", named_view.name))?;
-
-            // Add the view to the appropriate vector
-            match named_view.view {
-                View::Events(filter) => all_views.events.push((named_view.name, filter)),
-                View::DailyNotes(filter) => all_views.daily_notes.push((named_view.name, filter)),
-                View::Tickles(filter) => all_views.tickles.push((named_view.name, filter)),
-                View::Dates(filter) => all_views.dates.push((named_view.name, filter)),
-                View::Waits(filter) => all_views.waits.push((named_view.name, filter)),
-                View::Stacks(filter) => all_views.stacks.push((named_view.name, filter)),
-                View::Tasks(filter) => all_views.tasks.push((named_view.name, filter)),
-                View::TargetContexts(filter) => {
-                    all_views.target_contexts.push((named_view.name, filter))
-                }
-                #[cfg(feature = "goals")]
-                View::Goals(filter) => all_views.goals.push((named_view.name, filter)),
-            }
+/// Creates the built-in views for `polaris today`, which cover everything relevant to the given
+/// date: its events, tasks scheduled or due on or before it, tickles and daily notes, and goals
+/// (if enabled).
+pub fn today_views(date: NaiveDate, week_start: Weekday) -> Result<AllViews, Error> {
+    #[cfg_attr(not(feature = "goals"), allow(unused_mut))]
+    let mut views = vec![
+        format!("events events --from {date} --until {date}"),
+        format!("scheduled_tasks tasks --scheduled {date} --planning_match scheduled-only"),
+        format!("due_tasks tasks --deadline {date} --planning_match deadline-only"),
+        format!("tickles tickles --until {date}"),
+        format!("daily_notes daily_notes --from {date} --until {date}"),
+    ];
+    #[cfg(feature = "goals")]
+    views.push(format!("goals goals --date {date}"));
+
+    let views_vec = views
+        .iter()
+        .map(|view| view.parse())
+        .collect::<Result<Vec<NamedView>, _>>()?;
+    organise_views(views_vec, week_start)
+}
+
+/// Creates the built-in views for `polaris review`, which make up a weekly review of where things
+/// stand as of the given date: waiting items sent more than `stale_days` days ago with nothing to
+/// resurface them, stalled stacks with no actionable tasks, tickles that have fired, items
+/// completed over the last week, tasks due over the next fortnight, and the days over that
+/// fortnight which are over capacity.
+pub fn review_views(
+    date: NaiveDate,
+    stale_days: u32,
+    week_start: Weekday,
+) -> Result<AllViews, Error> {
+    let stale_before = date - Duration::days(stale_days.into());
+    let week_ago = date - Duration::days(6);
+    let fortnight = date + Duration::days(13);
+    let views = [
+        format!("stale_waits waits --stale-before {stale_before}"),
+        "stalled_stacks stacks --stalled".to_string(),
+        format!("fired_tickles tickles --until {date}"),
+        format!("completed completed --from {week_ago} --until {date}"),
+        format!("upcoming_deadlines tasks --deadline {fortnight} --planning_match deadline-only"),
+        format!("crunch_points capacity --from {date} --until {fortnight} --over-only"),
+    ];
+
+    let views_vec = views
+        .iter()
+        .map(|view| view.parse())
+        .collect::<Result<Vec<NamedView>, _>>()?;
+    organise_views(views_vec, week_start)
+}
+
+/// Creates the single view for `polaris pick` from the given arguments (a view without a name).
+pub fn pick_views(view: &[String], week_start: Weekday) -> Result<AllViews, Error> {
+    let named_view = NamedView::try_parse_from(
+        ["polaris_view", "pick"]
+            .into_iter()
+            .chain(view.iter().map(String::as_str)),
+    )?;
+    organise_views(vec![named_view], week_start)
+}
+
+/// Gets an empty set of views, for commands which work on the action items directly rather than
+/// through views.
+pub fn no_views() -> Result<AllViews, Error> {
+    // With no views, it doesn't matter when weeks start
+    organise_views(Vec::new(), Weekday::Mon)
+}
+
+/// Reads the views defined in the given JSON file (see [`parse_json_views`]).
+pub fn read_json_views(path: &Path, week_start: Weekday) -> Result<AllViews, Error> {
+    let json_contents =
+        std::fs::read_to_string(path).with_context(|| "failed to read json views file")?;
+    parse_json_views(&json_contents, week_start)
+}
+
+/// Parses the given JSON definition of views, which maps view names to either a single view or a
+/// list of them (in the same format as `--views-json`).
+pub fn parse_json_views(json: &str, week_start: Weekday) -> Result<AllViews, Error> {
+    let views: HashMap<String, JsonView> =
+        serde_json::from_str(json).with_context(|| "failed to parse json views")?;
+    let views_vec = views
+        .into_iter()
+        .flat_map(|(name, view)| {
+            let vec = match view {
+                JsonView::Single(view) => vec![view],
+                JsonView::Multiple(v) => v,
+            };
+            vec.into_iter().map(move |view| NamedView {
+                name: name.clone(),
+                view,
+            })
+        })
+        .collect();
+
+    organise_views(views_vec, week_start)
+}
+
+/// Organises the given views by their data types, validating them and working out the latest date
+/// among them.
+fn organise_views(views_vec: Vec<NamedView>, week_start: Weekday) -> Result<AllViews, Error> {
+    let mut all_views = AllViews {
+        events: Vec::new(),
+        daily_notes: Vec::new(),
+        tickles: Vec::new(),
+        dates: Vec::new(),
+        waits: Vec::new(),
+        stacks: Vec::new(),
+        tasks: Vec::new(),
+        target_contexts: Vec::new(),
+        eisenhowers: Vec::new(),
+        picks: Vec::new(),
+        boards: Vec::new(),
+        completeds: Vec::new(),
+        velocities: Vec::new(),
+        timelines: Vec::new(),
+        overdues: Vec::new(),
+        inboxes: Vec::new(),
+        clocks: Vec::new(),
+        time_reports: Vec::new(),
+        free_slots: Vec::new(),
+        busies: Vec::new(),
+        travels: Vec::new(),
+        capacities: Vec::new(),
+        searches: Vec::new(),
+        agendas: Vec::new(),
+        weeks: Vec::new(),
+        months: Vec::new(),
+        #[cfg(feature = "goals")]
+        goals: Vec::new(),
+        #[cfg(feature = "goals")]
+        alignments: Vec::new(),
+        #[cfg(feature = "plugins")]
+        plugins: Vec::new(),
 
-            // If we have a last date, update it
-            if let Some(last_date) = last_date {
-                if all_views
-                    .last_date
-                    .is_none_or(|latest_date| latest_date < last_date)
-                {
-                    all_views.last_date = Some(last_date);
-                }
+        last_date: None,
+        ranges: BTreeMap::new(),
+    };
+    for mut named_view in views_vec {
+        named_view.view.default_week_start(week_start);
+        // Validate the view, which will also return the last date in it
+        let last_date = named_view
+            .view
+            .validate()
+            .with_context(|| format!("failed to validate view `{}`", named_view.name))?;
+        all_views.ranges.insert(
+            named_view.name.clone(),
+            DateRange {
+                from: named_view.view.first_date(),
+                until: last_date,
+            },
+        );
+
+        // Add the view to the appropriate vector
+        match named_view.view {
+            View::Events(filter) => all_views.events.push((named_view.name, filter)),
+            View::DailyNotes(filter) => all_views.daily_notes.push((named_view.name, filter)),
+            View::Tickles(filter) => all_views.tickles.push((named_view.name, filter)),
+            View::Dates(filter) => all_views.dates.push((named_view.name, filter)),
+            View::Waits(filter) => all_views.waits.push((named_view.name, filter)),
+            View::Stacks(filter) => all_views.stacks.push((named_view.name, filter)),
+            View::Tasks(filter) => all_views.tasks.push((named_view.name, filter)),
+            View::TargetContexts(filter) => {
+                all_views.target_contexts.push((named_view.name, filter))
             }
+            View::Eisenhower(filter) => all_views.eisenhowers.push((named_view.name, filter)),
+            View::Pick(filter) => all_views.picks.push((named_view.name, filter)),
+            View::Board(filter) => all_views.boards.push((named_view.name, filter)),
+            View::Completed(filter) => all_views.completeds.push((named_view.name, filter)),
+            View::Velocity(filter) => all_views.velocities.push((named_view.name, filter)),
+            View::Timeline(filter) => all_views.timelines.push((named_view.name, filter)),
+            View::Overdue(filter) => all_views.overdues.push((named_view.name, filter)),
+            View::Inbox(filter) => all_views.inboxes.push((named_view.name, filter)),
+            View::Clock(filter) => all_views.clocks.push((named_view.name, filter)),
+            View::TimeReport(filter) => all_views.time_reports.push((named_view.name, filter)),
+            View::FreeSlots(filter) => all_views.free_slots.push((named_view.name, filter)),
+            View::Busy(filter) => all_views.busies.push((named_view.name, filter)),
+            View::Travel(filter) => all_views.travels.push((named_view.name, filter)),
+            View::Capacity(filter) => all_views.capacities.push((named_view.name, filter)),
+            View::Search(filter) => all_views.searches.push((named_view.name, filter)),
+            View::Agenda(filter) => all_views.agendas.push((named_view.name, filter)),
+            View::Week(filter) => all_views.weeks.push((named_view.name, filter)),
+            View::Month(filter) => all_views.months.push((named_view.name, filter)),
+            #[cfg(feature = "goals")]
+            View::Goals(filter) => all_views.goals.push((named_view.name, filter)),
+            #[cfg(feature = "goals")]
+            View::Alignment(filter) => all_views.alignments.push((named_view.name, filter)),
+            #[cfg(feature = "plugins")]
+            View::Plugin(filter) => all_views.plugins.push((named_view.name, filter)),
         }
 
-        // The `Ok(None)` branch was handled in the first section
-        Ok(Some(all_views))
+        // If we have a last date, update it
+        if let Some(last_date) = last_date {
+            if all_views
+                .last_date
+                .is_none_or(|latest_date| latest_date < last_date)
+            {
+                all_views.last_date = Some(last_date);
+            }
+        }
     }
+
+    Ok(all_views)
+}
+
+/// Commands other than producing views, which is what Polaris does if no command is given.
+#[derive(Subcommand, Debug)]
+#[command(rename_all = "snake_case")]
+pub enum Command {
+    /// Inspects the configuration Polaris will run with.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Generates shell completion scripts, which complete contexts, people, and view names from
+    /// those seen on the last run that produced views.
+    #[command(subcommand)]
+    Completions(CompletionsCommand),
+    /// Produces a built-in set of views for a single day (events, scheduled and due tasks,
+    /// tickles, daily notes, and goals if enabled), without needing any views to be specified.
+    Today {
+        /// The date to produce views for, which defaults to the present date.
+        #[arg(short, long)]
+        date: Option<NaiveDate>,
+    },
+    /// Produces a built-in set of views for a weekly review (stale waiting items, stalled stacks,
+    /// fired tickles, items completed over the last week, and deadlines and days over capacity
+    /// in the next fortnight), without needing any views to be specified.
+    Review {
+        /// The date to review from, which defaults to the present date.
+        #[arg(short, long)]
+        date: Option<NaiveDate>,
+        /// The number of days after which a waiting item with nothing to resurface it is
+        /// considered stale.
+        #[arg(long, default_value_t = STALE_WAIT_DAYS)]
+        stale_days: u32,
+    },
+    /// Shows everything worked out about a single action item, including any priority inherited
+    /// from its parent stacks, its computed deadlines, and the repeats of its timestamps, for
+    /// checking why it appears (or doesn't appear) in a view.
+    Show {
+        /// The ID of the item to show.
+        id: String,
+        /// Prints the details as human-readable text, rather than JSON.
+        #[arg(long)]
+        text: bool,
+    },
+    /// Produces a single view and presents its items in a fuzzy finder (`fzf` by default, see
+    /// `picker` in the config file), printing the ID of the one selected. If nothing is selected,
+    /// this exits with code 5.
+    Pick {
+        /// Prints the whole selected item as JSON, rather than just its ID.
+        #[arg(long)]
+        json: bool,
+        /// The view to pick from, as it would be given to `--view`, but without a name (e.g.
+        /// `tasks --context home`).
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, num_args = 1.., required = true)]
+        view: Vec<String>,
+    },
+    /// Runs Polaris as a server, answering HTTP requests until stopped. This serves workload
+    /// metrics for Prometheus at `/metrics`, produces the views given in the body of requests to
+    /// `/query`, and, if views are given, pushes their data to WebSocket clients at `/ws` whenever
+    /// it changes. SIGTERM or SIGINT shut it down cleanly, and its logs go to stdout as JSON lines.
+    #[cfg(feature = "web")]
+    Serve {
+        /// The address to listen on.
+        #[arg(long, env = "POLARIS_LISTEN", default_value = "127.0.0.1:9090")]
+        listen: String,
+        /// A JSON file of views to push to WebSocket clients (in the same format as
+        /// `--views-json`).
+        #[arg(long, env = "POLARIS_SERVE_VIEWS")]
+        views: Option<PathBuf>,
+        /// How often to check for changes to the views pushed to WebSocket clients, in seconds.
+        /// Queries reuse nodes fetched more recently than this. This must be at least one.
+        #[arg(
+            long,
+            env = "POLARIS_REFRESH",
+            default_value_t = 60,
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        refresh: u64,
+    },
+    /// Checks that Starling is reachable and serving action items, exiting with an error if not.
+    /// This is intended for use as a container healthcheck.
+    Healthcheck {
+        /// The number of seconds to wait for Starling to respond before failing.
+        #[arg(long, default_value_t = 5)]
+        timeout: u64,
+    },
+    /// Fetches and processes every action item, reporting every problem found with them (e.g.
+    /// unknown keywords, malformed properties, or stacks with nothing actionable) without
+    /// producing any views. This exits with an error if there are any problems, so it can be used
+    /// as a pre-commit hook.
+    Check,
+    /// Marks the action item with the given ID as done in Starling, giving it the first
+    /// completion keyword and a `CLOSED` timestamp. Items with repeating timestamps will have
+    /// them moved on to their next repeat instead, as in Org mode.
+    Done {
+        /// The ID of the item to mark as done.
+        id: String,
+        /// The completion keyword to use, rather than the first of the completion keywords.
+        #[arg(short, long)]
+        keyword: Option<String>,
+    },
+    /// Moves the scheduled and/or deadline dates of the action item with the given ID in
+    /// Starling. Existing timestamps keep their times and repeaters.
+    Reschedule {
+        /// The ID of the item to reschedule.
+        id: String,
+        /// The new scheduled date for the item.
+        #[arg(short, long)]
+        scheduled: Option<NaiveDate>,
+        /// The new deadline date for the item.
+        #[arg(short, long)]
+        deadline: Option<NaiveDate>,
+    },
+    /// Snoozes the action item with the given ID (usually a tickle or daily note) by moving its
+    /// primary timestamp in Starling to a later date.
+    Snooze {
+        /// The ID of the item to snooze.
+        id: String,
+        /// When to snooze the item until, which can be a date or a number of days or weeks from
+        /// the present date (e.g. `+3d` or `+2w`).
+        until: SnoozeTarget,
+    },
+    /// Tracks time spent on action items with Org-style `CLOCK` entries in their logbooks.
+    #[command(subcommand)]
+    Clock(ClockCommand),
+    /// Captures a new item at the end of the inbox file (`inbox_file` in the configuration file)
+    /// through Starling, printing its ID.
+    Capture {
+        /// The title of the new item.
+        title: String,
+        /// The body of the new item.
+        #[arg(short, long)]
+        body: Option<String>,
+        /// The keyword to give the new item (e.g. `TODO`). Items without keywords can be
+        /// processed later from the inbox.
+        #[arg(short, long)]
+        keyword: Option<String>,
+        /// Contexts (tags) to give the new item.
+        #[arg(short, long)]
+        context: Vec<String>,
+        /// The file to capture into (relative to the Starling root), overriding the configured
+        /// inbox file.
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+}
+
+/// Commands for inspecting the configuration.
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Prints the effective configuration, merged from defaults, the configuration file,
+    /// environment variables, and CLI flags, noting where each value came from.
+    Show,
+}
+
+/// Commands for shell completions.
+#[derive(Subcommand, Debug)]
+pub enum CompletionsCommand {
+    /// Prints the completion script for the given shell (e.g. `source <(polaris completions
+    /// script bash)`).
+    Script {
+        /// The shell to generate the script for.
+        shell: Shell,
+    },
+    /// Prints the cached values of the given kind, one per line. This is used by the completion
+    /// scripts.
+    Values {
+        /// The kind of values to print.
+        kind: CompletionValues,
+    },
+}
+
+/// A shell completion scripts can be generated for.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+#[clap(rename_all = "snake_case")]
+pub enum Shell {
+    /// Bash, through `complete`.
+    Bash,
+    /// Zsh, through its Bash compatibility layer.
+    Zsh,
+    /// Fish.
+    Fish,
+}
+
+/// A kind of value which can be completed from the cache of the last run.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+#[clap(rename_all = "snake_case")]
+pub enum CompletionValues {
+    /// The contexts on tasks.
+    Contexts,
+    /// The names of people.
+    People,
+    /// The names of views.
+    Views,
+}
+
+/// Commands for tracking time.
+#[derive(Subcommand, Debug)]
+pub enum ClockCommand {
+    /// Starts a clock on the action item with the given ID, stopping any clock that's already
+    /// running.
+    In {
+        /// The ID of the item to clock in to.
+        id: String,
+    },
+    /// Stops the clock that's running, if there is one.
+    Out,
 }
 
 /// Options that allow the user to pass views directly, with a JSON file (for more complex
@@ -130,12 +559,13 @@ impl Cli {
 struct ViewOptions {
     /// Every one of these will create a new view (e.g. `--view "my_view events -u 2025-01-01"`).
     /// Within each argument, a separate CLI parse occurs, see help by running `polaris
-    /// --help-views`
-    #[arg(short, long = "view", num_args=1.., value_parser)]
+    /// --help-views`. Only a single view can be given through the environment, so use
+    /// `POLARIS_VIEWS_JSON` for more.
+    #[arg(short, long = "view", env = "POLARIS_VIEW", num_args=1.., value_parser)]
     views: Vec<NamedView>,
 
     /// The path to a JSON file declaring the views to use as a map of view names to view options
-    #[arg(short = 'j', long = "views-json")]
+    #[arg(short = 'j', long = "views-json", env = "POLARIS_VIEWS_JSON")]
     views_json: Option<PathBuf>,
 
     /// Produces a help message about how to to specify views on the CLI (you can add a particular
@@ -145,8 +575,9 @@ struct ViewOptions {
 }
 
 /// The encoding to use for the output of the CLI.
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Deserialize, Clone, Debug)]
 #[clap(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum Encoding {
     /// JSON, the default encoding.
     Json,
@@ -179,11 +610,29 @@ impl FromStr for RepeatBuffer {
         let duration = match s.chars().last() {
             Some('w') => chrono::Duration::weeks(s[..s.len() - 1].parse()?),
             Some('d') => chrono::Duration::days(s[..s.len() - 1].parse()?),
+            // No buffer at all doesn't need a unit
+            _ if s == "0" => chrono::Duration::zero(),
             _ => bail!("invalid repeat buffer format, expected a number followed by 'w', 'd', 'h', 'm', or 's'"),
         };
         Ok(RepeatBuffer(duration))
     }
 }
+impl<'de> Deserialize<'de> for RepeatBuffer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+impl fmt::Display for RepeatBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = self.0.num_days();
+        if days % 7 == 0 && days != 0 {
+            write!(f, "{}w", days / 7)
+        } else {
+            write!(f, "{days}d")
+        }
+    }
+}
 impl Deref for RepeatBuffer {
     type Target = chrono::Duration;
 
@@ -192,6 +641,70 @@ impl Deref for RepeatBuffer {
     }
 }
 
+/// A date to snooze an item until, given either as a date or as an amount of time from the present
+/// date (e.g. `+3d` or `+2w`, in the same format as a [`RepeatBuffer`]).
+#[derive(Clone, Copy, Debug)]
+pub enum SnoozeTarget {
+    Date(NaiveDate),
+    Offset(chrono::Duration),
+}
+impl SnoozeTarget {
+    /// Works out the date this refers to, if the present date is `today`.
+    pub fn resolve(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Date(date) => date,
+            Self::Offset(offset) => today + offset,
+        }
+    }
+}
+impl FromStr for SnoozeTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('+') {
+            Some(offset) => Ok(Self::Offset(*offset.parse::<RepeatBuffer>()?)),
+            None => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(Self::Date)
+                .with_context(|| {
+                    format!("invalid snooze target `{s}`, expected a date or `+<n>d`")
+                }),
+        }
+    }
+}
+
+/// A time of day given as `HH:MM` (or `HH:MM:SS`), used for things like the bounds of the working
+/// day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ClockTime(pub NaiveTime);
+impl FromStr for ClockTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NaiveTime::parse_from_str(s, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M:%S"))
+            .map(ClockTime)
+            .with_context(|| format!("invalid time `{s}`, expected the format `HH:MM`"))
+    }
+}
+impl<'de> Deserialize<'de> for ClockTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format("%H:%M"))
+    }
+}
+impl Deref for ClockTime {
+    type Target = NaiveTime;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 /// A view with a name, which will be parsed from what is effectively a sub-CLI inside the
 /// `-v/--view` argument.
 #[derive(Parser, Clone, Debug)]
@@ -0,0 +1,224 @@
+//! Shell completion scripts for Polaris. Contexts, people, and view names can't be known ahead of
+//! time, so each run that produces views caches the ones it saw, and the scripts call back into
+//! Polaris to read that cache when completing them.
+
+use crate::cli::{Cli, CompletionValues, Shell};
+use crate::config::cache_dir;
+use crate::parse::ActionItem;
+use crate::views::View;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// The values seen on the last run that produced views, for completing on the command line.
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+struct CompletionCache {
+    /// The contexts on all the tasks.
+    contexts: BTreeSet<String>,
+    /// The names of all the people on tasks and other items.
+    people: BTreeSet<String>,
+    /// The names of every view that's been produced (these are kept from earlier runs too, since
+    /// each run only has the views it was asked for).
+    views: BTreeSet<String>,
+}
+
+/// Updates the completion cache with the contexts and people in the given action items, and the
+/// given view names. The cache is only written if that changes it.
+pub fn update_cache<'a>(
+    action_items: &HashMap<Uuid, ActionItem>,
+    view_names: impl Iterator<Item = &'a String>,
+) -> Result<()> {
+    let Some(path) = cache_path() else {
+        return Ok(());
+    };
+    let old_contents = std::fs::read_to_string(&path).ok();
+    let mut cache: CompletionCache = old_contents
+        .as_deref()
+        .and_then(|contents| serde_json::from_str(contents).ok())
+        .unwrap_or_default();
+    cache.contexts.clear();
+    cache.people.clear();
+    for item in action_items.values() {
+        match item {
+            ActionItem::Task {
+                contexts, people, ..
+            } => {
+                cache.contexts.extend(contexts.iter().cloned());
+                cache
+                    .people
+                    .extend(people.iter().map(|(_, name)| name.clone()));
+            }
+            ActionItem::None { people, .. } => {
+                cache
+                    .people
+                    .extend(people.iter().map(|(_, name)| name.clone()));
+            }
+            _ => {}
+        }
+    }
+    cache.views.extend(view_names.cloned());
+    let contents = serde_json::to_string(&cache)?;
+    if old_contents.as_ref() == Some(&contents) {
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create cache directory {}", dir.display()))?;
+    }
+    std::fs::write(&path, contents)
+        .with_context(|| format!("failed to write completion cache {}", path.display()))
+}
+
+/// Gets the cached values of the given kind, one per line. If nothing's been cached yet, this
+/// will be empty.
+pub fn cached_values(kind: CompletionValues) -> String {
+    let cache = cache_path()
+        .map(|path| read_cache(&path))
+        .unwrap_or_default();
+    let values = match kind {
+        CompletionValues::Contexts => cache.contexts,
+        CompletionValues::People => cache.people,
+        CompletionValues::Views => cache.views,
+    };
+    values.into_iter().map(|value| value + "\n").collect()
+}
+
+/// Generates the completion script for the given shell.
+pub fn completion_script(shell: Shell) -> String {
+    let commands = Cli::command()
+        .get_subcommands()
+        .map(|command| command.get_name().to_string())
+        .collect::<Vec<_>>();
+    let view_types = View::augment_subcommands(clap::Command::new("view"))
+        .get_subcommands()
+        .map(|command| command.get_name().to_string())
+        .collect::<Vec<_>>();
+
+    match shell {
+        Shell::Bash => bash_script(&commands, &view_types),
+        // Zsh can use the Bash script directly through its compatibility layer
+        Shell::Zsh => format!(
+            "autoload -U +X bashcompinit && bashcompinit\n{}",
+            bash_script(&commands, &view_types)
+        ),
+        Shell::Fish => {
+            let commands = commands.join(" ");
+            let view_types = view_types.join(" ");
+            format!(
+                r#"# Completes inside a view given to `--view` (e.g. `"name tasks --contexts ho`)
+function __polaris_view_spec
+    set -l spec (string trim --left --chars '\'"' -- (commandline -ct))
+    set -l words (string split ' ' -- $spec)
+    if test (count $words) -lt 2
+        polaris completions values views 2>/dev/null
+        return
+    end
+    set -l head (string join ' ' -- $words[1..-2])
+    set -l kind
+    switch $words[-2]
+        case -c --contexts
+            set kind contexts
+        case -p --people
+            set kind people
+        case '*'
+            if test (count $words) -eq 2
+                for view_type in {view_types}
+                    echo "$head $view_type"
+                end
+            end
+            return
+    end
+    for value in (polaris completions values $kind 2>/dev/null)
+        echo "$head $value"
+    end
+end
+complete -c polaris -n __fish_use_subcommand -f -a '{commands}'
+complete -c polaris -n '__fish_seen_subcommand_from pick' -f -a '{view_types}'
+complete -c polaris -n '__fish_seen_subcommand_from pick' -s c -l contexts -x -a '(polaris completions values contexts 2>/dev/null)'
+complete -c polaris -n '__fish_seen_subcommand_from pick' -s p -l people -x -a '(polaris completions values people 2>/dev/null)'
+complete -c polaris -s v -l view -x -a '(__polaris_view_spec)'
+"#
+            )
+        }
+    }
+}
+
+/// Generates the Bash completion script, completing the given commands and view types.
+fn bash_script(commands: &[String], view_types: &[String]) -> String {
+    // Words are split on newlines in the script, so they need to be separated by them here too
+    let commands = commands.join("\n");
+    let view_types = view_types.join("\n");
+    format!(
+        r#"# Completes inside a view given to `--view` (e.g. `"name tasks --contexts ho`)
+_polaris_view_spec() {{
+    local spec="${{cur#[\"\']}}"
+    local quote="${{cur%"$spec"}}"
+    if [[ "$spec" != *" "* ]]; then
+        COMPREPLY=($(compgen -W "$(polaris completions values views 2>/dev/null)" -- "$cur"))
+        return
+    fi
+    local head="${{spec% *}}"
+    local word="${{spec##* }}"
+    local values
+    case "${{head##* }}" in
+        -c|--contexts) values="$(polaris completions values contexts 2>/dev/null)";;
+        -p|--people) values="$(polaris completions values people 2>/dev/null)";;
+        *)
+            # Only the second word is the view type
+            [[ "$head" == *" "* ]] && return
+            values="{view_types}";;
+    esac
+    COMPREPLY=($(compgen -P "$quote$head " -W "$values" -- "$word"))
+}}
+_polaris() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    # People's names can have spaces in them
+    local IFS=$'\n'
+    case "$prev" in
+        -v|--view)
+            _polaris_view_spec
+            return;;
+        pick)
+            COMPREPLY=($(compgen -W "{view_types}" -- "$cur"))
+            return;;
+    esac
+    # Views given to `pick` are split into separate words, so their options can be completed
+    # directly
+    if [[ "${{COMP_WORDS[1]}}" == pick ]]; then
+        case "$prev" in
+            -c|--contexts)
+                COMPREPLY=($(compgen -W "$(polaris completions values contexts 2>/dev/null)" -- "$cur"))
+                return;;
+            -p|--people)
+                COMPREPLY=($(compgen -W "$(polaris completions values people 2>/dev/null)" -- "$cur"))
+                return;;
+        esac
+    fi
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W "{commands}" -- "$cur"))
+    fi
+}}
+complete -o default -F _polaris polaris
+"#
+    )
+}
+
+/// Reads the completion cache at the given path. The cache is only ever a convenience, so if it
+/// doesn't exist or can't be read, this will just be empty.
+fn read_cache(path: &Path) -> CompletionCache {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Gets the location of the completion cache.
+fn cache_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("completions.json"))
+}
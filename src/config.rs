@@ -0,0 +1,658 @@
+//! Configuration for Polaris, which is merged from built-in defaults, a JSON configuration file,
+//! environment variables, and CLI flags (in increasing order of precedence).
+
+use crate::cli::{Cli, ClockTime, Encoding, RepeatBuffer};
+#[cfg(feature = "mqtt")]
+use crate::mqtt::MqttConfig;
+#[cfg(feature = "notifications")]
+use crate::notify::NotificationTarget;
+#[cfg(feature = "goals")]
+use crate::parse::{GoalType, PathTemplate};
+use crate::parse::{LocalDir, Node, NodeSource, Starling, MAX_RETRIES};
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use clap::{parser::ValueSource, ArgMatches, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, fmt::Write, path::PathBuf, time::Duration};
+use uuid::Uuid;
+
+/// The options which can be set in a configuration file. Everything here is optional, and
+/// anything not set will fall back to the CLI defaults. Unknown keys are rejected, so a typo
+/// doesn't silently leave an option at its default.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    done_keywords: Option<Vec<String>>,
+    starling: Option<String>,
+    starling_token: Option<Option<String>>,
+    starling_timeout: Option<u64>,
+    starling_retries: Option<u32>,
+    #[cfg(feature = "tls")]
+    starling_ca: Option<Option<PathBuf>>,
+    #[cfg(feature = "tls")]
+    starling_insecure: Option<bool>,
+    source: Option<String>,
+    encoding: Option<Encoding>,
+    lenient: Option<bool>,
+    fail_if_empty: Option<bool>,
+    changed_since: Option<Option<PathBuf>>,
+    repeat_buffer: Option<RepeatBuffer>,
+    repeat_buffers: RepeatBuffersFile,
+    week_start: Option<Weekday>,
+    travel_times: Option<TravelTimes>,
+    working_hours: Option<WorkingHours>,
+    #[cfg(feature = "ical")]
+    holidays_calendar: Option<PathBuf>,
+    #[cfg(feature = "ical")]
+    calendars: Option<Vec<String>>,
+    #[cfg(feature = "plugins")]
+    plugins: Option<BTreeMap<String, String>>,
+    daily_capacity: Option<f64>,
+    default_advance: Option<RepeatBuffer>,
+    areas: Option<Vec<String>>,
+    inbox_file: Option<PathBuf>,
+    aliases: Option<BTreeMap<String, Uuid>>,
+    picker: Option<String>,
+    #[cfg(feature = "notifications")]
+    notifications: Option<BTreeMap<String, Vec<NotificationTarget>>>,
+    #[cfg(feature = "mqtt")]
+    mqtt: Option<MqttConfig>,
+    #[cfg(feature = "goals")]
+    journal_template: Option<PathTemplate>,
+    #[cfg(feature = "goals")]
+    goals: Option<Vec<GoalType>>,
+}
+
+/// Repeat buffers for particular types of items in the configuration file, which override the
+/// general `repeat_buffer`.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct RepeatBuffersFile {
+    events: Option<RepeatBuffer>,
+    daily_notes: Option<RepeatBuffer>,
+    tickles: Option<RepeatBuffer>,
+    person_dates: Option<RepeatBuffer>,
+    tasks: Option<RepeatBuffer>,
+}
+
+/// The repeat buffer to use for each type of item. Different types need very different buffers
+/// (e.g. person dates need to be expanded far enough to catch their notification dates, whereas
+/// events are only ever needed up to the last date in the views).
+#[derive(Clone, Debug)]
+pub struct RepeatBuffers {
+    pub events: RepeatBuffer,
+    pub daily_notes: RepeatBuffer,
+    pub tickles: RepeatBuffer,
+    pub person_dates: RepeatBuffer,
+    /// The buffer for tasks, which also covers stacks and waiting items, because the repeats of
+    /// tasks are matched up with those of their parent stacks.
+    pub tasks: RepeatBuffer,
+}
+impl RepeatBuffers {
+    /// Gets the repeat buffer for the given node, based on the type of item it will become.
+    pub fn for_node(&self, node: &Node) -> &RepeatBuffer {
+        if node.parent_tags.contains("person_dates") {
+            return &self.person_dates;
+        } else if node.parent_tags.contains("tickles") {
+            return &self.tickles;
+        }
+
+        match node.metadata.as_ref().unwrap().keyword.as_deref() {
+            Some("NOTE") => &self.daily_notes,
+            Some(_) => &self.tasks,
+            None => &self.events,
+        }
+    }
+
+    /// Gets the dates up to which each type of item will be expanded, given the date the buffers
+    /// are measured from.
+    pub fn horizons(&self, last_date: NaiveDate) -> ExpansionHorizons {
+        ExpansionHorizons {
+            events: last_date + *self.events,
+            daily_notes: last_date + *self.daily_notes,
+            tickles: last_date + *self.tickles,
+            person_dates: last_date + *self.person_dates,
+            tasks: last_date + *self.tasks,
+        }
+    }
+}
+
+/// How long it takes to travel between locations, which is used to check there's enough time
+/// between consecutive events in different places. This can only be set in the configuration file.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct TravelTimes {
+    /// The number of minutes to assume for travel between two different locations which aren't
+    /// covered by `routes`. If this isn't set, only the listed routes will be checked.
+    pub default: Option<u32>,
+    /// Travel times between particular pairs of locations, which apply in both directions.
+    pub routes: Vec<TravelRoute>,
+}
+impl TravelTimes {
+    /// Gets the number of minutes it takes to travel between the given locations, if it's known.
+    /// Staying in the same location never needs any travel.
+    pub fn between(&self, from: &str, to: &str) -> Option<u32> {
+        if from == to {
+            return None;
+        }
+        self.routes
+            .iter()
+            .find(|route| {
+                (route.from == from && route.to == to) || (route.from == to && route.to == from)
+            })
+            .map(|route| route.minutes)
+            .or(self.default)
+    }
+}
+
+/// The time it takes to travel between two locations (as they're written in `LOCATION`
+/// properties).
+#[derive(Deserialize, Clone, Debug)]
+pub struct TravelRoute {
+    pub from: String,
+    pub to: String,
+    pub minutes: u32,
+}
+
+/// The hours the user works on each day of the week. Days without hours are treated as
+/// non-working days.
+#[derive(Deserialize, Clone, Debug)]
+pub struct WorkingHours {
+    mon: Option<DayHours>,
+    tue: Option<DayHours>,
+    wed: Option<DayHours>,
+    thu: Option<DayHours>,
+    fri: Option<DayHours>,
+    sat: Option<DayHours>,
+    sun: Option<DayHours>,
+}
+impl WorkingHours {
+    /// Gets the working hours on the given day of the week, if it's a working day.
+    pub fn on(&self, weekday: Weekday) -> Option<DayHours> {
+        match weekday {
+            Weekday::Mon => self.mon,
+            Weekday::Tue => self.tue,
+            Weekday::Wed => self.wed,
+            Weekday::Thu => self.thu,
+            Weekday::Fri => self.fri,
+            Weekday::Sat => self.sat,
+            Weekday::Sun => self.sun,
+        }
+    }
+
+    /// Gets the time deadlines on the given date should fall at if they don't have one of their
+    /// own, which is the end of work on working days, and the end of the day otherwise.
+    pub fn deadline_time(&self, date: NaiveDate) -> NaiveTime {
+        self.on(date.weekday())
+            .map_or(NaiveTime::from_hms_opt(23, 59, 59).unwrap(), |hours| {
+                *hours.end
+            })
+    }
+
+    /// Gets the number of minutes of work on the given date, which is zero on non-working days.
+    pub fn minutes_on(&self, date: NaiveDate) -> u32 {
+        self.on(date.weekday())
+            .map_or(0, |hours| (*hours.end - *hours.start).num_minutes() as u32)
+    }
+
+    /// Makes sure the hours on every day end after they start.
+    fn validate(&self) -> Result<()> {
+        for (weekday, hours) in self.days() {
+            if hours.is_some_and(|hours| hours.end <= hours.start) {
+                bail!("working hours on {weekday} must end after they start");
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the hours on each day of the week, starting from Monday.
+    fn days(&self) -> impl Iterator<Item = (Weekday, Option<DayHours>)> + '_ {
+        std::iter::successors(Some(Weekday::Mon), |day| Some(day.succ()))
+            .take(7)
+            .map(|day| (day, self.on(day)))
+    }
+}
+
+/// The start and end of work on a single day.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct DayHours {
+    pub start: ClockTime,
+    pub end: ClockTime,
+}
+
+/// The date up to which repeating timestamps were expanded for each type of item. Beyond these,
+/// the absence of an item only means its repeats weren't expanded that far, not that it doesn't
+/// occur.
+#[derive(Serialize, Clone, Debug)]
+pub struct ExpansionHorizons {
+    pub events: NaiveDate,
+    pub daily_notes: NaiveDate,
+    pub tickles: NaiveDate,
+    pub person_dates: NaiveDate,
+    /// The horizon for tasks, which also covers stacks and waiting items.
+    pub tasks: NaiveDate,
+}
+
+/// Where a configuration value came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    Env,
+    Cli,
+}
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File => write!(f, "config file"),
+            Self::Env => write!(f, "environment"),
+            Self::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// The fully-resolved configuration Polaris will run with.
+#[derive(Debug)]
+pub struct Config {
+    /// Completion keywords to recognise and exclude from the action items.
+    pub done_keywords: Vec<String>,
+    /// Where to read action items from: a Starling instance (and how to authenticate with it), or
+    /// a directory of files.
+    pub backend: Box<dyn NodeSource>,
+    /// Which encoding to output.
+    pub encoding: Encoding,
+    /// Whether to skip items which can't be processed, rather than failing entirely.
+    pub lenient: bool,
+    /// Whether to exit with a distinct code if every view is empty.
+    pub fail_if_empty: bool,
+    /// The state file to compare against and update, if only new and changed items should be
+    /// emitted.
+    pub changed_since: Option<PathBuf>,
+    /// The amount of time to expand repeating timestamps for past the last date in the views, for
+    /// each type of item.
+    pub repeat_buffers: RepeatBuffers,
+    /// The day of the week weeks start on.
+    pub week_start: Weekday,
+    /// How long it takes to travel between the locations of events.
+    pub travel_times: TravelTimes,
+    /// The hours the user works on each day of the week, if they've been set.
+    pub working_hours: Option<WorkingHours>,
+    /// An ICS calendar file to read holidays from, in addition to those in Starling.
+    #[cfg(feature = "ical")]
+    pub holidays_calendar: Option<PathBuf>,
+    /// ICS calendars (files or URLs) whose events are shown alongside those in Starling.
+    #[cfg(feature = "ical")]
+    pub calendars: Vec<String>,
+    /// The commands of plugins which produce views of their own, keyed by view name (see
+    /// [`crate::plugins`]).
+    #[cfg(feature = "plugins")]
+    pub plugins: BTreeMap<String, String>,
+    /// The number of hours available for tasks on each working day, if it's been set.
+    pub daily_capacity: Option<f64>,
+    /// How long before person dates without an `ADVANCE` property to be notified of them, if
+    /// they shouldn't need one.
+    pub default_advance: Option<RepeatBuffer>,
+    /// The tags which mark areas of focus (e.g. `work`, `family`, or `health`), from the most
+    /// specific to the least.
+    pub areas: Vec<String>,
+    /// The file (relative to the Starling root) new items are captured into, if it's been set.
+    pub inbox_file: Option<PathBuf>,
+    /// Names which can be used in place of the IDs of particular nodes on the command line.
+    pub aliases: BTreeMap<String, Uuid>,
+    /// The fuzzy finder command `polaris pick` presents items through.
+    pub picker: String,
+    /// Where to send notifications for the items in each view, by view name.
+    #[cfg(feature = "notifications")]
+    pub notifications: BTreeMap<String, Vec<NotificationTarget>>,
+    /// The broker to publish views to, and which topics to publish them under, if it's been set.
+    #[cfg(feature = "mqtt")]
+    pub mqtt: Option<MqttConfig>,
+    /// The template for the paths of journal files goals are read from.
+    #[cfg(feature = "goals")]
+    pub journal_template: PathTemplate,
+    /// The types of goals to extract, and where each is found.
+    #[cfg(feature = "goals")]
+    pub goals: Vec<GoalType>,
+
+    /// The configuration file values were read from, if there was one.
+    file_path: Option<PathBuf>,
+    /// The name, displayed value, and source of each option, for `polaris config show`.
+    entries: Vec<(&'static str, String, Source)>,
+}
+impl Config {
+    /// Resolves the configuration from the given parsed CLI arguments (and the raw matches they
+    /// came from, which tell us which values were explicitly provided). This will read the
+    /// configuration file given on the CLI, or the one in the default location if it exists.
+    pub fn resolve(cli: &Cli, matches: &ArgMatches) -> Result<Self> {
+        // An explicitly-given file has to exist, but the default one is optional
+        let file_path = match &cli.config {
+            Some(path) => Some(path.clone()),
+            None => default_config_path().filter(|path| path.exists()),
+        };
+        let mut file: ConfigFile = match &file_path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read config file {}", path.display()))?;
+                serde_json::from_str(&contents)
+                    .with_context(|| format!("failed to parse config file {}", path.display()))?
+            }
+            None => ConfigFile::default(),
+        };
+
+        let mut entries = Vec::new();
+        // A value given explicitly (on the command line or through the environment) wins, then
+        // anything in the configuration file, then the CLI's default
+        macro_rules! resolve {
+            ($cli_field:ident, $file_field:ident, |$v:ident| $display:expr) => {{
+                let source = match matches.value_source(stringify!($cli_field)) {
+                    Some(ValueSource::CommandLine) => Source::Cli,
+                    Some(ValueSource::EnvVariable) => Source::Env,
+                    _ if file.$file_field.is_some() => Source::File,
+                    _ => Source::Default,
+                };
+                let value = match source {
+                    Source::File => file.$file_field.take().unwrap(),
+                    _ => cli.$cli_field.clone(),
+                };
+                let display = {
+                    let $v = &value;
+                    $display
+                };
+                entries.push((stringify!($file_field), display, source));
+                value
+            }};
+        }
+
+        let done_keywords = resolve!(done_keywords, done_keywords, |v| v.join(", "));
+        let starling_address = resolve!(starling_address, starling, |v| v.clone());
+        // The token is a secret, so only whether there is one gets shown
+        let starling_token = resolve!(starling_token, starling_token, |v| v
+            .as_ref()
+            .map_or_else(|| "none".to_string(), |_| "(hidden)".to_string()));
+        let starling_timeout = resolve!(starling_timeout, starling_timeout, |v| format!("{v}s"));
+        let starling_retries = resolve!(starling_retries, starling_retries, |v| v.to_string());
+        if starling_retries > MAX_RETRIES {
+            bail!("starling requests can be retried at most {MAX_RETRIES} times");
+        }
+        let starling = Starling::new(
+            starling_address,
+            starling_token,
+            Duration::from_secs(starling_timeout),
+            starling_retries,
+        )?;
+        #[cfg(feature = "tls")]
+        let starling = {
+            let starling_ca = resolve!(starling_ca, starling_ca, |v| v
+                .as_ref()
+                .map_or_else(|| "none".to_string(), |path| path.display().to_string()));
+            let starling_insecure =
+                resolve!(starling_insecure, starling_insecure, |v| v.to_string());
+            starling.with_tls(starling_ca.as_deref(), starling_insecure)?
+        };
+        let source = resolve!(source, source, |v| v.clone());
+        let backend: Box<dyn NodeSource> = if source == "starling" {
+            Box::new(starling)
+        } else if let Some(path) = source.strip_prefix("dir:") {
+            Box::new(LocalDir {
+                path: PathBuf::from(path),
+                done_keywords: done_keywords.clone(),
+            })
+        } else {
+            bail!("invalid source `{source}`, expected `starling` or `dir:<path>`");
+        };
+        let encoding = resolve!(encoding, encoding, |v| v
+            .to_possible_value()
+            .unwrap()
+            .get_name()
+            .to_string());
+        let lenient = resolve!(lenient, lenient, |v| v.to_string());
+        let fail_if_empty = resolve!(fail_if_empty, fail_if_empty, |v| v.to_string());
+        let changed_since = resolve!(changed_since, changed_since, |v| v
+            .as_ref()
+            .map_or_else(|| "none".to_string(), |path| path.display().to_string()));
+
+        // An explicitly-given repeat buffer overrides everything, otherwise type-specific buffers
+        // in the configuration file override the general one
+        let repeat_buffer_source = match matches.value_source("repeat_buffer") {
+            Some(ValueSource::CommandLine) => Some(Source::Cli),
+            Some(ValueSource::EnvVariable) => Some(Source::Env),
+            _ => None,
+        };
+        let repeat_buffer = resolve!(repeat_buffer, repeat_buffer, |v| v.to_string());
+        let general_source = entries.last().unwrap().2;
+        macro_rules! resolve_buffer {
+            ($field:ident) => {{
+                let (value, source) =
+                    match (repeat_buffer_source, file.repeat_buffers.$field.take()) {
+                        (Some(source), _) => (repeat_buffer.clone(), source),
+                        (None, Some(value)) => (value, Source::File),
+                        (None, None) => (repeat_buffer.clone(), general_source),
+                    };
+                entries.push((
+                    concat!("repeat_buffers.", stringify!($field)),
+                    value.to_string(),
+                    source,
+                ));
+                value
+            }};
+        }
+        let repeat_buffers = RepeatBuffers {
+            events: resolve_buffer!(events),
+            daily_notes: resolve_buffer!(daily_notes),
+            tickles: resolve_buffer!(tickles),
+            person_dates: resolve_buffer!(person_dates),
+            tasks: resolve_buffer!(tasks),
+        };
+        let week_start = resolve!(week_start, week_start, |v| v.to_string());
+
+        // Travel times have no CLI equivalent, so they can only come from the file
+        let travel_times_source = match file.travel_times {
+            Some(_) => Source::File,
+            None => Source::Default,
+        };
+        let travel_times = file.travel_times.take().unwrap_or_default();
+        entries.push((
+            "travel_times.default",
+            travel_times
+                .default
+                .map_or_else(|| "none".to_string(), |minutes| format!("{minutes}m")),
+            travel_times_source,
+        ));
+        entries.push((
+            "travel_times.routes",
+            format!("{} route(s)", travel_times.routes.len()),
+            travel_times_source,
+        ));
+
+        // Everything else is similarly only set in the file, so it either comes from there or is
+        // left unset (shown as the given default)
+        macro_rules! file_only {
+            ($field:ident, $default:expr, |$v:ident| $display:expr) => {{
+                let value = file.$field.take();
+                let (display, source) = match &value {
+                    Some($v) => ($display, Source::File),
+                    None => ($default.to_string(), Source::Default),
+                };
+                entries.push((stringify!($field), display, source));
+                value
+            }};
+        }
+
+        let working_hours = file_only!(working_hours, "none", |v| v
+            .days()
+            .filter_map(|(day, hours)| {
+                hours.map(|hours| format!("{day} {}-{}", hours.start, hours.end))
+            })
+            .collect::<Vec<_>>()
+            .join(", "));
+        if let Some(working_hours) = &working_hours {
+            working_hours.validate()?;
+        }
+        #[cfg(feature = "ical")]
+        let holidays_calendar = file_only!(holidays_calendar, "none", |v| v.display().to_string());
+        #[cfg(feature = "ical")]
+        let calendars = file_only!(calendars, "none", |v| v.join(", ")).unwrap_or_default();
+        // Without `tls`, there's nothing to fetch HTTPS calendars with
+        #[cfg(all(feature = "ical", not(feature = "tls")))]
+        if let Some(url) = calendars.iter().find(|url| url.starts_with("https://")) {
+            bail!("fetching calendar {url} over https requires the `tls` feature");
+        }
+        #[cfg(feature = "plugins")]
+        let plugins = file_only!(plugins, "none", |v| v
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", "))
+        .unwrap_or_default();
+        #[cfg(feature = "goals")]
+        let goals = {
+            let goals = file_only!(goals, "daily and weekly journal headings", |v| v
+                .iter()
+                .map(|ty| ty.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "));
+            for ty in goals.iter().flatten() {
+                ty.validate()?;
+            }
+            goals.unwrap_or_else(|| GoalType::defaults(week_start))
+        };
+        let default_advance = file_only!(default_advance, "none", |v| v.to_string());
+        let daily_capacity = file_only!(daily_capacity, "none", |v| format!("{v}h"));
+        if daily_capacity.is_some_and(|hours| !(0.0..=24.0).contains(&hours)) {
+            bail!("daily capacity must be between 0 and 24 hours");
+        }
+        let areas = file_only!(areas, "none", |v| v.join(", ")).unwrap_or_default();
+        let inbox_file = file_only!(inbox_file, "none", |v| v.display().to_string());
+        let aliases = file_only!(aliases, "none", |v| v
+            .iter()
+            .map(|(alias, id)| format!("{alias}={id}"))
+            .collect::<Vec<_>>()
+            .join(", "))
+        .unwrap_or_default();
+        let picker = file_only!(picker, "fzf", |v| v.clone()).unwrap_or_else(|| "fzf".to_string());
+        #[cfg(feature = "notifications")]
+        let notifications = file_only!(notifications, "none", |v| v
+            .iter()
+            .map(|(view, targets)| {
+                let targets = targets.iter().map(ToString::to_string);
+                format!("{view} -> {}", targets.collect::<Vec<_>>().join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("; "));
+        #[cfg(feature = "mqtt")]
+        let mqtt = file_only!(mqtt, "none", |v| {
+            let topics = v
+                .topics
+                .iter()
+                .map(|(view, topic)| format!("{view} -> {topic}"));
+            format!("{} ({})", v.broker, topics.collect::<Vec<_>>().join(", "))
+        });
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &mqtt {
+            mqtt.validate()?;
+        }
+
+        Ok(Self {
+            done_keywords,
+            backend,
+            encoding,
+            lenient,
+            fail_if_empty,
+            changed_since,
+            repeat_buffers,
+            week_start,
+            travel_times,
+            working_hours,
+            #[cfg(feature = "ical")]
+            holidays_calendar,
+            #[cfg(feature = "ical")]
+            calendars,
+            #[cfg(feature = "plugins")]
+            plugins,
+            daily_capacity,
+            default_advance,
+            areas,
+            inbox_file,
+            aliases,
+            picker,
+            #[cfg(feature = "notifications")]
+            notifications: notifications.unwrap_or_default(),
+            #[cfg(feature = "mqtt")]
+            mqtt,
+            #[cfg(feature = "goals")]
+            journal_template: resolve!(journal_template, journal_template, |v| v.to_string()),
+            #[cfg(feature = "goals")]
+            goals,
+
+            file_path,
+            entries,
+        })
+    }
+
+    /// Gets the time untimed deadlines on the given date fall at: the end of the working day, if
+    /// working hours have been set, or otherwise the end of the day itself.
+    pub fn deadline_time(&self, date: NaiveDate) -> NaiveTime {
+        match &self.working_hours {
+            Some(working_hours) => working_hours.deadline_time(date),
+            None => NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+        }
+    }
+
+    /// Gets the minutes available for tasks on the given date, which is nothing on holidays and
+    /// non-working days. On working days, it's the given number of hours if there is one, or the
+    /// configured daily capacity, or otherwise the length of the working day (defaulting to eight
+    /// hours).
+    pub fn capacity_minutes(&self, date: NaiveDate, holiday: bool, hours: Option<f64>) -> u32 {
+        let working_minutes = match &self.working_hours {
+            Some(working_hours) => working_hours.minutes_on(date),
+            None => 8 * 60,
+        };
+        if holiday || working_minutes == 0 {
+            return 0;
+        }
+        match hours.or(self.daily_capacity) {
+            Some(hours) => (hours * 60.0).round() as u32,
+            None => working_minutes,
+        }
+    }
+
+    /// Renders this configuration for display, annotating each value with where it came from.
+    pub fn show(&self) -> String {
+        let mut out = match &self.file_path {
+            Some(path) => format!("# config file: {}\n", path.display()),
+            None => "# no config file\n".to_string(),
+        };
+        let width = self
+            .entries
+            .iter()
+            .map(|(name, _, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        for (name, value, source) in &self.entries {
+            writeln!(out, "{name:width$} = {value}  # from {source}").unwrap();
+        }
+
+        out
+    }
+}
+
+/// Gets the directory Polaris keeps its caches in, `polaris` in the platform's cache directory.
+/// This doesn't check whether or not it exists.
+pub fn cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .map(|dir| dir.join("polaris"))
+}
+
+/// Gets the default location of the configuration file, `polaris/config.json` in the platform's
+/// configuration directory. This doesn't check whether or not the file exists.
+fn default_config_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .map(|dir| dir.join("polaris").join("config.json"))
+}
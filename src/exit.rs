@@ -0,0 +1,52 @@
+//! The exit codes Polaris uses, so wrapper scripts can react to different kinds of failure without
+//! parsing error messages:
+//!
+//! - `0`: success
+//! - `1`: any error not covered below (e.g. a bad configuration file)
+//! - `2`: invalid command-line arguments
+//! - `3`: Starling couldn't be reached, or responded with an error
+//! - `4`: some action items were invalid
+//! - `5`: every view was empty (only if `--fail-if-empty` is given), or nothing was picked in
+//!   `polaris pick`
+
+use std::fmt;
+use std::process::ExitCode;
+
+/// The exit code for when every view was empty, and the user asked to be told about that.
+pub const EMPTY_EXIT_CODE: u8 = 5;
+
+/// A kind of failure with its own exit code. These are attached to errors as context, so the
+/// exit code can be found from any error that comes back to `main`.
+#[derive(Clone, Copy, Debug)]
+pub enum Failure {
+    /// Starling couldn't be reached, or responded with an error.
+    Starling,
+    /// Some of the action items were invalid.
+    InvalidData,
+}
+impl Failure {
+    /// Gets the exit code for this kind of failure.
+    fn code(self) -> u8 {
+        match self {
+            Self::Starling => 3,
+            Self::InvalidData => 4,
+        }
+    }
+}
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Starling => write!(f, "failed to communicate with starling"),
+            Self::InvalidData => write!(f, "some action items are invalid"),
+        }
+    }
+}
+
+/// Gets the exit code for the given error, which is the one for the kind of failure attached to
+/// it if there is one, or `1` otherwise.
+pub fn exit_code(err: &anyhow::Error) -> ExitCode {
+    ExitCode::from(
+        err.downcast_ref::<Failure>()
+            .map_or(1, |failure| failure.code()),
+    )
+}
@@ -0,0 +1,188 @@
+use super::{travel_blocks, DailyNote, Event, Holiday, PersonDate, Task, Tickle, TravelBlock};
+use crate::config::TravelTimes;
+use crate::parse::{ActionItem, SimpleTimestamp};
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// A single day in an agenda, with everything relevant to that day.
+#[derive(Serialize, Clone, Debug)]
+pub struct AgendaDay {
+    /// The date of this day.
+    pub date: NaiveDate,
+    /// The items on this day, in chronological order (with untimed items first).
+    pub items: Vec<AgendaItem>,
+}
+
+/// Something that appears on a day in an agenda. Tasks can appear on several days for different
+/// reasons (e.g. once on the day they're scheduled for, and again on the day they're due).
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum AgendaItem {
+    Event(Event),
+    /// A task with a timestamp on this day.
+    Task(Task),
+    /// A task which is scheduled to be started on this day.
+    ScheduledTask(Task),
+    /// A task which is due on this day.
+    DueTask(Task),
+    DailyNote(DailyNote),
+    Tickle(Tickle),
+    PersonDate(PersonDate),
+    Holiday(Holiday),
+    /// Time spent travelling to an event from the location of the one before it.
+    Travel(TravelBlock),
+}
+impl AgendaItem {
+    /// Gets the time at which this item occurs on the given day, if it has one.
+    pub fn time_on(&self, date: NaiveDate) -> Option<NaiveTime> {
+        match self {
+            Self::Event(ev) => ts_time_on(&ev.timestamp, date),
+            Self::Task(task) => task.timestamp.as_ref().and_then(|ts| ts_time_on(ts, date)),
+            Self::ScheduledTask(task) => task.scheduled.and_then(dt_time),
+            Self::DueTask(task) => task.deadline.and_then(dt_time),
+            Self::Travel(block) => (block.start.date() == date).then_some(block.start.time()),
+            Self::DailyNote(_) | Self::Tickle(_) | Self::PersonDate(_) | Self::Holiday(_) => None,
+        }
+    }
+
+    /// Gets the ID of the underlying item, if it has one (travel blocks, and holidays from
+    /// calendar files, don't).
+    pub fn id(&self) -> Option<Uuid> {
+        match self {
+            Self::Event(ev) => Some(ev.id),
+            Self::Task(task) | Self::ScheduledTask(task) | Self::DueTask(task) => Some(task.id),
+            Self::DailyNote(dn) => Some(dn.id),
+            Self::Tickle(t) => Some(t.id),
+            Self::PersonDate(d) => Some(d.id),
+            Self::Holiday(h) => h.id,
+            Self::Travel(_) => None,
+        }
+    }
+
+    /// Gets the title of the underlying item.
+    pub fn title(&self) -> &str {
+        match self {
+            Self::Event(ev) => &ev.title,
+            Self::Task(task) | Self::ScheduledTask(task) | Self::DueTask(task) => &task.title,
+            Self::DailyNote(dn) => &dn.title,
+            Self::Tickle(t) => &t.title,
+            Self::PersonDate(d) => &d.title,
+            Self::Holiday(h) => &h.title,
+            Self::Travel(block) => &block.title,
+        }
+    }
+}
+
+/// Builds an agenda of every day from `from` to `until` (inclusive), merging in events, tasks
+/// with timestamps or scheduled/deadline dates on each day, daily notes, tickles, and person
+/// dates, as well as the given holidays. Every day in the range will be present, even if there's
+/// nothing on it.
+///
+/// If travel times are given, blocks of travel will also be added before events which are in a
+/// different location to the one before them.
+pub fn build_agenda(
+    action_items: &HashMap<Uuid, ActionItem>,
+    from: NaiveDate,
+    until: NaiveDate,
+    holidays: &[Holiday],
+    travel_times: Option<&TravelTimes>,
+) -> Result<Vec<AgendaDay>> {
+    let mut days = from
+        .iter_days()
+        .take_while(|date| *date <= until)
+        .map(|date| (date, Vec::new()))
+        .collect::<BTreeMap<_, _>>();
+    // Adds an item to the given day, if it's in the range
+    let mut add = |date: NaiveDate, item: AgendaItem| {
+        if let Some(items) = days.get_mut(&date) {
+            items.push(item);
+        }
+    };
+    let mut events = Vec::new();
+    for holiday in holidays {
+        add(holiday.date, AgendaItem::Holiday(holiday.clone()));
+    }
+
+    for item in action_items.values() {
+        for ev in Event::from_action_item(item, action_items) {
+            let ev = ev?;
+            for date in ts_days(&ev.timestamp, from, until) {
+                add(date, AgendaItem::Event(ev.clone()));
+            }
+            if travel_times.is_some() {
+                events.push(ev);
+            }
+        }
+        for task in Task::from_action_item(item, action_items) {
+            let task = task?;
+            if let Some(ts) = &task.timestamp {
+                for date in ts_days(ts, from, until) {
+                    add(date, AgendaItem::Task(task.clone()));
+                }
+            }
+            if let Some(scheduled) = task.scheduled {
+                add(scheduled.date(), AgendaItem::ScheduledTask(task.clone()));
+            }
+            if let Some(deadline) = task.deadline {
+                add(deadline.date(), AgendaItem::DueTask(task.clone()));
+            }
+        }
+        for dn in DailyNote::from_action_item(item, action_items) {
+            let dn = dn?;
+            add(dn.date, AgendaItem::DailyNote(dn));
+        }
+        for tickle in Tickle::from_action_item(item, action_items) {
+            let tickle = tickle?;
+            add(tickle.date, AgendaItem::Tickle(tickle));
+        }
+        for pd in PersonDate::from_action_item(item, action_items) {
+            let pd = pd?;
+            add(pd.date, AgendaItem::PersonDate(pd));
+        }
+    }
+    if let Some(travel_times) = travel_times {
+        for block in travel_blocks(events, travel_times) {
+            add(block.start.date(), AgendaItem::Travel(block));
+        }
+    }
+
+    Ok(days
+        .into_iter()
+        .map(|(date, mut items)| {
+            items.sort_by_cached_key(|item| item.sort_key(date));
+            AgendaDay { date, items }
+        })
+        .collect())
+}
+
+/// Gets the days the given timestamp covers (which may be several for ranges), clamped to the
+/// given range.
+fn ts_days(
+    ts: &SimpleTimestamp,
+    from: NaiveDate,
+    until: NaiveDate,
+) -> impl Iterator<Item = NaiveDate> {
+    let start = ts.start.date.max(from);
+    let end = ts.end.as_ref().unwrap_or(&ts.start).date.min(until);
+    start.iter_days().take_while(move |date| *date <= end)
+}
+
+/// Gets the time a timestamp starts at on the given day. Days after the first in a range
+/// timestamp are treated as untimed.
+fn ts_time_on(ts: &SimpleTimestamp, date: NaiveDate) -> Option<NaiveTime> {
+    if ts.start.date == date {
+        ts.start.time
+    } else {
+        None
+    }
+}
+
+/// Gets the time from a scheduled/deadline datetime. Dates without times are stored as the end of
+/// the day, so we treat that as untimed.
+fn dt_time(dt: NaiveDateTime) -> Option<NaiveTime> {
+    let time = dt.time();
+    (time != NaiveTime::from_hms_opt(23, 59, 59).unwrap()).then_some(time)
+}
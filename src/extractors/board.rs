@@ -0,0 +1,64 @@
+use super::{DoneItem, Stack, Task, Waiting};
+use crate::parse::{ActionItem, Priority};
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A single stack on a board, with its items organised into columns by state.
+#[derive(Serialize, Clone, Debug)]
+pub struct BoardStack {
+    /// The ID of the node corresponding to this stack.
+    pub id: Uuid,
+    /// The title of this stack.
+    pub title: String,
+    /// The priority of this stack.
+    pub priority: Priority,
+    /// Non-actionable tasks (with the `NEXT` keyword).
+    pub next: Vec<Task>,
+    /// Actionable tasks (with the `TODO` keyword).
+    pub todo: Vec<Task>,
+    /// Items being waited on (with the `WAIT` keyword).
+    pub waiting: Vec<Waiting>,
+    /// Items in this stack completed recently.
+    pub done: Vec<DoneItem>,
+}
+
+/// Builds a board with a column for each state of item in every stack, in the usual order of
+/// stacks. Completed items are taken from the given list, and will only be included if they were
+/// closed on or after `done_since` (if this isn't provided, no completed items will be included).
+pub fn build_board(
+    action_items: &HashMap<Uuid, ActionItem>,
+    done_items: &[DoneItem],
+    done_since: Option<NaiveDate>,
+) -> Result<Vec<BoardStack>> {
+    // We only want one entry per stack, so we take the first repeat of each
+    let mut stacks = action_items
+        .values()
+        .filter_map(|item| Stack::from_action_item(item, action_items).next())
+        .collect::<Result<Vec<_>>>()?;
+    stacks.sort_unstable_by_key(Stack::sort_key);
+
+    Ok(stacks
+        .into_iter()
+        .map(|stack| BoardStack {
+            done: done_items
+                .iter()
+                .filter(|item| {
+                    item.parent_id == Some(stack.id)
+                        && done_since.is_some_and(|since| {
+                            item.closed.is_some_and(|closed| closed.date() >= since)
+                        })
+                })
+                .cloned()
+                .collect(),
+            id: stack.id,
+            title: stack.title,
+            priority: stack.priority,
+            next: stack.next_tasks,
+            todo: stack.actionable_tasks,
+            waiting: stack.waiting,
+        })
+        .collect())
+}
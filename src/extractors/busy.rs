@@ -0,0 +1,213 @@
+use super::{Event, Task};
+use crate::parse::{ActionItem, SimpleTimestamp};
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// A period of time, from `start` (inclusive) to `end` (exclusive).
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interval {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+impl Interval {
+    /// Gets the length of this interval.
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// The times the user is busy on a single day.
+#[derive(Serialize, Clone, Debug)]
+pub struct BusyDay {
+    /// The date of this day.
+    pub date: NaiveDate,
+    /// The busy intervals on this day, in order. These never overlap.
+    pub busy: Vec<Interval>,
+}
+
+/// Works out when the user is busy on each day from `from` to `until` (inclusive), based on the
+/// timed events on those days, as well as tasks with timed timestamps if `include_tasks` is set.
+/// Overlapping intervals will be merged, and those crossing midnight will be split between the
+/// days they cover. Every day in the range will be present, even if nothing is on it.
+///
+/// All-day items don't make the user busy, and neither do items without an end time, because
+/// there's no way of knowing how long they'll take.
+pub fn busy_intervals(
+    action_items: &HashMap<Uuid, ActionItem>,
+    from: NaiveDate,
+    until: NaiveDate,
+    include_tasks: bool,
+) -> Result<BTreeMap<NaiveDate, Vec<Interval>>> {
+    let mut days = from
+        .iter_days()
+        .take_while(|date| *date <= until)
+        .map(|date| (date, Vec::new()))
+        .collect::<BTreeMap<_, _>>();
+    // Splits the given timestamp's interval (if it has one) over the days in the range
+    let mut add = |ts: &SimpleTimestamp| {
+        let Some(interval) = ts_interval(ts) else {
+            return;
+        };
+        for (date, intervals) in days.range_mut(interval.start.date()..=interval.end.date()) {
+            let day_start = date.and_time(NaiveTime::MIN);
+            let day_end = day_start + Duration::days(1);
+            let clipped = Interval {
+                start: interval.start.max(day_start),
+                end: interval.end.min(day_end),
+            };
+            if clipped.start < clipped.end {
+                intervals.push(clipped);
+            }
+        }
+    };
+
+    for item in action_items.values() {
+        for ev in Event::from_action_item(item, action_items) {
+            add(&ev?.timestamp);
+        }
+        if include_tasks {
+            for task in Task::from_action_item(item, action_items) {
+                if let Some(ts) = &task?.timestamp {
+                    add(ts);
+                }
+            }
+        }
+    }
+
+    Ok(days
+        .into_iter()
+        .map(|(date, intervals)| (date, merge(intervals)))
+        .collect())
+}
+
+/// Lists the busy intervals on each day from `from` to `until` (inclusive), in the same way as
+/// [`busy_intervals`]. If `granularity` is given, every interval will be widened to start and end
+/// on multiples of that many minutes from midnight (e.g. 10:05 to 10:40 becomes 10:00 to 10:45 with
+/// a granularity of 15), which avoids revealing exact times when sharing availability.
+pub fn build_busy(
+    action_items: &HashMap<Uuid, ActionItem>,
+    from: NaiveDate,
+    until: NaiveDate,
+    include_tasks: bool,
+    granularity: Option<u32>,
+) -> Result<Vec<BusyDay>> {
+    Ok(busy_intervals(action_items, from, until, include_tasks)?
+        .into_iter()
+        .map(|(date, busy)| BusyDay {
+            busy: match granularity {
+                Some(granularity) => merge(
+                    busy.into_iter()
+                        .map(|interval| round(date, interval, granularity))
+                        .collect(),
+                ),
+                None => busy,
+            },
+            date,
+        })
+        .collect())
+}
+
+/// Finds the gaps of at least `min_duration` between busy intervals within the working hours of
+/// each day, which `hours` gives as a start and end time (or [`None`] for days with no working
+/// hours). The given busy intervals should be merged and sorted within each day.
+pub fn free_slots(
+    busy: &BTreeMap<NaiveDate, Vec<Interval>>,
+    hours: impl Fn(NaiveDate) -> Option<(NaiveTime, NaiveTime)>,
+    min_duration: Duration,
+) -> Vec<Interval> {
+    let mut slots = Vec::new();
+    for (date, intervals) in busy {
+        let Some((day_start, day_end)) = hours(*date) else {
+            continue;
+        };
+        let mut cursor = date.and_time(day_start);
+        let end = date.and_time(day_end);
+        for interval in intervals
+            .iter()
+            .chain(std::iter::once(&Interval { start: end, end }))
+        {
+            let slot = Interval {
+                start: cursor,
+                end: interval.start.min(end),
+            };
+            if slot.duration() >= min_duration {
+                slots.push(slot);
+            }
+            cursor = cursor.max(interval.end);
+        }
+    }
+
+    slots
+}
+
+/// Gets the total number of minutes covered by the given intervals on the given day, only counting
+/// the parts of them between the given start and end times, if there are any.
+pub fn busy_minutes(
+    date: NaiveDate,
+    intervals: &[Interval],
+    within: Option<(NaiveTime, NaiveTime)>,
+) -> u32 {
+    let (start, end) = match within {
+        Some((start, end)) => (date.and_time(start), date.and_time(end)),
+        None => {
+            let midnight = date.and_time(NaiveTime::MIN);
+            (midnight, midnight + Duration::days(1))
+        }
+    };
+    intervals
+        .iter()
+        .map(|interval| {
+            (interval.end.min(end) - interval.start.max(start))
+                .num_minutes()
+                .max(0) as u32
+        })
+        .sum()
+}
+
+/// Gets the interval a timestamp covers, if it has both a start and an end time.
+fn ts_interval(ts: &SimpleTimestamp) -> Option<Interval> {
+    let start = ts.start.date.and_time(ts.start.time?);
+    let end = ts.end.as_ref()?;
+    let end = end.date.and_time(end.time?);
+    (start < end).then_some(Interval { start, end })
+}
+
+/// Widens the given interval on the given day to start and end on multiples of `granularity`
+/// minutes from midnight, without leaving the day.
+fn round(date: NaiveDate, interval: Interval, granularity: u32) -> Interval {
+    let midnight = date.and_time(NaiveTime::MIN);
+    let step = Duration::minutes(granularity as i64);
+    let start = interval.start - midnight;
+    let end = interval.end - midnight;
+
+    let floor = |d: Duration| step * (d.num_seconds() / step.num_seconds()) as i32;
+    let ceil = |d: Duration| {
+        let floored = floor(d);
+        if floored < d {
+            floored + step
+        } else {
+            floored
+        }
+    };
+    Interval {
+        start: midnight + floor(start),
+        end: midnight + ceil(end).min(Duration::days(1)),
+    }
+}
+
+/// Sorts the given intervals and merges any that overlap or touch.
+fn merge(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort_unstable_by_key(|interval| interval.start);
+    let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+            _ => merged.push(interval),
+        }
+    }
+
+    merged
+}
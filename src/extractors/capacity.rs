@@ -0,0 +1,174 @@
+use super::completed::week_number;
+use super::{Task, POMODORO_MINUTES};
+use crate::parse::ActionItem;
+use anyhow::Result;
+use chrono::{Local, NaiveDate, Weekday};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// A comparison of the work planned for a single day against how much time there is for it.
+#[derive(Serialize, Clone, Debug)]
+pub struct CapacityDay {
+    /// The date of this day.
+    pub date: NaiveDate,
+    /// The number of minutes available for tasks on this day, after taking out the time spent in
+    /// events.
+    pub capacity_minutes: u32,
+    /// The number of minutes of working time on this day taken up by events. This is always zero
+    /// unless events were asked to be included.
+    pub event_minutes: u32,
+    /// The estimated number of minutes of work in the tasks on this day.
+    pub load_minutes: u32,
+    /// The summed effort weights of the tasks on this day (see [`crate::parse::Effort::weight`]),
+    /// which is the same unitless score used for the total effort of stacks. This is kept
+    /// alongside the estimate in minutes for comparison.
+    pub load_weight: u32,
+    /// The number of pomodoros needed to cover the load on this day.
+    pub load_pomodoros: u32,
+    /// The number of minutes the load goes over capacity by, which is zero if it fits.
+    pub excess_minutes: u32,
+    /// The tasks contributing to the load on this day.
+    pub tasks: Vec<Task>,
+}
+
+/// The totals of the capacity days in a single week.
+#[derive(Serialize, Clone, Debug)]
+pub struct CapacityWeek {
+    /// The ISO year most of this week belongs to (which may differ from the calendar year of its
+    /// days near the start or end of a year).
+    pub year: i32,
+    /// The ISO number of the week most of this week falls in.
+    pub week: u32,
+    /// The first day of this week which was planned for.
+    pub first_day: NaiveDate,
+    /// The last day of this week which was planned for.
+    pub last_day: NaiveDate,
+    /// The total number of minutes available for tasks over this week.
+    pub capacity_minutes: u32,
+    /// The total number of minutes of working time taken up by events over this week.
+    pub event_minutes: u32,
+    /// The total estimated number of minutes of work in the tasks over this week.
+    pub load_minutes: u32,
+    /// The total effort weight of the tasks over this week.
+    pub load_weight: u32,
+    /// The number of minutes the week's total load goes over its total capacity by, which is zero
+    /// if it fits (even if some individual days don't).
+    pub excess_minutes: u32,
+    /// The number of days in this week which are over capacity on their own.
+    pub days_over: usize,
+}
+
+/// Compares the estimated effort of the tasks on each day from `from` to `until` (inclusive)
+/// against the number of minutes `capacity` gives for that day, less the number of minutes
+/// `event_minutes` says are taken up by events.
+///
+/// Normally, each task is counted on the day it's scheduled for, or the day it's due if it isn't
+/// scheduled. If `spread` is set, tasks with deadlines will instead have their load spread evenly
+/// over every day from when they're scheduled (or today, if they aren't) to when they're due.
+pub fn build_capacity(
+    action_items: &HashMap<Uuid, ActionItem>,
+    from: NaiveDate,
+    until: NaiveDate,
+    capacity: impl Fn(NaiveDate) -> u32,
+    event_minutes: impl Fn(NaiveDate) -> u32,
+    spread: bool,
+) -> Result<Vec<CapacityDay>> {
+    let today = Local::now().date_naive();
+    let mut days = from
+        .iter_days()
+        .take_while(|date| *date <= until)
+        .map(|date| (date, (Vec::new(), 0, 0)))
+        .collect::<BTreeMap<_, _>>();
+    for item in action_items.values() {
+        for task in Task::from_action_item(item, action_items) {
+            let task = task?;
+            let scheduled = task.scheduled.map(|dt| dt.date());
+            let deadline = task.deadline.map(|dt| dt.date());
+            // The days this task's load falls on
+            let (start, end) = match (scheduled, deadline) {
+                (scheduled, Some(deadline)) if spread => {
+                    // Overdue tasks, and those scheduled after they're due, can only go on their
+                    // deadlines
+                    let start = scheduled.unwrap_or(today);
+                    (start.min(deadline), deadline)
+                }
+                (Some(date), _) | (None, Some(date)) => (date, date),
+                (None, None) => continue,
+            };
+
+            let num_days = (end - start).num_days() as u32 + 1;
+            let minutes = task.estimated_minutes();
+            let weight = task.effort.weight();
+            for (date, (tasks, day_minutes, day_weight)) in days.range_mut(start..=end) {
+                // Share out the load, with any remainder going to the earliest days
+                let i = (*date - start).num_days() as u32;
+                *day_minutes += share(minutes, num_days, i);
+                *day_weight += share(weight, num_days, i);
+                tasks.push(task.clone());
+            }
+        }
+    }
+
+    Ok(days
+        .into_iter()
+        .map(|(date, (mut tasks, load_minutes, load_weight))| {
+            tasks.sort_unstable_by_key(Task::sort_key);
+            let event_minutes = event_minutes(date);
+            let capacity_minutes = capacity(date).saturating_sub(event_minutes);
+            CapacityDay {
+                date,
+                capacity_minutes,
+                event_minutes,
+                load_minutes,
+                load_weight,
+                load_pomodoros: load_minutes.div_ceil(POMODORO_MINUTES),
+                excess_minutes: load_minutes.saturating_sub(capacity_minutes),
+                tasks,
+            }
+        })
+        .collect())
+}
+
+/// Rolls the given capacity days (which should be in order) up into totals for each week they
+/// cover, with weeks starting on the given day. Weeks only partly covered will only total the
+/// days given.
+pub fn weekly_capacity(days: &[CapacityDay], week_start: Weekday) -> Vec<CapacityWeek> {
+    let mut weeks: Vec<CapacityWeek> = Vec::new();
+    for day in days {
+        let iso = week_number(day.date, week_start);
+        let week = match weeks.last_mut() {
+            Some(week) if week.year == iso.year() && week.week == iso.week() => week,
+            _ => {
+                weeks.push(CapacityWeek {
+                    year: iso.year(),
+                    week: iso.week(),
+                    first_day: day.date,
+                    last_day: day.date,
+                    capacity_minutes: 0,
+                    event_minutes: 0,
+                    load_minutes: 0,
+                    load_weight: 0,
+                    excess_minutes: 0,
+                    days_over: 0,
+                });
+                weeks.last_mut().unwrap()
+            }
+        };
+        week.last_day = day.date;
+        week.capacity_minutes += day.capacity_minutes;
+        week.event_minutes += day.event_minutes;
+        week.load_minutes += day.load_minutes;
+        week.load_weight += day.load_weight;
+        week.excess_minutes = week.load_minutes.saturating_sub(week.capacity_minutes);
+        week.days_over += usize::from(day.excess_minutes > 0);
+    }
+
+    weeks
+}
+
+/// Gets the share of `total` that falls on the `i`th of `parts` parts when it's divided as evenly
+/// as possible, with the remainder going to the earliest parts.
+fn share(total: u32, parts: u32, i: u32) -> u32 {
+    total / parts + u32::from(i < total % parts)
+}
@@ -0,0 +1,90 @@
+use crate::parse::{clock_entries, ActionItem};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The time tracked on a single day, from the `CLOCK` entries in the logbooks of action items.
+#[derive(Serialize, Clone, Debug)]
+pub struct ClockSummary {
+    /// The item which is clocked in right now, if there is one.
+    pub current: Option<CurrentClock>,
+    /// The items with time tracked on the day, with the most time first.
+    pub items: Vec<TrackedItem>,
+    /// The total number of minutes tracked on the day.
+    pub total_minutes: u32,
+}
+
+/// The item which is clocked in right now.
+#[derive(Serialize, Clone, Debug)]
+pub struct CurrentClock {
+    /// The ID of the node corresponding to this item.
+    pub id: Uuid,
+    /// The title of this item.
+    pub title: String,
+    /// When the clock was started.
+    pub since: NaiveDateTime,
+    /// The number of minutes the clock has been running for.
+    pub minutes: u32,
+}
+
+/// An item with time tracked on it on a particular day.
+#[derive(Serialize, Clone, Debug)]
+pub struct TrackedItem {
+    /// The ID of the node corresponding to this item.
+    pub id: Uuid,
+    /// The title of this item.
+    pub title: String,
+    /// The area of focus this item falls under, if any.
+    pub area: Option<String>,
+    /// The number of minutes tracked on this item on the day (including any running clock, up to
+    /// `now`).
+    pub minutes: u32,
+}
+
+/// Works out how much time was tracked on each action item on the given date from the `CLOCK`
+/// entries in their bodies, along with the clock that's running at `now`, if there is one.
+pub fn build_clock(
+    action_items: &HashMap<Uuid, ActionItem>,
+    date: NaiveDate,
+    now: NaiveDateTime,
+) -> ClockSummary {
+    let mut current = None;
+    let mut items = Vec::new();
+    for item in action_items.values() {
+        let base = item.base();
+        let Some(body) = &base.body else {
+            continue;
+        };
+        let title = base.title.last().cloned().unwrap();
+
+        let entries = clock_entries(body);
+        if let Some(entry) = entries.iter().find(|entry| entry.end.is_none()) {
+            current = Some(CurrentClock {
+                id: base.id,
+                title: title.clone(),
+                since: entry.start,
+                minutes: (now - entry.start).num_minutes().max(0) as u32,
+            });
+        }
+        let minutes = entries
+            .iter()
+            .map(|entry| entry.minutes_on(date, now))
+            .sum::<u32>();
+        if minutes > 0 {
+            items.push(TrackedItem {
+                id: base.id,
+                title,
+                area: base.area.clone(),
+                minutes,
+            });
+        }
+    }
+    items.sort_unstable_by_key(|item| (std::cmp::Reverse(item.minutes), item.id));
+
+    ClockSummary {
+        current,
+        total_minutes: items.iter().map(|item| item.minutes).sum(),
+        items,
+    }
+}
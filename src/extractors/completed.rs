@@ -0,0 +1,72 @@
+use super::DoneItem;
+use chrono::{Datelike, Duration, IsoWeek, NaiveDate, Weekday};
+use serde::Serialize;
+
+/// The items completed over a single day, or a single week.
+#[derive(Serialize, Clone, Debug)]
+pub struct CompletedPeriod {
+    /// The first day of this period which was asked for.
+    pub first_day: NaiveDate,
+    /// The last day of this period which was asked for. For daily periods, this is the same as
+    /// the first day.
+    pub last_day: NaiveDate,
+    /// The items completed in this period, in the order they were completed.
+    pub items: Vec<DoneItem>,
+}
+
+/// Collects the given completed items into a period for every day from `from` to `until`
+/// (inclusive) by their `CLOSED` timestamps, or a period for every week covering them if `weekly`
+/// gives the day weeks start on (weeks only partly in the range will only cover the days in it).
+/// Items without `CLOSED` timestamps can't be placed, so they're left out, as are cancelled items
+/// unless `include_cancelled` is set.
+pub fn build_completed(
+    done_items: &[DoneItem],
+    from: NaiveDate,
+    until: NaiveDate,
+    weekly: Option<Weekday>,
+    include_cancelled: bool,
+) -> Vec<CompletedPeriod> {
+    let mut periods: Vec<CompletedPeriod> = Vec::new();
+    for date in from.iter_days().take_while(|date| *date <= until) {
+        match periods.last_mut() {
+            Some(period)
+                if weekly.is_some_and(|week_start| {
+                    period.first_day.week(week_start).first_day()
+                        == date.week(week_start).first_day()
+                }) =>
+            {
+                period.last_day = date;
+            }
+            _ => periods.push(CompletedPeriod {
+                first_day: date,
+                last_day: date,
+                items: Vec::new(),
+            }),
+        }
+    }
+
+    let mut items = done_items
+        .iter()
+        .filter(|item| include_cancelled || item.keyword != "CANCELLED")
+        .filter_map(|item| item.closed.map(|closed| (closed, item)))
+        .collect::<Vec<_>>();
+    items.sort_unstable_by_key(|(closed, item)| (*closed, item.id));
+    for (closed, item) in items {
+        let date = closed.date();
+        // Periods are in order and don't overlap, so at most one can hold this date
+        let idx = periods.partition_point(|period| period.last_day < date);
+        if let Some(period) = periods.get_mut(idx).filter(|p| p.first_day <= date) {
+            period.items.push(item.clone());
+        }
+    }
+
+    periods
+}
+
+/// Gets the ISO week most of the week the given date is in falls in, with weeks starting on the
+/// given day. For weeks starting on Monday, this is just the ISO week of the date, and for weeks
+/// starting on other days, it's the ISO week of the middle day of the week, so every week gets a
+/// different number.
+pub(super) fn week_number(date: NaiveDate, week_start: Weekday) -> IsoWeek {
+    (date.week(week_start).first_day() + Duration::days(3)).iso_week()
+}
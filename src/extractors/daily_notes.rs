@@ -14,6 +14,8 @@ pub struct DailyNote {
     pub id: Uuid,
     /// The title of this note.
     pub title: String,
+    /// The area of focus this note falls under, if any.
+    pub area: Option<String>,
     /// The body of this note, if one is present.
     pub body: Option<String>,
     /// The date on which this daily note should be displayed.
@@ -38,6 +40,7 @@ impl DailyNote {
                         Ok(Self {
                             id: item.base().id,
                             title: item.base().title.last().cloned().unwrap(),
+                            area: item.base().area.clone(),
                             body: item.base().body.clone(),
                             date: ts.start.date,
                         })
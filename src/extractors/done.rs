@@ -0,0 +1,66 @@
+use crate::parse::{area_from_node, is_done_keyword, Effort, Node};
+use chrono::{NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// An item which has been completed. These are excluded from normal processing entirely, so
+/// they're extracted directly from the raw nodes, and only carry the basic details needed to show
+/// what's been done recently.
+#[derive(Serialize, Clone, Debug)]
+pub struct DoneItem {
+    /// The ID of the node corresponding to this item.
+    pub id: Uuid,
+    /// The title of this item.
+    pub title: String,
+    /// The area of focus this item fell under, if any.
+    pub area: Option<String>,
+    /// The completion keyword on this item (e.g. `DONE`).
+    pub keyword: String,
+    /// The ID of this item's parent node, if it has one.
+    pub parent_id: Option<Uuid>,
+    /// When this item was completed, if it has a `CLOSED` timestamp.
+    pub closed: Option<NaiveDateTime>,
+    /// The effort this item was estimated to take.
+    pub effort: Effort,
+    /// The number of minutes this item was estimated to take, if its effort was given as a
+    /// duration.
+    pub effort_minutes: Option<u32>,
+}
+impl DoneItem {
+    /// Gets the number of minutes this item was estimated to take, in the same way as
+    /// [`super::Task::estimated_minutes`].
+    pub fn estimated_minutes(&self) -> u32 {
+        self.effort_minutes.unwrap_or_else(|| self.effort.minutes())
+    }
+
+    /// Extracts a completed item from the given raw node, if it has one of the given completion
+    /// keywords (or has been cancelled). Its area will be the first of the given area tags it's
+    /// under.
+    pub fn from_node(node: &Node, done_keywords: &[String], areas: &[String]) -> Option<Self> {
+        let metadata = node.metadata.as_ref().unwrap();
+        let keyword = metadata
+            .keyword
+            .as_ref()
+            .filter(|k| is_done_keyword(k, done_keywords))?;
+        // There's no point rejecting finished items over their efforts, so we just fall back to
+        // the default if it can't be parsed
+        let (effort, effort_minutes) = Effort::from_node(node).unwrap_or((Effort::Medium, None));
+
+        Some(Self {
+            id: node.id,
+            title: node.title.last().cloned().unwrap(),
+            area: area_from_node(node, areas),
+            keyword: keyword.clone(),
+            parent_id: node.parent_id,
+            closed: metadata.closed.as_ref().map(|ts| {
+                ts.start.date.and_time(
+                    ts.start
+                        .time
+                        .unwrap_or(NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
+                )
+            }),
+            effort,
+            effort_minutes,
+        })
+    }
+}
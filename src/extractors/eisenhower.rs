@@ -0,0 +1,43 @@
+use super::Task;
+use crate::parse::Priority;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// Tasks sorted into the four quadrants of an Eisenhower matrix, by whether they're urgent (due
+/// soon) and whether they're important (high priority). Each quadrant keeps the tasks in their
+/// usual sorted order.
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct EisenhowerMatrix {
+    /// Urgent and important tasks, which should be done first.
+    pub do_first: Vec<Task>,
+    /// Important tasks which aren't urgent, which should be scheduled for later.
+    pub schedule: Vec<Task>,
+    /// Urgent tasks which aren't important, which might be delegated.
+    pub delegate: Vec<Task>,
+    /// Tasks which are neither urgent nor important.
+    pub eliminate: Vec<Task>,
+}
+impl EisenhowerMatrix {
+    /// Sorts the given tasks into quadrants. Tasks due on or before `urgent_until` are urgent,
+    /// and those with at least the given priority are important.
+    pub fn from_tasks(
+        tasks: Vec<Task>,
+        urgent_until: NaiveDate,
+        important_priority: Priority,
+    ) -> Self {
+        let mut matrix = Self::default();
+        for task in tasks {
+            let urgent = task.deadline.is_some_and(|d| d.date() <= urgent_until);
+            let important = task.priority >= important_priority;
+            let quadrant = match (urgent, important) {
+                (true, true) => &mut matrix.do_first,
+                (false, true) => &mut matrix.schedule,
+                (true, false) => &mut matrix.delegate,
+                (false, false) => &mut matrix.eliminate,
+            };
+            quadrant.push(task);
+        }
+
+        matrix
+    }
+}
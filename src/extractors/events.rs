@@ -12,6 +12,8 @@ pub struct Event {
     ///
     /// We don't need any of the parent titles, because events exist as standalone nodes.
     pub title: String,
+    /// The area of focus this event falls under, if any.
+    pub area: Option<String>,
     /// The body of the event, if there is one.
     pub body: Option<String>,
     /// The location, if there is one.
@@ -30,11 +32,14 @@ impl Event {
         _map: &'m HashMap<Uuid, ActionItem>,
     ) -> impl Iterator<Item = Result<Self, Infallible>> + 'a {
         item.base().repeats.iter().filter_map(move |repeat| {
-            // No person-related dates, tickles, daily notes, or waiting items are events. This
-            // check is the same every time, so should get hoisted out of the loop
+            // No person-related dates, tickles, holidays, daily notes, or waiting items are
+            // events. This check is the same every time, so should get hoisted out of the loop
             let parent_tags = &item.base().parent_tags;
-            if parent_tags.contains("person_dates") || parent_tags.contains("tickles") {
-                // No person-related dates or tickles are events
+            if parent_tags.contains("person_dates")
+                || parent_tags.contains("tickles")
+                || parent_tags.contains("holidays")
+            {
+                // No person-related dates, tickles, or holidays are events
                 None
             } else if let ActionItem::None {
                 base,
@@ -46,6 +51,7 @@ impl Event {
                     Ok(Self {
                         id: base.id,
                         title: base.title.last().cloned().unwrap(),
+                        area: base.area.clone(),
                         body: base.body.clone(),
                         location: properties.get("LOCATION").cloned(),
                         people: people.clone(),
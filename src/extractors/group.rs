@@ -0,0 +1,202 @@
+use super::{DailyNote, Event, PersonDate, Stack, Task, Tickle, Waiting};
+use crate::parse::SimpleTimestamp;
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt};
+use uuid::Uuid;
+
+/// A property to group the items in a view by, rather than returning them as a flat list.
+#[derive(Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[clap(rename_all = "snake_case")]
+pub enum GroupBy {
+    /// Group by the date the item is relevant on (for items with scheduled/deadline dates, this is
+    /// their timestamp if they have one, otherwise their scheduled date, otherwise their deadline).
+    Day,
+    /// Group tasks by their contexts (tasks with multiple contexts will appear in each group).
+    Context,
+    /// Group by priority.
+    Priority,
+    /// Group by the people involved (items with multiple people will appear in each group).
+    Person,
+    /// Group by the stack an item is part of.
+    #[clap(alias = "project")]
+    #[serde(alias = "project")]
+    Stack,
+    /// Group by the area of focus an item falls under.
+    Area,
+}
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// Items which can be grouped in a view.
+pub trait Groupable: Clone {
+    /// The ways items of this type can be grouped.
+    const GROUPINGS: &'static [GroupBy];
+
+    /// Gets the keys of the groups this item belongs in for the given grouping, which is
+    /// guaranteed to be one of [`Self::GROUPINGS`]. If this is empty, the item will go in the
+    /// group with an empty key (e.g. tasks with no contexts).
+    fn group_keys(&self, by: GroupBy) -> Vec<String>;
+}
+
+/// Groups the given (sorted) items, keeping them in order within each group.
+pub fn group<T: Groupable>(items: Vec<T>, by: GroupBy) -> BTreeMap<String, Vec<T>> {
+    let mut groups = BTreeMap::new();
+    for item in items {
+        let mut keys = item.group_keys(by);
+        if keys.is_empty() {
+            keys.push(String::new());
+        }
+        for key in keys {
+            groups
+                .entry(key)
+                .or_insert_with(Vec::new)
+                .push(item.clone());
+        }
+    }
+
+    groups
+}
+
+impl Groupable for Event {
+    const GROUPINGS: &'static [GroupBy] = &[GroupBy::Day, GroupBy::Person, GroupBy::Area];
+
+    fn group_keys(&self, by: GroupBy) -> Vec<String> {
+        match by {
+            GroupBy::Person => names(&self.people),
+            GroupBy::Area => area_key(&self.area),
+            _ => day_key(Some(self.timestamp.start.date)),
+        }
+    }
+}
+
+impl Groupable for DailyNote {
+    const GROUPINGS: &'static [GroupBy] = &[GroupBy::Day, GroupBy::Area];
+
+    fn group_keys(&self, by: GroupBy) -> Vec<String> {
+        match by {
+            GroupBy::Area => area_key(&self.area),
+            _ => day_key(Some(self.date)),
+        }
+    }
+}
+
+impl Groupable for Tickle {
+    const GROUPINGS: &'static [GroupBy] = &[GroupBy::Day, GroupBy::Area];
+
+    fn group_keys(&self, by: GroupBy) -> Vec<String> {
+        match by {
+            GroupBy::Area => area_key(&self.area),
+            _ => day_key(Some(self.date)),
+        }
+    }
+}
+
+impl Groupable for PersonDate {
+    const GROUPINGS: &'static [GroupBy] = &[GroupBy::Day, GroupBy::Person, GroupBy::Area];
+
+    fn group_keys(&self, by: GroupBy) -> Vec<String> {
+        match by {
+            GroupBy::Person => vec![self.person.1.clone()],
+            GroupBy::Area => area_key(&self.area),
+            _ => day_key(Some(self.date)),
+        }
+    }
+}
+
+impl Groupable for Waiting {
+    const GROUPINGS: &'static [GroupBy] = &[GroupBy::Day, GroupBy::Stack, GroupBy::Area];
+
+    fn group_keys(&self, by: GroupBy) -> Vec<String> {
+        match by {
+            GroupBy::Stack => self.stack.iter().map(|(_, title)| title.clone()).collect(),
+            GroupBy::Area => area_key(&self.area),
+            _ => day_key(planning_date(None, self.scheduled, self.deadline)),
+        }
+    }
+}
+
+impl Groupable for Stack {
+    const GROUPINGS: &'static [GroupBy] = &[GroupBy::Day, GroupBy::Priority, GroupBy::Area];
+
+    fn group_keys(&self, by: GroupBy) -> Vec<String> {
+        match by {
+            GroupBy::Area => area_key(&self.area),
+            GroupBy::Priority => vec![self
+                .priority
+                .to_possible_value()
+                .unwrap()
+                .get_name()
+                .to_string()],
+            _ => day_key(planning_date(
+                self.timestamp.as_ref(),
+                self.scheduled,
+                self.deadline,
+            )),
+        }
+    }
+}
+
+impl Groupable for Task {
+    const GROUPINGS: &'static [GroupBy] = &[
+        GroupBy::Day,
+        GroupBy::Context,
+        GroupBy::Priority,
+        GroupBy::Person,
+        GroupBy::Stack,
+        GroupBy::Area,
+    ];
+
+    fn group_keys(&self, by: GroupBy) -> Vec<String> {
+        match by {
+            GroupBy::Day => day_key(planning_date(
+                self.timestamp.as_ref().or(self.parent_timestamp.as_ref()),
+                self.scheduled,
+                self.deadline,
+            )),
+            GroupBy::Context => self.contexts.iter().cloned().collect(),
+            GroupBy::Priority => vec![self
+                .priority
+                .to_possible_value()
+                .unwrap()
+                .get_name()
+                .to_string()],
+            GroupBy::Person => names(&self.people),
+            GroupBy::Stack => self.stack.iter().map(|(_, title)| title.clone()).collect(),
+            GroupBy::Area => area_key(&self.area),
+        }
+    }
+}
+
+/// Formats the given date as a group key (ISO 8601, so the groups are in chronological order).
+fn day_key(date: Option<NaiveDate>) -> Vec<String> {
+    date.map(|date| date.to_string()).into_iter().collect()
+}
+
+/// Gets the date an item with planning dates is relevant on: its timestamp if it has one,
+/// otherwise its scheduled date, otherwise its deadline.
+fn planning_date(
+    timestamp: Option<&SimpleTimestamp>,
+    scheduled: Option<NaiveDateTime>,
+    deadline: Option<NaiveDateTime>,
+) -> Option<NaiveDate> {
+    timestamp
+        .map(|ts| ts.start.date)
+        .or(scheduled.map(|dt| dt.date()))
+        .or(deadline.map(|dt| dt.date()))
+}
+
+/// Formats the given area as a group key (items outside any area will go in the empty group).
+fn area_key(area: &Option<String>) -> Vec<String> {
+    area.iter().cloned().collect()
+}
+
+/// Extracts the names from a list of people.
+fn names(people: &[(Uuid, String)]) -> Vec<String> {
+    people.iter().map(|(_, name)| name.clone()).collect()
+}
@@ -0,0 +1,117 @@
+use crate::ActionItem;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+#[cfg(feature = "ical")]
+use {
+    crate::parse::{parse_ics_date, unescape_ics, unfold_ics},
+    anyhow::{bail, Context},
+    std::path::Path,
+};
+
+/// A day off, like a public holiday. These are shown in agendas as all-day events, and are
+/// treated as non-working days when working out when there's time free.
+///
+/// Holidays can come from all-day items under a `holidays` parent tag, or from an ICS calendar
+/// file given in the configuration (with the `ical` feature).
+#[derive(Serialize, Clone, Debug)]
+pub struct Holiday {
+    /// The ID of the node associated with this holiday, if it came from Starling (holidays from
+    /// calendar files don't have IDs).
+    pub id: Option<Uuid>,
+    /// The title of the holiday.
+    pub title: String,
+    /// The date of the holiday. Holidays spanning several days will have an entry for each one.
+    pub date: NaiveDate,
+}
+impl Holiday {
+    /// Converts the given action item into holidays, if its repeats would go in the holidays list.
+    /// Range timestamps will produce a holiday for every day they cover.
+    pub fn from_action_item<'a, 'm: 'a>(
+        item: &'a ActionItem,
+        _map: &'m HashMap<Uuid, ActionItem>,
+    ) -> impl Iterator<Item = Result<Self>> + 'a {
+        item.base()
+            .repeats
+            .iter()
+            .filter(move |_| {
+                item.base().parent_tags.contains("holidays")
+                    && matches!(item, ActionItem::None { .. })
+            })
+            .filter_map(|repeat| repeat.primary.as_ref())
+            .flat_map(move |ts| {
+                let end = ts.end.as_ref().unwrap_or(&ts.start);
+                if ts.start.time.is_some() || end.time.is_some() {
+                    let err = anyhow!("holiday {} is not an all-day event", item.base().id);
+                    return vec![Err(err)];
+                }
+                ts.start
+                    .date
+                    .iter_days()
+                    .take_while(|date| *date <= end.date)
+                    .map(|date| {
+                        Ok(Self {
+                            id: Some(item.base().id),
+                            title: item.base().title.last().cloned().unwrap(),
+                            date,
+                        })
+                    })
+                    .collect()
+            })
+    }
+}
+
+/// Reads the holidays from the events in the ICS calendar file at the given path. Only the dates
+/// of events are used, so any events with times are treated as lasting all day.
+#[cfg(feature = "ical")]
+pub fn read_ics_holidays(path: &Path) -> Result<Vec<Holiday>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read holidays calendar {}", path.display()))?;
+    parse_ics(&contents)
+        .with_context(|| format!("failed to parse holidays calendar {}", path.display()))
+}
+
+/// Parses the events in the given ICS calendar into holidays. This only handles the parts of the
+/// format we need: the `DTSTART`, `DTEND`, and `SUMMARY` properties of each `VEVENT`.
+#[cfg(feature = "ical")]
+fn parse_ics(contents: &str) -> Result<Vec<Holiday>> {
+    let mut holidays = Vec::new();
+    let mut event: Option<(Option<NaiveDate>, Option<NaiveDate>, Option<String>)> = None;
+    for line in unfold_ics(contents) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters (like `;VALUE=DATE`) don't matter to us
+        let name = name.split(';').next().unwrap();
+        match (name, &mut event) {
+            ("BEGIN", None) if value == "VEVENT" => event = Some((None, None, None)),
+            ("DTSTART", Some((start, _, _))) => *start = Some(parse_ics_date(value)?),
+            ("DTEND", Some((_, end, _))) => *end = Some(parse_ics_date(value)?),
+            ("SUMMARY", Some((_, _, summary))) => *summary = Some(unescape_ics(value)),
+            ("END", Some(_)) if value == "VEVENT" => {
+                let (start, end, summary) = event.take().unwrap();
+                let Some(start) = start else {
+                    bail!("calendar event has no start date");
+                };
+                // End dates are exclusive, and events without them last for a day
+                let end = end.unwrap_or(start).max(start.succ_opt().unwrap());
+                let title = summary.unwrap_or_else(|| "Holiday".to_string());
+                holidays.extend(
+                    start
+                        .iter_days()
+                        .take_while(|date| *date < end)
+                        .map(|date| Holiday {
+                            id: None,
+                            title: title.clone(),
+                            date,
+                        }),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(holidays)
+}
@@ -0,0 +1,44 @@
+use crate::parse::ActionItem;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Something that's been captured into the inbox but not yet processed into any other kind of
+/// item. These have no keyword, no timestamps of any kind, and aren't part of a stack. Items like
+/// that aren't action items as far as Starling is concerned, so the candidates for these are
+/// fetched separately.
+///
+/// Like search hits, we don't produce one of these per repeat, because inbox items never repeat.
+#[derive(Serialize, Clone, Debug)]
+pub struct InboxItem {
+    /// The ID of the node corresponding to this item.
+    pub id: Uuid,
+    /// The title of this item.
+    pub title: String,
+    /// The area of focus this item falls under, if any.
+    pub area: Option<String>,
+    /// The body of this item, if there is one.
+    pub body: Option<String>,
+}
+impl InboxItem {
+    /// Converts the given inbox candidate into an inbox item, if it hasn't been processed yet. The
+    /// given map should contain the action items, so we can tell if it's in a stack.
+    pub fn from_action_item(item: &ActionItem, map: &HashMap<Uuid, ActionItem>) -> Option<Self> {
+        let ActionItem::None { base, .. } = item else {
+            return None;
+        };
+        let unprocessed = base.repeats.iter().all(|repeat| {
+            repeat.primary.is_none() && repeat.scheduled.is_none() && repeat.deadline.is_none()
+        }) && !base
+            .parent_id
+            .and_then(|parent_id| map.get(&parent_id))
+            .is_some_and(|parent| matches!(parent, ActionItem::Stack { .. }));
+
+        unprocessed.then(|| Self {
+            id: base.id,
+            title: base.title.last().cloned().unwrap(),
+            area: base.area.clone(),
+            body: base.body.clone(),
+        })
+    }
+}
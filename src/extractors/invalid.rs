@@ -0,0 +1,84 @@
+use super::{DailyNote, Holiday, PersonDate, Stack, Task, Tickle, Waiting};
+use crate::parse::{ActionItem, ItemError, ItemErrorKind};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Removes every action item which any of the extractors fail on from the given map, returning
+/// records of what went wrong with each of them. Removing an item can cause others to fail (e.g.
+/// a stack whose only actionable task was invalid), so this keeps going until everything left is
+/// valid.
+pub fn remove_invalid_items(map: &mut HashMap<Uuid, ActionItem>) -> Vec<ItemError> {
+    let mut errors = Vec::new();
+    loop {
+        let invalid = map
+            .values()
+            .filter_map(|item| first_error(item, map).map(|err| (item.base().id, err)))
+            .collect::<Vec<_>>();
+        if invalid.is_empty() {
+            break;
+        }
+
+        for (id, (kind, message)) in invalid {
+            let item = map.remove(&id).unwrap();
+            errors.push(ItemError {
+                id,
+                title: item.base().title.last().cloned().unwrap_or_default(),
+                path: item.base().path.clone(),
+                kind,
+                message,
+            });
+        }
+        // Stacks can't refer to children that aren't in the map any more
+        let ids = map.keys().copied().collect::<HashSet<_>>();
+        for item in map.values_mut() {
+            if let ActionItem::Stack { child_items, .. } = item {
+                child_items.retain(|id| ids.contains(id));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Gets the first error any of the extractors produce for the given item, if there is one, along
+/// with the kind of item that was being extracted.
+fn first_error(
+    item: &ActionItem,
+    map: &HashMap<Uuid, ActionItem>,
+) -> Option<(ItemErrorKind, String)> {
+    // Events can't fail to extract
+    let (kind, err) = DailyNote::from_action_item(item, map)
+        .find_map(Result::err)
+        .map(|err| (ItemErrorKind::DailyNote, err))
+        .or_else(|| {
+            Tickle::from_action_item(item, map)
+                .find_map(Result::err)
+                .map(|err| (ItemErrorKind::Tickle, err))
+        })
+        .or_else(|| {
+            PersonDate::from_action_item(item, map)
+                .find_map(Result::err)
+                .map(|err| (ItemErrorKind::PersonDate, err))
+        })
+        .or_else(|| {
+            Waiting::from_action_item(item, map)
+                .find_map(Result::err)
+                .map(|err| (ItemErrorKind::Waiting, err))
+        })
+        .or_else(|| {
+            Stack::from_action_item(item, map)
+                .find_map(Result::err)
+                .map(|err| (ItemErrorKind::Stack, err))
+        })
+        .or_else(|| {
+            Task::from_action_item(item, map)
+                .find_map(Result::err)
+                .map(|err| (ItemErrorKind::Task, err))
+        })
+        .or_else(|| {
+            Holiday::from_action_item(item, map)
+                .find_map(Result::err)
+                .map(|err| (ItemErrorKind::Holiday, err))
+        })?;
+    Some((kind, format!("{err:#}")))
+}
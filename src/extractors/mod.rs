@@ -1,16 +1,61 @@
+mod agenda;
+mod board;
+mod busy;
+mod capacity;
+mod clock;
+mod completed;
 mod daily_notes;
+mod done;
+mod eisenhower;
 mod events;
+mod group;
+mod holidays;
+mod inbox;
+mod invalid;
+mod month;
+mod overdue;
 mod people_dates;
+mod pick;
+mod search;
+mod show;
 mod sort;
 mod stacks;
 mod tasks;
 mod tickles;
+mod time_log;
+mod timeline;
+mod travel;
+mod velocity;
 mod waiting;
 
+pub use agenda::{build_agenda, AgendaDay, AgendaItem};
+pub use board::{build_board, BoardStack};
+pub use busy::{build_busy, busy_intervals, busy_minutes, free_slots, BusyDay, Interval};
+pub use capacity::{build_capacity, weekly_capacity, CapacityDay, CapacityWeek};
+pub use clock::{build_clock, ClockSummary};
+pub use completed::{build_completed, CompletedPeriod};
 pub use daily_notes::DailyNote;
+pub use done::DoneItem;
+pub use eisenhower::EisenhowerMatrix;
 pub use events::Event;
+pub use group::{group, GroupBy, Groupable};
+#[cfg(feature = "ical")]
+pub use holidays::read_ics_holidays;
+pub use holidays::Holiday;
+pub use inbox::InboxItem;
+pub use invalid::remove_invalid_items;
+pub use month::{build_month, MonthDay};
+pub use overdue::{build_overdue, OverdueItem};
 pub use people_dates::PersonDate;
+pub use pick::pick_tasks;
+pub use search::SearchHit;
+pub use show::ItemDetails;
+pub use sort::{sort, SortBy, SortKey, Sortable};
 pub use stacks::Stack;
-pub use tasks::Task;
+pub use tasks::{Task, POMODORO_MINUTES};
 pub use tickles::Tickle;
-pub use waiting::Waiting;
+pub use time_log::{build_time_report, TimeLogBuilder, TimeLogEntry, TimeReport};
+pub use timeline::{build_timeline, Timeline};
+pub use travel::{find_travel_conflicts, travel_blocks, TravelBlock, TravelConflict};
+pub use velocity::{build_velocity, Velocity};
+pub use waiting::{Waiting, STALE_WAIT_DAYS};
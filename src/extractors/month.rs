@@ -0,0 +1,80 @@
+use super::{build_agenda, AgendaItem, Holiday};
+use crate::parse::{ActionItem, Priority};
+use anyhow::Result;
+use chrono::{NaiveDate, Weekday};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A summary of a single day in a month calendar grid.
+#[derive(Serialize, Clone, Debug)]
+pub struct MonthDay {
+    /// The date of this day.
+    pub date: NaiveDate,
+    /// Whether or not this day is in the month being shown (the grid is padded out to full weeks
+    /// with days from the months either side).
+    pub in_month: bool,
+    /// The number of events on this day.
+    pub events: usize,
+    /// The number of tasks with timestamps or scheduled dates on this day.
+    pub tasks: usize,
+    /// The number of tasks due on this day.
+    pub deadlines: usize,
+    /// The titles of things on this day that deserve to stand out in an overview: holidays,
+    /// person dates, and tasks with a high or important priority that are due.
+    pub highlights: Vec<String>,
+}
+
+/// Builds a calendar grid for the month from `first` to `last`, as a list of weeks (starting on
+/// the given day), each with seven days.
+pub fn build_month(
+    action_items: &HashMap<Uuid, ActionItem>,
+    first: NaiveDate,
+    last: NaiveDate,
+    week_start: Weekday,
+    holidays: &[Holiday],
+) -> Result<Vec<Vec<MonthDay>>> {
+    let agenda = build_agenda(
+        action_items,
+        first.week(week_start).first_day(),
+        last.week(week_start).last_day(),
+        holidays,
+        None,
+    )?;
+
+    let days = agenda
+        .into_iter()
+        .map(|day| {
+            let mut month_day = MonthDay {
+                date: day.date,
+                in_month: day.date >= first && day.date <= last,
+                events: 0,
+                tasks: 0,
+                deadlines: 0,
+                highlights: Vec::new(),
+            };
+            for item in day.items {
+                match item {
+                    AgendaItem::Event(_) => month_day.events += 1,
+                    AgendaItem::Task(_) | AgendaItem::ScheduledTask(_) => month_day.tasks += 1,
+                    AgendaItem::DueTask(task) => {
+                        month_day.deadlines += 1;
+                        if task.priority >= Priority::High {
+                            month_day.highlights.push(task.title);
+                        }
+                    }
+                    AgendaItem::Holiday(holiday) => month_day.highlights.push(holiday.title),
+                    AgendaItem::PersonDate(pd) => month_day
+                        .highlights
+                        .push(format!("{} ({})", pd.title, pd.person.1)),
+                    AgendaItem::DailyNote(_) | AgendaItem::Tickle(_) | AgendaItem::Travel(_) => {}
+                }
+            }
+
+            month_day
+        })
+        .collect::<Vec<_>>();
+
+    // The agenda covers full weeks, so this will divide evenly
+    Ok(days.chunks(7).map(<[MonthDay]>::to_vec).collect())
+}
@@ -0,0 +1,87 @@
+use super::{Stack, Task, Waiting};
+use crate::parse::ActionItem;
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// An item whose deadline has passed, along with how far past it is.
+#[derive(Serialize, Clone, Debug)]
+pub struct OverdueItem {
+    /// The number of whole days between the item's deadline and the reference date. This is
+    /// always at least one.
+    pub days_overdue: i64,
+    /// The item itself.
+    pub item: OverdueKind,
+}
+
+/// The kinds of items that can be overdue.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum OverdueKind {
+    Task(Task),
+    Stack(Stack),
+    Waiting(Waiting),
+}
+impl OverdueKind {
+    /// Gets the deadline of the underlying item, if it has one.
+    pub fn deadline(&self) -> Option<NaiveDateTime> {
+        match self {
+            Self::Task(task) => task.deadline,
+            Self::Stack(stack) => stack.deadline,
+            Self::Waiting(waiting) => waiting.deadline,
+        }
+    }
+
+    /// Gets the ID of the underlying item.
+    pub fn id(&self) -> Uuid {
+        match self {
+            Self::Task(task) => task.id,
+            Self::Stack(stack) => stack.id,
+            Self::Waiting(waiting) => waiting.id,
+        }
+    }
+
+    /// Gets the title of the underlying item.
+    pub fn title(&self) -> &str {
+        match self {
+            Self::Task(task) => &task.title,
+            Self::Stack(stack) => &stack.title,
+            Self::Waiting(waiting) => &waiting.title,
+        }
+    }
+}
+
+/// Finds every task, stack, and waiting item whose deadline falls strictly before the given date,
+/// with the most overdue first.
+pub fn build_overdue(
+    action_items: &HashMap<Uuid, ActionItem>,
+    date: NaiveDate,
+) -> Result<Vec<OverdueItem>> {
+    let mut overdue = Vec::new();
+    // Adds the given item if it's overdue
+    let mut add = |item: OverdueKind| {
+        if let Some(deadline) = item.deadline().filter(|d| d.date() < date) {
+            overdue.push(OverdueItem {
+                days_overdue: (date - deadline.date()).num_days(),
+                item,
+            });
+        }
+    };
+
+    for item in action_items.values() {
+        for task in Task::from_action_item(item, action_items) {
+            add(OverdueKind::Task(task?));
+        }
+        for stack in Stack::from_action_item(item, action_items) {
+            add(OverdueKind::Stack(stack?));
+        }
+        for waiting in Waiting::from_action_item(item, action_items) {
+            add(OverdueKind::Waiting(waiting?));
+        }
+    }
+    overdue.sort_by_cached_key(OverdueItem::sort_key);
+
+    Ok(overdue)
+}
@@ -13,6 +13,8 @@ pub struct PersonDate {
     pub id: Uuid,
     /// The title of the date (e.g. birthday).
     pub title: String,
+    /// The area of focus this date falls under, if any.
+    pub area: Option<String>,
     /// The ID and name of the person this date is associated with.
     pub person: (Uuid, String),
     /// The body of the date, if there is one.
@@ -65,13 +67,14 @@ impl PersonDate {
                             Ok(Self {
                                 id: item.base().id,
                                 title: item.base().title.last().cloned().unwrap(),
+                                area: item.base().area.clone(),
                                 body: item.base().body.clone(),
                                 date,
                                 notify_date,
                                 person: person.clone(),
                             })
                         } else {
-                            Err(anyhow!("person date {} must have an ADVANCE property", item.base().id))
+                            Err(anyhow!("person date {} must have an ADVANCE property (or `default_advance` must be set in the config file)", item.base().id))
                         }
                     })
                 } else {
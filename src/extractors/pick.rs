@@ -0,0 +1,77 @@
+use super::Task;
+use chrono::NaiveDate;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Picks up to `count` different tasks at random from the given ones, favouring those with higher
+/// priorities and closer deadlines (measured from `date`). Higher priorities each double a task's
+/// chances, and a task due on or before `date` is eight times as likely to be picked as one with
+/// no deadline, with the boost falling away as the deadline gets further off.
+///
+/// The same `seed` will always pick the same tasks from the same list, and if there isn't one, a
+/// seed will be taken from the current time.
+pub fn pick_tasks(
+    mut tasks: Vec<Task>,
+    date: NaiveDate,
+    count: usize,
+    seed: Option<u64>,
+) -> Vec<Task> {
+    let mut rng = SplitMix64(seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    }));
+    let mut weights = tasks
+        .iter()
+        .map(|task| weight(task, date))
+        .collect::<Vec<_>>();
+
+    let mut picked = Vec::with_capacity(count.min(tasks.len()));
+    while picked.len() < count && !tasks.is_empty() {
+        let mut target = rng.next_f64() * weights.iter().sum::<f64>();
+        // Fall back to the last task in case rounding leaves us just past the end
+        let idx = weights
+            .iter()
+            .position(|weight| {
+                target -= weight;
+                target < 0.0
+            })
+            .unwrap_or(tasks.len() - 1);
+        weights.remove(idx);
+        picked.push(tasks.remove(idx));
+    }
+
+    picked
+}
+
+/// Gets the relative chance of the given task being picked.
+fn weight(task: &Task, date: NaiveDate) -> f64 {
+    let priority = 2f64.powi(task.priority as i32);
+    let urgency = match task
+        .deadline
+        .map(|deadline| (deadline.date() - date).num_days())
+    {
+        Some(days) if days <= 0 => 8.0,
+        Some(days) => 1.0 + 7.0 / (days as f64 + 1.0),
+        None => 1.0,
+    };
+    priority * urgency
+}
+
+/// A small, fast pseudorandom number generator (SplitMix64). This is nowhere near good enough for
+/// anything security-related, but it's plenty for picking tasks.
+struct SplitMix64(u64);
+impl SplitMix64 {
+    /// Generates the next random number in the sequence.
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Generates a random number from zero (inclusive) to one (exclusive).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,63 @@
+use crate::parse::ActionItem;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// How much more a match in the title of an item counts for than a match in its body.
+const TITLE_WEIGHT: u32 = 3;
+
+/// An action item which matched a full-text search, along with how well it matched.
+///
+/// Unlike the other extractors, we don't produce one of these per repeat, because the text of an
+/// item is the same across all its repeats.
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchHit {
+    /// The ID of the node corresponding to this item.
+    pub id: Uuid,
+    /// The title of the item.
+    pub title: String,
+    /// The area of focus the item falls under, if any.
+    pub area: Option<String>,
+    /// The body of the item, if there is one.
+    pub body: Option<String>,
+    /// The kind of action item this is (e.g. `task`, `stack`, `waiting`).
+    pub kind: &'static str,
+    /// How relevant this item is to the query. Higher scores are better matches, but they're only
+    /// meaningful relative to other hits for the same query.
+    pub score: u32,
+}
+impl SearchHit {
+    /// Scores the given action item against the given query terms, which should already be
+    /// lowercased. Terms containing spaces are treated as phrases, and must appear verbatim.
+    ///
+    /// Every term must appear in either the title or the body of the item for it to match, and
+    /// this will return `None` otherwise. Each occurrence of a term adds to the score (more so if
+    /// it's in the title), and phrases count for as many words as they contain, so more specific
+    /// matches rank higher.
+    pub fn from_action_item(item: &ActionItem, terms: &[String]) -> Option<Self> {
+        let base = item.base();
+        let title = base.title.last().cloned().unwrap();
+        let title_lower = title.to_lowercase();
+        let body_lower = base.body.as_deref().unwrap_or("").to_lowercase();
+
+        let mut score = 0;
+        for term in terms {
+            let title_matches = title_lower.matches(term.as_str()).count() as u32;
+            let body_matches = body_lower.matches(term.as_str()).count() as u32;
+            if title_matches == 0 && body_matches == 0 {
+                return None;
+            }
+
+            let term_words = term.split_whitespace().count().max(1) as u32;
+            score += term_words * (TITLE_WEIGHT * title_matches + body_matches);
+        }
+
+        Some(Self {
+            id: base.id,
+            title,
+            area: base.area.clone(),
+            body: base.body.clone(),
+            kind: item.kind(),
+            score,
+        })
+    }
+}
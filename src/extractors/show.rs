@@ -0,0 +1,163 @@
+use super::{tasks::parent_stack, Stack, Task};
+use crate::parse::{ActionItem, SimpleTimestamp};
+use anyhow::{bail, Result};
+use chrono::NaiveDateTime;
+use orgish::timestamp::DateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Everything worked out about a single action item, for checking how its properties were derived
+/// from those of its parents.
+#[derive(Serialize)]
+pub struct ItemDetails<'a> {
+    /// The normalised action item, including any priority inherited from its parent stacks, and
+    /// the expanded repeats of its timestamps.
+    pub item: &'a ActionItem,
+    /// The shortest prefix of this item's ID (without hyphens) which doesn't match any other
+    /// item's, which can be used in place of the full ID.
+    pub short_id: String,
+    /// The ID and title of the stack this item is part of, if it's in one.
+    pub stack: Option<(Uuid, String)>,
+    /// The tasks extracted from the repeats of this item, with their computed scheduled and
+    /// deadline dates. This will be empty for anything other than a task, and for tasks which are
+    /// handled by their own timestamps.
+    pub tasks: Vec<Task>,
+    /// The stacks extracted from the repeats of this item. This will be empty for anything other
+    /// than a stack.
+    pub stacks: Vec<Stack>,
+}
+impl<'a> ItemDetails<'a> {
+    /// Gets the details of the action item with the given ID and short ID. This will fail if
+    /// there's no such item (e.g. because it's been completed).
+    pub fn new(
+        id: Uuid,
+        short_id: String,
+        action_items: &'a HashMap<Uuid, ActionItem>,
+    ) -> Result<Self> {
+        let Some(item) = action_items.get(&id) else {
+            bail!("no action item with id {id} (it may have been completed)");
+        };
+
+        Ok(Self {
+            item,
+            short_id,
+            stack: parent_stack(item, action_items),
+            tasks: Task::from_action_item(item, action_items).collect::<Result<_>>()?,
+            stacks: Stack::from_action_item(item, action_items).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Renders these details as human-readable text, one property to a line.
+    pub fn to_text(&self) -> String {
+        let base = self.item.base();
+        let mut text = format!("{}\n", base.title.join(" > "));
+        // Writing to a string can't fail
+        let mut line = |key: &str, value: &str| writeln!(text, "  {key}: {value}").unwrap();
+
+        line("id", &format!("{} (short: {})", base.id, self.short_id));
+        line(
+            "kind",
+            &match &base.keyword {
+                Some(keyword) => format!("{} ({keyword})", self.item.kind()),
+                None => self.item.kind().to_string(),
+            },
+        );
+        line("file", &base.path.to_string_lossy());
+        if let Some(area) = &base.area {
+            line("area", area);
+        }
+        match self.item {
+            ActionItem::Task {
+                priority,
+                computed_priority,
+                ..
+            }
+            | ActionItem::Stack {
+                priority,
+                computed_priority,
+                ..
+            } => line(
+                "priority",
+                &match computed_priority {
+                    Some(computed) => format!("{computed:?} (inherited, own is {priority:?})"),
+                    None => format!("{priority:?}"),
+                },
+            ),
+            _ => {}
+        }
+        if let Some((id, title)) = &self.stack {
+            line("stack", &format!("{title} ({id})"));
+        }
+        if let ActionItem::Task { contexts, .. } = self.item {
+            let mut contexts = contexts.iter().map(String::as_str).collect::<Vec<_>>();
+            contexts.sort_unstable();
+            line("contexts", &contexts.join(", "));
+        }
+        if let ActionItem::Task { people, .. } | ActionItem::None { people, .. } = self.item {
+            let people = people
+                .iter()
+                .map(|(id, name)| format!("{name} ({id})"))
+                .collect::<Vec<_>>();
+            line("people", &people.join(", "));
+        }
+
+        for repeat in &base.repeats {
+            line(
+                "repeat",
+                &planning(repeat.primary.as_ref(), repeat.scheduled, repeat.deadline),
+            );
+        }
+        for task in &self.tasks {
+            line(
+                "computed",
+                &planning(task.timestamp.as_ref(), task.scheduled, task.deadline),
+            );
+        }
+        for stack in &self.stacks {
+            line(
+                "computed",
+                &planning(stack.timestamp.as_ref(), stack.scheduled, stack.deadline),
+            );
+        }
+
+        text
+    }
+}
+
+/// Describes the given timestamp and planning dates on a single line.
+fn planning(
+    timestamp: Option<&SimpleTimestamp>,
+    scheduled: Option<NaiveDateTime>,
+    deadline: Option<NaiveDateTime>,
+) -> String {
+    let mut parts = Vec::new();
+    if let Some(ts) = timestamp {
+        let mut part = format!("timestamp {}", date_time(&ts.start));
+        if let Some(end) = &ts.end {
+            write!(part, " to {}", date_time(end)).unwrap();
+        }
+        parts.push(part);
+    }
+    if let Some(scheduled) = scheduled {
+        parts.push(format!("scheduled {}", scheduled.format("%Y-%m-%d %H:%M")));
+    }
+    if let Some(deadline) = deadline {
+        parts.push(format!("deadline {}", deadline.format("%Y-%m-%d %H:%M")));
+    }
+
+    if parts.is_empty() {
+        "no dates".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Formats the given date with its time, if it has one.
+fn date_time(dt: &DateTime) -> String {
+    match dt.time {
+        Some(time) => format!("{} {}", dt.date, time.format("%H:%M")),
+        None => dt.date.to_string(),
+    }
+}
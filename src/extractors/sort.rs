@@ -1,6 +1,14 @@
-use super::{DailyNote, Event, PersonDate, Stack, Task, Tickle, Waiting};
-use crate::parse::Priority;
+use super::{
+    AgendaItem, DailyNote, Event, Holiday, InboxItem, OverdueItem, PersonDate, SearchHit, Stack,
+    Task, Tickle, Waiting,
+};
+use crate::parse::{Priority, SimpleTimestamp};
+use anyhow::{anyhow, bail, Error};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+use std::{cmp::Reverse, fmt, str::FromStr};
+use uuid::Uuid;
 
 /// The end of representable time, used as a maximum time to push items without scheduled or
 /// deadline dates to the end of a sorted list.
@@ -43,46 +51,332 @@ impl PartialEq for ScheduledDeadline {
 }
 impl Eq for ScheduledDeadline {}
 
+/// A property to sort the items in a view by, instead of their usual order.
+#[derive(Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[clap(rename_all = "snake_case")]
+pub enum SortBy {
+    /// Sort by deadline.
+    Deadline,
+    /// Sort by scheduled date.
+    Scheduled,
+    /// Sort by priority.
+    Priority,
+    /// Sort alphabetically by title.
+    Title,
+    /// Sort by effort (for tasks, this is their estimated number of minutes, and for stacks, it's
+    /// the total effort of their remaining tasks).
+    Effort,
+    /// Sort by the item's timestamp or date (for tasks, this falls back to their parent's
+    /// timestamp).
+    Timestamp,
+}
+impl fmt::Display for SortBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// One component of a sort expression, which sorts by a property in a particular direction. These
+/// are written as the property followed optionally by `asc` or `desc` (e.g. `priority desc`),
+/// and are ascending by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    /// The property to sort by.
+    pub by: SortBy,
+    /// Whether to sort in descending order.
+    pub descending: bool,
+}
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut parts = s.split_whitespace();
+        let by = parts.next().ok_or_else(|| anyhow!("empty sort key"))?;
+        let by = SortBy::from_str(by, true).map_err(|_| anyhow!("can't sort by `{by}`"))?;
+        let descending = match parts.next() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(dir) => bail!("unknown sort direction `{dir}` (expected `asc` or `desc`)"),
+        };
+        if parts.next().is_some() {
+            bail!("sort key `{s}` should be a property and an optional direction");
+        }
+
+        Ok(Self { by, descending })
+    }
+}
+impl<'de> Deserialize<'de> for SortKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The value of the property an item is being sorted by. Only values of the same kind will ever
+/// be compared.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub enum SortValue {
+    DateTime(NaiveDateTime),
+    Priority(Priority),
+    Effort(u32),
+    Title(String),
+}
+
+/// Items which can be sorted by a chosen property in a view.
+pub trait Sortable {
+    /// The properties items of this type can be sorted by.
+    const SORTINGS: &'static [SortBy];
+    /// The keys items of this type are sorted by in views that don't ask for any others. Ties
+    /// are left in the order the items were already in.
+    const DEFAULT_SORT: &'static [SortKey];
+
+    /// Gets the value of the given property on this item, which is guaranteed to be one of
+    /// [`Self::SORTINGS`]. If the item doesn't have the property (e.g. a task with no deadline),
+    /// this should be `None`.
+    fn sort_value(&self, by: SortBy) -> Option<SortValue>;
+}
+
+/// Creates a key which sorts by the given property in ascending order, for default sort orders.
+const fn asc(by: SortBy) -> SortKey {
+    SortKey {
+        by,
+        descending: false,
+    }
+}
+
+/// Sorts the given items by the given keys, with ties on earlier keys broken by later ones. This
+/// is a stable sort, so items sorted in their usual order beforehand will stay that way among
+/// those with equal values for every key. Items without a property always go after those with it,
+/// even when sorting in descending order.
+pub fn sort<T: Sortable>(items: &mut Vec<T>, keys: &[SortKey]) {
+    // Work out every item's values up-front, rather than on every comparison
+    let mut keyed = items
+        .drain(..)
+        .map(|item| {
+            let values = keys
+                .iter()
+                .map(|key| item.sort_value(key.by))
+                .collect::<Vec<_>>();
+            (values, item)
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by(|(a, _), (b, _)| {
+        keys.iter()
+            .zip(a.iter().zip(b))
+            .map(|(key, (a, b))| {
+                a.is_none().cmp(&b.is_none()).then_with(|| {
+                    if key.descending {
+                        b.cmp(a)
+                    } else {
+                        a.cmp(b)
+                    }
+                })
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
+
+impl Sortable for Event {
+    const SORTINGS: &'static [SortBy] = &[SortBy::Title, SortBy::Timestamp];
+    const DEFAULT_SORT: &'static [SortKey] = &[asc(SortBy::Timestamp), asc(SortBy::Title)];
+
+    fn sort_value(&self, by: SortBy) -> Option<SortValue> {
+        match by {
+            SortBy::Title => Some(SortValue::Title(self.title.clone())),
+            _ => Some(timestamp_value(&self.timestamp)),
+        }
+    }
+}
+
+impl Sortable for DailyNote {
+    const SORTINGS: &'static [SortBy] = &[SortBy::Title, SortBy::Timestamp];
+    const DEFAULT_SORT: &'static [SortKey] = &[asc(SortBy::Timestamp), asc(SortBy::Title)];
+
+    fn sort_value(&self, by: SortBy) -> Option<SortValue> {
+        match by {
+            SortBy::Title => Some(SortValue::Title(self.title.clone())),
+            _ => Some(date_value(self.date)),
+        }
+    }
+}
+
+impl Sortable for Tickle {
+    const SORTINGS: &'static [SortBy] = &[SortBy::Title, SortBy::Timestamp];
+    const DEFAULT_SORT: &'static [SortKey] = &[asc(SortBy::Timestamp), asc(SortBy::Title)];
+
+    fn sort_value(&self, by: SortBy) -> Option<SortValue> {
+        match by {
+            SortBy::Title => Some(SortValue::Title(self.title.clone())),
+            _ => Some(date_value(self.date)),
+        }
+    }
+}
+
+impl Sortable for PersonDate {
+    const SORTINGS: &'static [SortBy] = &[SortBy::Title, SortBy::Timestamp];
+    // Person dates are usually in the order they'll be notified of, which isn't a property they
+    // can be sorted by, so this is left to their keys
+    const DEFAULT_SORT: &'static [SortKey] = &[];
+
+    fn sort_value(&self, by: SortBy) -> Option<SortValue> {
+        match by {
+            SortBy::Title => Some(SortValue::Title(self.title.clone())),
+            _ => Some(date_value(self.date)),
+        }
+    }
+}
+
+impl Sortable for Waiting {
+    const SORTINGS: &'static [SortBy] = &[SortBy::Deadline, SortBy::Scheduled, SortBy::Title];
+    const DEFAULT_SORT: &'static [SortKey] = &[
+        asc(SortBy::Scheduled),
+        asc(SortBy::Deadline),
+        asc(SortBy::Title),
+    ];
+
+    fn sort_value(&self, by: SortBy) -> Option<SortValue> {
+        match by {
+            SortBy::Deadline => self.deadline.map(SortValue::DateTime),
+            SortBy::Scheduled => self.scheduled.map(SortValue::DateTime),
+            _ => Some(SortValue::Title(self.title.clone())),
+        }
+    }
+}
+
+impl Sortable for Stack {
+    const SORTINGS: &'static [SortBy] = &[
+        SortBy::Deadline,
+        SortBy::Scheduled,
+        SortBy::Priority,
+        SortBy::Title,
+        SortBy::Effort,
+        SortBy::Timestamp,
+    ];
+    const DEFAULT_SORT: &'static [SortKey] = &[
+        asc(SortBy::Timestamp),
+        asc(SortBy::Scheduled),
+        asc(SortBy::Deadline),
+        asc(SortBy::Priority),
+        asc(SortBy::Title),
+    ];
+
+    fn sort_value(&self, by: SortBy) -> Option<SortValue> {
+        match by {
+            SortBy::Deadline => self.deadline.map(SortValue::DateTime),
+            SortBy::Scheduled => self.scheduled.map(SortValue::DateTime),
+            SortBy::Priority => Some(SortValue::Priority(self.priority)),
+            SortBy::Title => Some(SortValue::Title(self.title.clone())),
+            SortBy::Effort => Some(SortValue::Effort(self.total_effort)),
+            SortBy::Timestamp => self.timestamp.as_ref().map(timestamp_value),
+        }
+    }
+}
+
+impl Sortable for Task {
+    const SORTINGS: &'static [SortBy] = &[
+        SortBy::Deadline,
+        SortBy::Scheduled,
+        SortBy::Priority,
+        SortBy::Title,
+        SortBy::Effort,
+        SortBy::Timestamp,
+    ];
+    const DEFAULT_SORT: &'static [SortKey] = &[
+        asc(SortBy::Timestamp),
+        asc(SortBy::Scheduled),
+        asc(SortBy::Deadline),
+        asc(SortBy::Priority),
+        asc(SortBy::Title),
+    ];
+
+    fn sort_value(&self, by: SortBy) -> Option<SortValue> {
+        match by {
+            SortBy::Deadline => self.deadline.map(SortValue::DateTime),
+            SortBy::Scheduled => self.scheduled.map(SortValue::DateTime),
+            SortBy::Priority => Some(SortValue::Priority(self.priority)),
+            SortBy::Title => Some(SortValue::Title(self.title.clone())),
+            SortBy::Effort => Some(SortValue::Effort(self.estimated_minutes())),
+            SortBy::Timestamp => self
+                .timestamp
+                .as_ref()
+                .or(self.parent_timestamp.as_ref())
+                .map(timestamp_value),
+        }
+    }
+}
+
+/// Gets the sort value for the start of the given timestamp, putting timestamps without times at
+/// the start of their days.
+fn timestamp_value(ts: &SimpleTimestamp) -> SortValue {
+    SortValue::DateTime(
+        ts.start
+            .date
+            .and_time(ts.start.time.unwrap_or(NaiveTime::MIN)),
+    )
+}
+
+/// Gets the sort value for the given date, at the start of it.
+fn date_value(date: NaiveDate) -> SortValue {
+    SortValue::DateTime(date.and_time(NaiveTime::MIN))
+}
+
 // TODO: Is there a way to avoid all these string clones?
+//
+// Every key ends with the ID of the item, so items whose other properties are all the same will
+// still be in the same order every time.
 impl Event {
-    pub fn sort_key(&self) -> (NaiveDate, Option<NaiveTime>, String) {
+    pub fn sort_key(&self) -> (NaiveDate, Option<NaiveTime>, String, Uuid) {
         (
             self.timestamp.start.date,
             self.timestamp.start.time,
             self.title.clone(),
+            self.id,
         )
     }
 }
 
 impl DailyNote {
-    pub fn sort_key(&self) -> (NaiveDate, String) {
-        (self.date, self.title.clone())
+    pub fn sort_key(&self) -> (NaiveDate, String, Uuid) {
+        (self.date, self.title.clone(), self.id)
     }
 }
 
 impl Tickle {
-    pub fn sort_key(&self) -> (NaiveDate, String) {
-        (self.date, self.title.clone())
+    pub fn sort_key(&self) -> (NaiveDate, String, Uuid) {
+        (self.date, self.title.clone(), self.id)
     }
 }
 
 impl PersonDate {
-    pub fn sort_key(&self) -> (NaiveDate, NaiveDate, String) {
-        (self.notify_date, self.date, self.title.clone())
+    pub fn sort_key(&self) -> (NaiveDate, NaiveDate, String, Uuid) {
+        (self.notify_date, self.date, self.title.clone(), self.id)
     }
 }
 
 impl Waiting {
-    pub fn sort_key(&self) -> (ScheduledDeadline, String) {
+    pub fn sort_key(&self) -> (ScheduledDeadline, String, Uuid) {
         (
             ScheduledDeadline::new(self.scheduled, self.deadline),
             self.title.clone(),
+            self.id,
         )
     }
 }
 
 impl Stack {
-    pub fn sort_key(&self) -> (NaiveDate, NaiveTime, ScheduledDeadline, Priority, String) {
+    pub fn sort_key(
+        &self,
+    ) -> (
+        NaiveDate,
+        NaiveTime,
+        ScheduledDeadline,
+        Priority,
+        String,
+        Uuid,
+    ) {
         (
             self.timestamp
                 .as_ref()
@@ -95,6 +389,7 @@ impl Stack {
             ScheduledDeadline::new(self.scheduled, self.deadline),
             self.priority,
             self.title.clone(),
+            self.id,
         )
     }
 }
@@ -110,6 +405,7 @@ impl Task {
         ScheduledDeadline,
         Priority,
         String,
+        Uuid,
     ) {
         (
             self.timestamp
@@ -131,6 +427,41 @@ impl Task {
             ScheduledDeadline::new(self.scheduled, self.deadline),
             self.priority,
             self.title.clone(),
+            self.id,
+        )
+    }
+}
+
+impl AgendaItem {
+    pub fn sort_key(&self, date: NaiveDate) -> (Option<NaiveTime>, String, Option<Uuid>) {
+        (self.time_on(date), self.title().to_string(), self.id())
+    }
+}
+
+impl SearchHit {
+    pub fn sort_key(&self) -> (Reverse<u32>, String, Uuid) {
+        (Reverse(self.score), self.title.clone(), self.id)
+    }
+}
+
+impl OverdueItem {
+    pub fn sort_key(&self) -> (Option<NaiveDateTime>, String, Uuid) {
+        (
+            self.item.deadline(),
+            self.item.title().to_string(),
+            self.item.id(),
         )
     }
 }
+
+impl InboxItem {
+    pub fn sort_key(&self) -> (String, Uuid) {
+        (self.title.clone(), self.id)
+    }
+}
+
+impl Holiday {
+    pub fn sort_key(&self) -> (NaiveDate, String, Option<Uuid>) {
+        (self.date, self.title.clone(), self.id)
+    }
+}
@@ -5,7 +5,7 @@ use serde::Serialize;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use super::{Task, Waiting};
+use super::{tasks::checklist_counts, Task, Waiting};
 
 /// A stack, which is a collection of tasks, potentially with information about when those tasks
 /// should be started/finished, along with tasks that might not be actionable yet, and things that
@@ -25,6 +25,8 @@ pub struct Stack {
     pub id: Uuid,
     /// The title of the stack.
     pub title: String,
+    /// The area of focus this stack falls under, if any.
+    pub area: Option<String>,
     /// The body of the stack, if there is one.
     pub body: Option<String>,
     /// The main timestamp of the stack, indicating when to next work on it, if it has one.
@@ -37,10 +39,25 @@ pub struct Stack {
     pub priority: Priority,
     /// The actionable tasks on this stack, fully parsed for convenience.
     pub actionable_tasks: Vec<Task>,
-    /// The non-actionable tasks on this stack, fully parsed for convenience.
+    /// The non-actionable tasks on this stack, fully parsed for convenience. This includes tasks
+    /// on hold, which are marked as `held`.
     pub next_tasks: Vec<Task>,
     /// The items being waited for within this stack, fully parsed for convenience.
     pub waiting: Vec<Waiting>,
+    /// The total number of tasks directly in this stack, including completed ones.
+    pub tasks_total: usize,
+    /// The number of tasks directly in this stack which haven't been completed yet.
+    pub tasks_remaining: usize,
+    /// The percentage of the tasks in this stack which have been completed (rounded down), if it
+    /// has any tasks.
+    pub completion: Option<u8>,
+    /// The combined effort of the remaining tasks in this stack, as the sum of their effort
+    /// weights (see [`crate::parse::Effort::weight`]).
+    pub total_effort: u32,
+    /// The number of checklist items (e.g. `- [ ] Thing`) in the body of this stack.
+    pub checklist_total: usize,
+    /// The number of checklist items in the body of this stack which have been ticked off.
+    pub checklist_done: usize,
 }
 impl Stack {
     /// Converts the given action item into a series of stacks, if its repeats would go on the
@@ -63,11 +80,15 @@ impl Stack {
                     priority,
                     computed_priority,
                     child_items,
+                    completed_tasks,
                 } = item
                 {
+                    let (checklist_total, checklist_done) =
+                        checklist_counts(base.body.as_deref());
                     let mut proj = Self {
                         id: base.id,
                         title: base.title.last().cloned().unwrap(),
+                        area: base.area.clone(),
                         body: base.body.clone(),
                         timestamp: repeat.primary.clone(),
                         scheduled: repeat.scheduled,
@@ -76,6 +97,12 @@ impl Stack {
                         actionable_tasks: Vec::new(),
                         next_tasks: Vec::new(),
                         waiting: Vec::new(),
+                        tasks_total: *completed_tasks,
+                        tasks_remaining: 0,
+                        completion: None,
+                        total_effort: 0,
+                        checklist_total,
+                        checklist_done,
                     };
 
                     // We keep track of scheduled waiting items of substacks to check if this stack
@@ -126,6 +153,18 @@ impl Stack {
                         );
                     }
 
+                    proj.tasks_remaining = proj.actionable_tasks.len() + proj.next_tasks.len();
+                    proj.tasks_total += proj.tasks_remaining;
+                    proj.completion = (proj.tasks_total > 0).then(|| {
+                        (100 * (proj.tasks_total - proj.tasks_remaining) / proj.tasks_total) as u8
+                    });
+                    proj.total_effort = proj
+                        .actionable_tasks
+                        .iter()
+                        .chain(proj.next_tasks.iter())
+                        .map(|task| task.effort.weight())
+                        .sum();
+
                     Ok(Some(proj))
                 } else {
                     Ok(None)
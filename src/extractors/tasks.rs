@@ -1,10 +1,14 @@
+use crate::warnings::warn_once;
 use crate::{ActionItem, ActionItemRepeat, Effort, Priority, SimpleTimestamp};
 use anyhow::{bail, Result};
-use chrono::{Duration, NaiveDateTime, NaiveTime};
-use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use uuid::Uuid;
 
+/// The length of a single pomodoro (a focused work session), in minutes.
+pub const POMODORO_MINUTES: u32 = 25;
+
 /// A task which has not been slated for a particular time, and which can be actioned immediately.
 /// From the information in each task, the user can schedule them for particular times/days, or
 /// simply leave them and do them when convenient.
@@ -17,10 +21,17 @@ pub struct Task {
     pub id: Uuid,
     /// The title of this task.
     pub title: String,
+    /// The area of focus this task falls under, if any.
+    pub area: Option<String>,
     /// The body of this task, if one exists.
     pub body: Option<String>,
+    /// The raw keyword on this task (e.g. `TODO` or `NEXT`).
+    pub keyword: String,
     /// Whether or not this task is actionable, and can be started immediately.
     pub can_start: bool,
+    /// Whether or not this task is on hold. Held tasks are never actionable, and they're left out
+    /// of task views entirely, only appearing in their stacks.
+    pub held: bool,
     /// A timestamp stating when exactly this task should be done, if it has one.
     pub timestamp: Option<SimpleTimestamp>,
     /// The timestamp on the parent stack, if it has one. This is returned separately for maximum
@@ -40,15 +51,47 @@ pub struct Task {
     /// Whether or not the parent stack of this task has other, non-actionable tasks. This should
     /// be displayed to the user just to make sure they don't get caught unaware.
     pub stack_has_non_actionable: bool,
-    /// The effort required to complete this task.
+    /// The effort required to complete this task. If the effort was given as a duration, this
+    /// will be the level closest to it.
     pub effort: Effort,
-    /// The contexts required to complete this task.
+    /// The number of minutes this task is estimated to take, if its effort was given as a
+    /// duration rather than a level.
+    pub effort_minutes: Option<u32>,
+    /// The number of pomodoros this task is estimated to take, based on its effort.
+    pub pomodoros: u32,
+    /// The contexts required to complete this task. These are serialized in alphabetical order, so
+    /// the same task always produces the same output.
+    #[serde(serialize_with = "serialize_sorted")]
     pub contexts: HashSet<String>,
     /// The people needed to complete this task, listed by their IDs in the system and their
     /// names.
     pub people: Vec<(Uuid, String)>,
+    /// The ID and title of the stack this task is part of, if it's in one.
+    pub stack: Option<(Uuid, String)>,
+    /// The number of checklist items (e.g. `- [ ] Thing`) in the body of this task.
+    pub checklist_total: usize,
+    /// The number of checklist items in the body of this task which have been ticked off.
+    pub checklist_done: usize,
+    /// The incomplete items which need to be completed before this task can be, by their IDs and
+    /// titles. These come from `DEPENDS` properties on this task, and `BLOCKS` properties on the
+    /// others.
+    pub blocked_by: Vec<(Uuid, String)>,
+    /// The incomplete items which can't be completed until this task is, by their IDs and titles.
+    pub blocks: Vec<(Uuid, String)>,
+    /// The date this task was created, from its `CREATED` property or its earliest inactive
+    /// timestamp, if it has either.
+    pub created: Option<NaiveDate>,
+    /// The number of days from when this task was created to the `age_from` date of the view it's
+    /// in, if it has a creation date and the view gave that date.
+    pub age_days: Option<i64>,
 }
 impl Task {
+    /// Gets the number of minutes this task is estimated to take, using the duration it was given
+    /// if there was one, or a rough estimate from its effort level otherwise.
+    pub fn estimated_minutes(&self) -> u32 {
+        self.effort_minutes.unwrap_or_else(|| self.effort.minutes())
+    }
+
     /// Converts the given action item into a series of tasks, if the repeats of that item would go
     /// on the list of tasks yet to be handled.
     pub fn from_action_item<'a, 'm: 'a>(
@@ -65,9 +108,14 @@ impl Task {
                     priority,
                     computed_priority,
                     effort,
+                    effort_minutes,
                     contexts,
                     people,
                     can_start,
+                    held,
+                    created,
+                    blocked_by,
+                    blocks,
                 } = item
                 {
                     let (
@@ -90,20 +138,25 @@ impl Task {
                         && deadline.is_some()
                         && earliest_ts.unwrap() > deadline.unwrap()
                     {
-                        eprintln!(
+                        warn_once(&format!(
                             "task {} will not be completed before its computed deadline",
                             base.id
-                        );
+                        ));
                     }
 
+                    let (checklist_total, checklist_done) = checklist_counts(base.body.as_deref());
+
                     // NOTE: We used to block if either the primary on the task or its stack
                     // existed because those would go through the events pipeline, now we return
                     // them actively and allow filtering for them.
                     Ok(Some(Self {
                         id: base.id,
                         title: base.title.last().cloned().unwrap(),
+                        area: base.area.clone(),
                         body: base.body.clone(),
+                        keyword: base.keyword.clone().unwrap(),
                         can_start: *can_start,
+                        held: *held,
                         timestamp: repeat.primary.clone(),
                         parent_timestamp: parent_ts.clone(),
                         scheduled,
@@ -111,8 +164,20 @@ impl Task {
                         priority: computed_priority.unwrap_or(*priority),
                         stack_has_non_actionable: has_next_tasks,
                         effort: *effort,
+                        effort_minutes: *effort_minutes,
+                        pomodoros: effort_minutes
+                            .unwrap_or_else(|| effort.minutes())
+                            .div_ceil(POMODORO_MINUTES),
                         contexts: contexts.clone(),
                         people: people.clone(),
+                        stack: parent_stack(item, map),
+                        checklist_total,
+                        checklist_done,
+                        blocked_by: blocked_by.clone(),
+                        blocks: blocks.clone(),
+                        created: *created,
+                        // This depends on the view, so it's filled in once we know which one
+                        age_days: None,
                     }))
                 } else {
                     Ok(None)
@@ -207,8 +272,11 @@ pub fn compute_from_parent(
                 // falling back to the stack deadline, if there is one.
                 let mut earliest_imposed_deadline = parent_repeat.deadline.min(repeat.deadline);
                 for child_id in child_items {
+                    // Held tasks are blocked indefinitely, so they don't impose anything
                     if let ActionItem::Task {
-                        can_start: false, ..
+                        can_start: false,
+                        held: false,
+                        ..
                     } = map.get(child_id).unwrap()
                     {
                         has_next_tasks = true;
@@ -260,6 +328,40 @@ pub fn compute_from_parent(
     ))
 }
 
+/// Gets the ID and title of the stack the given action item is directly under, if there is one.
+pub fn parent_stack(item: &ActionItem, map: &HashMap<Uuid, ActionItem>) -> Option<(Uuid, String)> {
+    match item.base().parent_id.and_then(|id| map.get(&id)) {
+        Some(ActionItem::Stack { base, .. }) => {
+            Some((base.id, base.title.last().cloned().unwrap()))
+        }
+        _ => None,
+    }
+}
+
+/// Counts the checklist items in the given body, returning the total number of them and the
+/// number that have been ticked off. These are list items starting with `[ ]` or `[x]`.
+pub fn checklist_counts(body: Option<&str>) -> (usize, usize) {
+    let mut total = 0;
+    let mut done = 0;
+    for line in body.unwrap_or("").lines() {
+        let Some(item) = line
+            .trim_start()
+            .strip_prefix(['-', '*', '+'])
+            .map(str::trim_start)
+        else {
+            continue;
+        };
+        if item.starts_with("[ ]") {
+            total += 1;
+        } else if item.starts_with("[x]") || item.starts_with("[X]") {
+            total += 1;
+            done += 1;
+        }
+    }
+
+    (total, done)
+}
+
 /// Returns the earlier of the two given datetimes, either of which may be `None`.
 fn min_dt(a: Option<NaiveDateTime>, b: Option<NaiveDateTime>) -> Option<NaiveDateTime> {
     match (a, b) {
@@ -282,3 +384,11 @@ fn final_ts_point(ts: &SimpleTimestamp) -> NaiveDateTime {
             .unwrap_or(NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
     )
 }
+
+/// Serializes the given set in sorted order, rather than the arbitrary order it's stored in.
+fn serialize_sorted<S: Serializer>(
+    set: &HashSet<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(set.iter().collect::<BTreeSet<_>>())
+}
@@ -17,6 +17,8 @@ pub struct Tickle {
     pub id: Uuid,
     /// The title of the tickle.
     pub title: String,
+    /// The area of focus this tickle falls under, if any.
+    pub area: Option<String>,
     /// The body of the tickle, if there is one.
     pub body: Option<String>,
     /// The date on which this tickle should be displayed.
@@ -38,6 +40,7 @@ impl Tickle {
                             Ok(Self {
                                 id: item.base().id,
                                 title: item.base().title.last().cloned().unwrap(),
+                                area: item.base().area.clone(),
                                 body: item.base().body.clone(),
                                 date: ts.start.date,
                             })
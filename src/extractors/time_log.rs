@@ -0,0 +1,177 @@
+use crate::parse::{area_from_node, clock_entries, ClockEntry, Node};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// A single period of time tracked on a node, from a `CLOCK` entry in its logbook. These are
+/// extracted from the raw nodes, so time tracked on items which have since been completed is
+/// still included.
+#[derive(Serialize, Clone, Debug)]
+pub struct TimeLogEntry {
+    /// The ID of the node the time was tracked on.
+    pub id: Uuid,
+    /// The title of the node the time was tracked on.
+    pub title: String,
+    /// The ID and title of the stack this node is part of, if it's in one.
+    pub stack: Option<(Uuid, String)>,
+    /// The contexts on this node, in alphabetical order.
+    pub contexts: Vec<String>,
+    /// The area of focus this node falls under, if any.
+    pub area: Option<String>,
+    /// When the clock was started.
+    pub start: NaiveDateTime,
+    /// When the clock was stopped, or `None` if it's still running.
+    pub end: Option<NaiveDateTime>,
+}
+impl TimeLogEntry {
+    /// Gets the number of minutes of this entry which fall between `from` and `until`
+    /// (inclusive), treating a running clock as ending at `now`.
+    pub fn minutes_in(&self, from: NaiveDate, until: NaiveDate, now: NaiveDateTime) -> u32 {
+        ClockEntry {
+            start: self.start,
+            end: self.end,
+        }
+        .minutes_in(from, until, now)
+    }
+}
+
+/// A report of how much time was tracked over a date range, broken down by task, stack, and
+/// context.
+#[derive(Serialize, Clone, Debug)]
+pub struct TimeReport {
+    /// The total number of minutes tracked over the range.
+    pub total_minutes: u32,
+    /// The time tracked on each individual node, with the most time first.
+    pub tasks: Vec<TimeTotal>,
+    /// The time tracked on the nodes in each stack, with the most time first. Time tracked outside
+    /// stacks isn't included here.
+    pub stacks: Vec<TimeTotal>,
+    /// The time tracked on the nodes with each context, with the most time first. Time tracked on
+    /// nodes with several contexts will count towards each of them, and time tracked on nodes with
+    /// no contexts isn't included here.
+    pub contexts: Vec<TimeTotal>,
+}
+
+/// The total time tracked on a single task, stack, or context.
+#[derive(Serialize, Clone, Debug)]
+pub struct TimeTotal {
+    /// The ID of the task or stack, if this isn't a context.
+    pub id: Option<Uuid>,
+    /// The title of the task or stack, or the name of the context.
+    pub name: String,
+    /// The number of minutes tracked.
+    pub minutes: u32,
+}
+
+/// Builds up a log of every `CLOCK` entry in the bodies of raw nodes, taking them one at a time
+/// so they don't all have to be held at once.
+#[derive(Default, Debug)]
+pub struct TimeLogBuilder {
+    /// The titles of the stacks seen so far, keyed by ID.
+    stacks: HashMap<Uuid, String>,
+    /// The entries so far, each with the ID of the parent of its node (which might be a stack that
+    /// hasn't been seen yet).
+    entries: Vec<(Option<Uuid>, TimeLogEntry)>,
+}
+impl TimeLogBuilder {
+    /// Adds the `CLOCK` entries from the body of the given raw node. Areas will be the first of the
+    /// given area tags the node is under.
+    pub fn add(&mut self, node: &Node, areas: &[String]) {
+        let title = node.title.last().cloned().unwrap();
+        if node.metadata.as_ref().unwrap().keyword.as_deref() == Some("STACK") {
+            self.stacks.insert(node.id, title.clone());
+        }
+        let Some(body) = &node.body else {
+            return;
+        };
+        let entries = clock_entries(body);
+        if entries.is_empty() {
+            return;
+        }
+
+        let mut contexts = node.tags.iter().cloned().collect::<Vec<_>>();
+        contexts.sort_unstable();
+        let area = area_from_node(node, areas);
+        for entry in entries {
+            self.entries.push((
+                node.parent_id,
+                TimeLogEntry {
+                    id: node.id,
+                    title: title.clone(),
+                    // Filled in once every stack has been seen
+                    stack: None,
+                    contexts: contexts.clone(),
+                    area: area.clone(),
+                    start: entry.start,
+                    end: entry.end,
+                },
+            ));
+        }
+    }
+
+    /// Finishes the log, with the entries in the order they were started.
+    pub fn finish(self) -> Vec<TimeLogEntry> {
+        let mut log = self
+            .entries
+            .into_iter()
+            .map(|(parent_id, mut entry)| {
+                entry.stack =
+                    parent_id.and_then(|id| self.stacks.get(&id).map(|title| (id, title.clone())));
+                entry
+            })
+            .collect::<Vec<_>>();
+        log.sort_unstable_by_key(|entry| (entry.start, entry.id));
+
+        log
+    }
+}
+
+/// Totals up the time in the given log which falls from `from` to `until` (inclusive), treating
+/// any running clock as ending at `now`.
+pub fn build_time_report(
+    log: &[TimeLogEntry],
+    from: NaiveDate,
+    until: NaiveDate,
+    now: NaiveDateTime,
+) -> TimeReport {
+    let mut tasks = BTreeMap::new();
+    let mut stacks = BTreeMap::new();
+    let mut contexts = BTreeMap::new();
+    let mut total_minutes = 0;
+    for entry in log {
+        let minutes = entry.minutes_in(from, until, now);
+        if minutes == 0 {
+            continue;
+        }
+
+        total_minutes += minutes;
+        *tasks
+            .entry((Some(entry.id), entry.title.clone()))
+            .or_insert(0) += minutes;
+        if let Some((id, title)) = &entry.stack {
+            *stacks.entry((Some(*id), title.clone())).or_insert(0) += minutes;
+        }
+        for context in &entry.contexts {
+            *contexts.entry((None, context.clone())).or_insert(0) += minutes;
+        }
+    }
+
+    TimeReport {
+        total_minutes,
+        tasks: into_totals(tasks),
+        stacks: into_totals(stacks),
+        contexts: into_totals(contexts),
+    }
+}
+
+/// Converts the given map of accumulated minutes into a list of totals, with the most time first.
+fn into_totals(map: BTreeMap<(Option<Uuid>, String), u32>) -> Vec<TimeTotal> {
+    let mut totals = map
+        .into_iter()
+        .map(|((id, name), minutes)| TimeTotal { id, name, minutes })
+        .collect::<Vec<_>>();
+    // The map is already ordered by name, and this sort is stable, so ties stay alphabetical
+    totals.sort_by_key(|total| std::cmp::Reverse(total.minutes));
+    totals
+}
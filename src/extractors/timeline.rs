@@ -0,0 +1,155 @@
+use super::{Stack, Task};
+use crate::parse::ActionItem;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// A timeline of stacks and their tasks, laid out by their scheduled and deadline dates, suitable
+/// for drawing as a Gantt chart.
+#[derive(Serialize, Clone, Debug)]
+pub struct Timeline {
+    /// The stacks on the timeline, in the usual order of stacks.
+    pub stacks: Vec<TimelineStack>,
+    /// Mermaid `gantt` source for the timeline, if it was asked for.
+    pub mermaid: Option<String>,
+}
+impl Timeline {
+    /// Generates Mermaid source for the stacks currently on this timeline. This should be done
+    /// after any stacks have been cut out, so the chart matches them.
+    pub fn add_mermaid(&mut self) {
+        self.mermaid = Some(to_mermaid(&self.stacks));
+    }
+}
+
+/// A stack on a timeline, spanning from when it (or its earliest task) should be started to when
+/// it (or its latest task) must be completed.
+#[derive(Serialize, Clone, Debug)]
+pub struct TimelineStack {
+    /// The ID of the node corresponding to this stack.
+    pub id: Uuid,
+    /// The title of this stack.
+    pub title: String,
+    /// The date this stack starts on.
+    pub start: NaiveDate,
+    /// The date this stack ends on.
+    pub end: NaiveDate,
+    /// The tasks in this stack with scheduled or deadline dates.
+    pub tasks: Vec<TimelineTask>,
+}
+
+/// A single task on a timeline. Tasks with only one of a scheduled or deadline date will start
+/// and end on that date.
+#[derive(Serialize, Clone, Debug)]
+pub struct TimelineTask {
+    /// The ID of the node corresponding to this task.
+    pub id: Uuid,
+    /// The title of this task.
+    pub title: String,
+    /// The date this task starts on.
+    pub start: NaiveDate,
+    /// The date this task ends on.
+    pub end: NaiveDate,
+}
+impl TimelineTask {
+    /// Places the given task on a timeline, if it has any dates.
+    fn from_task(task: &Task) -> Option<Self> {
+        let start = task.scheduled.or(task.deadline)?.date();
+        let end = task.deadline.map_or(start, |deadline| deadline.date());
+        Some(Self {
+            id: task.id,
+            title: task.title.clone(),
+            start,
+            end,
+        })
+    }
+}
+
+/// Builds a timeline of every stack which has dates (either of its own or on its tasks) and
+/// overlaps the given range, if there is one. Mermaid source for it can be generated afterwards with
+/// [`Timeline::add_mermaid`].
+pub fn build_timeline(
+    action_items: &HashMap<Uuid, ActionItem>,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Result<Timeline> {
+    // As with boards, we only want one entry per stack
+    let mut stacks = action_items
+        .values()
+        .filter_map(|item| Stack::from_action_item(item, action_items).next())
+        .collect::<Result<Vec<_>>>()?;
+    stacks.sort_unstable_by_key(Stack::sort_key);
+
+    let stacks = stacks
+        .into_iter()
+        .filter_map(|stack| {
+            let tasks = stack
+                .actionable_tasks
+                .iter()
+                .chain(stack.next_tasks.iter())
+                .filter_map(TimelineTask::from_task)
+                .collect::<Vec<_>>();
+            // Stacks without their own dates span all their tasks
+            let start = stack
+                .scheduled
+                .map(|dt| dt.date())
+                .or_else(|| tasks.iter().map(|task| task.start).min())?;
+            let end = stack
+                .deadline
+                .map(|dt| dt.date())
+                .or_else(|| tasks.iter().map(|task| task.end).max())
+                .unwrap_or(start)
+                .max(start);
+
+            let overlaps =
+                from.is_none_or(|from| end >= from) && until.is_none_or(|until| start <= until);
+            overlaps.then(|| TimelineStack {
+                id: stack.id,
+                title: stack.title,
+                start,
+                end,
+                tasks,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Timeline {
+        stacks,
+        mermaid: None,
+    })
+}
+
+/// Renders the given timeline stacks as Mermaid `gantt` source, with a section for each stack.
+fn to_mermaid(stacks: &[TimelineStack]) -> String {
+    // Colons, semicolons, and hashes are all syntax in Mermaid's task lines
+    let clean = |title: &str| title.replace([':', ';', '#'], " ");
+
+    let mut out = "gantt\n    dateFormat YYYY-MM-DD\n".to_string();
+    for stack in stacks {
+        writeln!(out, "    section {}", clean(&stack.title)).unwrap();
+        if stack.tasks.is_empty() {
+            writeln!(
+                out,
+                "    {} :{}, {}",
+                clean(&stack.title),
+                stack.start,
+                stack.end
+            )
+            .unwrap();
+        }
+        for task in &stack.tasks {
+            writeln!(
+                out,
+                "    {} :{}, {}",
+                clean(&task.title),
+                task.start,
+                task.end
+            )
+            .unwrap();
+        }
+    }
+
+    out
+}
@@ -0,0 +1,142 @@
+use super::Event;
+use crate::config::TravelTimes;
+use crate::parse::ActionItem;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A pair of consecutive events in different locations without enough time between them to
+/// travel from one to the other.
+#[derive(Serialize, Clone, Debug)]
+pub struct TravelConflict {
+    /// The event being travelled from.
+    pub from: Event,
+    /// The event being travelled to.
+    pub to: Event,
+    /// The number of minutes between the end of the first event and the start of the second. This
+    /// will be negative if they overlap.
+    pub gap_minutes: i64,
+    /// The number of minutes it takes to travel between their locations.
+    pub travel_minutes: u32,
+}
+
+/// A block of time spent travelling to an event, ending when it starts.
+#[derive(Serialize, Clone, Debug)]
+pub struct TravelBlock {
+    /// A title for this block, naming where it's travelling to.
+    pub title: String,
+    /// The location being travelled from.
+    pub from_location: String,
+    /// The location being travelled to.
+    pub to_location: String,
+    /// When travel needs to start.
+    pub start: NaiveDateTime,
+    /// When travel needs to end, which is when the event being travelled to starts.
+    pub end: NaiveDateTime,
+}
+
+/// Finds every pair of consecutive timed events starting between `from` and `until` (inclusive)
+/// where both have locations, and the gap between them is shorter than the time it takes to travel
+/// between those locations. Events without an end time are treated as ending when they start.
+pub fn find_travel_conflicts(
+    action_items: &HashMap<Uuid, ActionItem>,
+    from: NaiveDate,
+    until: NaiveDate,
+    travel_times: &TravelTimes,
+) -> Result<Vec<TravelConflict>> {
+    let mut events = Vec::new();
+    for item in action_items.values() {
+        for ev in Event::from_action_item(item, action_items) {
+            let ev = ev?;
+            if ev.timestamp.start.date >= from && ev.timestamp.start.date <= until {
+                events.push(ev);
+            }
+        }
+    }
+    let events = sort_timed(events);
+
+    Ok(travel_legs(&events, travel_times)
+        .filter(|leg| leg.gap < Duration::minutes(leg.minutes as i64))
+        .map(|leg| TravelConflict {
+            from: leg.from.clone(),
+            to: leg.to.clone(),
+            gap_minutes: leg.gap.num_minutes(),
+            travel_minutes: leg.minutes,
+        })
+        .collect())
+}
+
+/// Creates travel blocks before each of the given events that needs travel from the location of
+/// the one before it. The events don't need to be sorted, and any untimed ones will be ignored.
+pub fn travel_blocks(events: Vec<Event>, travel_times: &TravelTimes) -> Vec<TravelBlock> {
+    let events = sort_timed(events);
+    travel_legs(&events, travel_times)
+        .map(|leg| {
+            let end = start_of(leg.to).unwrap();
+            TravelBlock {
+                title: format!("Travel to {}", leg.to_location),
+                from_location: leg.from_location.to_string(),
+                to_location: leg.to_location.to_string(),
+                start: end - Duration::minutes(leg.minutes as i64),
+                end,
+            }
+        })
+        .collect()
+}
+
+/// A journey between two consecutive events in different locations.
+struct TravelLeg<'a> {
+    from: &'a Event,
+    to: &'a Event,
+    from_location: &'a str,
+    to_location: &'a str,
+    /// The time between the end of the first event and the start of the second.
+    gap: Duration,
+    /// The number of minutes the journey takes.
+    minutes: u32,
+}
+
+/// Gets the journeys between each pair of consecutive events (which must be timed and sorted)
+/// that both have locations with a known travel time between them.
+fn travel_legs<'a>(
+    events: &'a [Event],
+    travel_times: &'a TravelTimes,
+) -> impl Iterator<Item = TravelLeg<'a>> + 'a {
+    events.windows(2).filter_map(|pair| {
+        let [from, to] = pair else { unreachable!() };
+        let from_location = from.location.as_deref()?;
+        let to_location = to.location.as_deref()?;
+        let minutes = travel_times.between(from_location, to_location)?;
+        Some(TravelLeg {
+            from,
+            to,
+            from_location,
+            to_location,
+            gap: start_of(to)? - end_of(from)?,
+            minutes,
+        })
+    })
+}
+
+/// Removes any untimed events from the given list, and sorts the rest by when they start.
+fn sort_timed(mut events: Vec<Event>) -> Vec<Event> {
+    events.retain(|ev| ev.timestamp.start.time.is_some());
+    events.sort_by_cached_key(|ev| (start_of(ev), ev.title.clone(), ev.id));
+    events
+}
+
+/// Gets when the given event starts, if it has a time.
+fn start_of(ev: &Event) -> Option<NaiveDateTime> {
+    let start = &ev.timestamp.start;
+    Some(start.date.and_time(start.time?))
+}
+
+/// Gets when the given event ends, if it has a time. Events without end times end when they start.
+fn end_of(ev: &Event) -> Option<NaiveDateTime> {
+    match &ev.timestamp.end {
+        Some(end) => Some(end.date.and_time(end.time?)),
+        None => start_of(ev),
+    }
+}
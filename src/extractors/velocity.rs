@@ -0,0 +1,136 @@
+use super::completed::week_number;
+use super::{build_completed, DoneItem, Task};
+use crate::parse::ActionItem;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, Weekday};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// How much was completed over a past date range, and what that means for the deadlines coming
+/// up.
+#[derive(Serialize, Clone, Debug)]
+pub struct Velocity {
+    /// The totals for each week in the range.
+    pub weeks: Vec<VelocityWeek>,
+    /// The average number of items completed per week over the range.
+    pub items_per_week: f64,
+    /// The average estimated number of minutes of work completed per week over the range.
+    pub minutes_per_week: f64,
+    /// A forecast for each task with a deadline, in order of their deadlines.
+    pub forecast: Vec<DeadlineForecast>,
+    /// The first deadline which won't be met if work carries on at the average rate, if there is
+    /// one.
+    pub unreachable_from: Option<NaiveDate>,
+}
+
+/// The totals of the items completed in a single week.
+#[derive(Serialize, Clone, Debug)]
+pub struct VelocityWeek {
+    /// The ISO year most of this week belongs to.
+    pub year: i32,
+    /// The ISO number of the week most of this week falls in.
+    pub week: u32,
+    /// The first day of this week which was in the range.
+    pub first_day: NaiveDate,
+    /// The last day of this week which was in the range.
+    pub last_day: NaiveDate,
+    /// The number of items completed this week.
+    pub items: usize,
+    /// The total estimated number of minutes of work in the items completed this week.
+    pub minutes: u32,
+    /// The total effort weight of the items completed this week (see
+    /// [`crate::parse::Effort::weight`]).
+    pub weight: u32,
+}
+
+/// A projection of when a task with a deadline will be done, assuming tasks are worked through in
+/// order of their deadlines at the average rate.
+#[derive(Serialize, Clone, Debug)]
+pub struct DeadlineForecast {
+    /// The task with the deadline.
+    pub task: Task,
+    /// The estimated number of minutes of work in this task and all those due before it.
+    pub cumulative_minutes: u32,
+    /// The day this task is projected to be finished on. This will be `None` if nothing was
+    /// completed over the range, in which case no deadlines can be met.
+    pub projected: Option<NaiveDate>,
+    /// Whether or not this task is projected to be finished by its deadline.
+    pub reachable: bool,
+}
+
+/// Works out how quickly items were completed from `from` to `until` (inclusive), using their
+/// `CLOSED` timestamps, and projects from `today` when each task with a deadline will be done if
+/// work carries on at that rate. Weeks start on the given day. Cancelled items don't count as
+/// work done.
+pub fn build_velocity(
+    action_items: &HashMap<Uuid, ActionItem>,
+    done_items: &[DoneItem],
+    from: NaiveDate,
+    until: NaiveDate,
+    today: NaiveDate,
+    week_start: Weekday,
+) -> Result<Velocity> {
+    let weeks = build_completed(done_items, from, until, Some(week_start), false)
+        .into_iter()
+        .map(|period| {
+            let iso = week_number(period.first_day, week_start);
+            VelocityWeek {
+                year: iso.year(),
+                week: iso.week(),
+                first_day: period.first_day,
+                last_day: period.last_day,
+                items: period.items.len(),
+                minutes: period.items.iter().map(DoneItem::estimated_minutes).sum(),
+                weight: period.items.iter().map(|item| item.effort.weight()).sum(),
+            }
+        })
+        .collect::<Vec<_>>();
+    let num_weeks = ((until - from).num_days() + 1) as f64 / 7.0;
+    let items_per_week = weeks.iter().map(|week| week.items).sum::<usize>() as f64 / num_weeks;
+    let minutes_per_week = weeks.iter().map(|week| week.minutes).sum::<u32>() as f64 / num_weeks;
+
+    let mut tasks = Vec::new();
+    for item in action_items.values() {
+        for task in Task::from_action_item(item, action_items) {
+            let task = task?;
+            if task.deadline.is_some() {
+                tasks.push(task);
+            }
+        }
+    }
+    tasks.sort_unstable_by_key(|task| (task.deadline, task.id));
+
+    let minutes_per_day = minutes_per_week / 7.0;
+    let mut cumulative_minutes = 0;
+    let mut unreachable_from = None;
+    let forecast = tasks
+        .into_iter()
+        .map(|task| {
+            cumulative_minutes += task.estimated_minutes();
+            let projected = (minutes_per_day > 0.0).then(|| {
+                let days = (cumulative_minutes as f64 / minutes_per_day).ceil() as i64;
+                today + Duration::days(days.saturating_sub(1))
+            });
+            let deadline = task.deadline.unwrap().date();
+            let reachable = projected.is_some_and(|projected| projected <= deadline);
+            if !reachable && unreachable_from.is_none() {
+                unreachable_from = Some(deadline);
+            }
+            DeadlineForecast {
+                task,
+                cumulative_minutes,
+                projected,
+                reachable,
+            }
+        })
+        .collect();
+
+    Ok(Velocity {
+        weeks,
+        items_per_week,
+        minutes_per_week,
+        forecast,
+        unreachable_from,
+    })
+}
@@ -1,4 +1,4 @@
-use super::tasks::compute_from_parent;
+use super::tasks::{compute_from_parent, parent_stack};
 use crate::{ActionItem, ActionItemRepeat};
 use anyhow::Result;
 use chrono::{NaiveDate, NaiveDateTime};
@@ -6,6 +6,10 @@ use serde::Serialize;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// The number of days after which a waiting item with nothing to resurface it is considered stale,
+/// unless a review asks for something else.
+pub const STALE_WAIT_DAYS: u32 = 30;
+
 /// Something being waited for. These will usually either exist in isolation, or as part of
 /// stacks, before `NEXT` tasks. As such, like actionable tasks, the scheduled and deadline dates
 /// of waiting items will be adjusted for their parent stack's non-actionable tasks.
@@ -15,6 +19,8 @@ pub struct Waiting {
     pub id: Uuid,
     /// The title of the waiting item.
     pub title: String,
+    /// The area of focus this waiting item falls under, if any.
+    pub area: Option<String>,
     /// The body of the waiting item, if there is one.
     pub body: Option<String>,
     /// The date on which the obligation to complete this was delegated to someone else.
@@ -23,8 +29,16 @@ pub struct Waiting {
     pub scheduled: Option<NaiveDateTime>,
     /// The date by which the user needs to have a response.
     pub deadline: Option<NaiveDateTime>,
+    /// The ID and title of the stack this waiting item is part of, if it's in one.
+    pub stack: Option<(Uuid, String)>,
 }
 impl Waiting {
+    /// Determines whether or not this waiting item is stale as of the given date: it was sent
+    /// before then, and has nothing to resurface it.
+    pub fn is_stale(&self, stale_before: NaiveDate) -> bool {
+        self.sent < stale_before && self.scheduled.is_none() && self.deadline.is_none()
+    }
+
     /// Converts the given action item into a series of waiting items, if the item's repeats would
     /// go onto the list of waiting items.
     pub fn from_action_item<'a, 'm: 'a>(
@@ -52,10 +66,12 @@ impl Waiting {
                     Ok(Some(Self {
                         id: base.id,
                         title: base.title.last().cloned().unwrap(),
+                        area: base.area.clone(),
                         body: base.body.clone(),
                         sent: *sent,
                         scheduled,
                         deadline,
+                        stack: parent_stack(item, map),
                     }))
                 } else {
                     Ok(None)
@@ -0,0 +1,476 @@
+//! A small expression language for filters that can't be expressed through the flat filter
+//! options, like `priority >= high && (effort <= low || !context:office)`.
+//!
+//! Expressions are built from these atoms:
+//!
+//! - `priority <op> <level>` and `effort <op> <level>`, where `<op>` is one of `==`, `!=`, `<`,
+//!   `<=`, `>`, or `>=`
+//! - `context:<name>` and `person:<name>`, which check for a context or person on the item
+//!   (values can be quoted, e.g. `person:"Sam Smith"`, and people can also be given by their IDs)
+//! - `area:<name>`, which checks the area of focus the item falls under
+//! - `has:scheduled`, `has:deadline`, and `has:timestamp`, which check for the presence of those
+//!   dates
+//! - `actionable`, which is true for items that can be started immediately
+//!
+//! These can be combined with `!`, `&&`, and `||` (in decreasing order of precedence), and grouped
+//! with parentheses.
+
+use crate::{
+    extractors::{Stack, Task},
+    parse::{Effort, Priority},
+    views::matches_person,
+};
+use anyhow::{anyhow, bail, Error, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+use std::{iter::Peekable, str::FromStr, vec::IntoIter};
+
+/// A parsed filter expression, ready to be evaluated against items.
+#[derive(Clone, Debug)]
+pub struct FilterExpr {
+    root: Expr,
+}
+impl FilterExpr {
+    /// Checks whether or not the given item matches this expression.
+    pub fn matches(&self, item: &impl Filterable) -> bool {
+        self.root.eval(item)
+    }
+}
+impl FromStr for FilterExpr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize(s)?.into_iter().peekable();
+        let root = parse_or(&mut tokens)?;
+        if let Some(token) = tokens.next() {
+            bail!("unexpected {token} in filter expression");
+        }
+
+        Ok(Self { root })
+    }
+}
+impl<'de> Deserialize<'de> for FilterExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Items which filter expressions can be evaluated against. Any properties an item doesn't have
+/// will simply fail to match (e.g. stacks have no effort, so `effort <= low` is false for them).
+pub trait Filterable {
+    fn priority(&self) -> Priority;
+    fn effort(&self) -> Option<Effort>;
+    fn has_context(&self, context: &str) -> bool;
+    fn has_person(&self, person: &str) -> bool;
+    fn in_area(&self, area: &str) -> bool;
+    fn has_field(&self, field: Field) -> bool;
+    fn actionable(&self) -> bool;
+}
+impl Filterable for Task {
+    fn priority(&self) -> Priority {
+        self.priority
+    }
+    fn effort(&self) -> Option<Effort> {
+        Some(self.effort)
+    }
+    fn has_context(&self, context: &str) -> bool {
+        self.contexts.contains(context)
+    }
+    fn has_person(&self, person: &str) -> bool {
+        self.people.iter().any(|p| matches_person(p, person))
+    }
+    fn in_area(&self, area: &str) -> bool {
+        self.area.as_deref() == Some(area)
+    }
+    fn has_field(&self, field: Field) -> bool {
+        match field {
+            Field::Scheduled => self.scheduled.is_some(),
+            Field::Deadline => self.deadline.is_some(),
+            Field::Timestamp => self.timestamp.is_some(),
+        }
+    }
+    fn actionable(&self) -> bool {
+        self.can_start
+    }
+}
+impl Filterable for Stack {
+    fn priority(&self) -> Priority {
+        self.priority
+    }
+    fn effort(&self) -> Option<Effort> {
+        None
+    }
+    fn has_context(&self, _context: &str) -> bool {
+        false
+    }
+    fn has_person(&self, _person: &str) -> bool {
+        false
+    }
+    fn in_area(&self, area: &str) -> bool {
+        self.area.as_deref() == Some(area)
+    }
+    fn has_field(&self, field: Field) -> bool {
+        match field {
+            Field::Scheduled => self.scheduled.is_some(),
+            Field::Deadline => self.deadline.is_some(),
+            Field::Timestamp => self.timestamp.is_some(),
+        }
+    }
+    fn actionable(&self) -> bool {
+        !self.actionable_tasks.is_empty()
+    }
+}
+
+/// Optional dates whose presence can be checked with `has:<field>`.
+#[derive(Clone, Copy, Debug)]
+pub enum Field {
+    Scheduled,
+    Deadline,
+    Timestamp,
+}
+
+/// A node in the expression tree.
+#[derive(Clone, Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Priority(Comparison, Priority),
+    Effort(Comparison, Effort),
+    Context(String),
+    Person(String),
+    Area(String),
+    Has(Field),
+    Actionable,
+}
+impl Expr {
+    fn eval(&self, item: &impl Filterable) -> bool {
+        match self {
+            Self::And(a, b) => a.eval(item) && b.eval(item),
+            Self::Or(a, b) => a.eval(item) || b.eval(item),
+            Self::Not(a) => !a.eval(item),
+            Self::Priority(cmp, p) => cmp.apply(&item.priority(), p),
+            Self::Effort(cmp, e) => item.effort().is_some_and(|effort| cmp.apply(&effort, e)),
+            Self::Context(c) => item.has_context(c),
+            Self::Person(p) => item.has_person(p),
+            Self::Area(a) => item.in_area(a),
+            Self::Has(field) => item.has_field(*field),
+            Self::Actionable => item.actionable(),
+        }
+    }
+}
+
+/// A comparison operator between an item's property and a fixed value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+impl Comparison {
+    fn apply<T: Ord>(&self, item_value: &T, value: &T) -> bool {
+        match self {
+            Self::Eq => item_value == value,
+            Self::Ne => item_value != value,
+            Self::Lt => item_value < value,
+            Self::Le => item_value <= value,
+            Self::Gt => item_value > value,
+            Self::Ge => item_value >= value,
+        }
+    }
+}
+
+/// A single lexical token in a filter expression.
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    OpenParen,
+    CloseParen,
+    Op(Comparison),
+    /// Any run of characters that isn't an operator or parenthesis (with quoted sections
+    /// unwrapped).
+    Word(String),
+}
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::And => write!(f, "`&&`"),
+            Self::Or => write!(f, "`||`"),
+            Self::Not => write!(f, "`!`"),
+            Self::OpenParen => write!(f, "`(`"),
+            Self::CloseParen => write!(f, "`)`"),
+            Self::Op(_) => write!(f, "comparison operator"),
+            Self::Word(w) => write!(f, "`{w}`"),
+        }
+    }
+}
+
+/// Splits the given expression into tokens.
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        // Two-character operators need a lookahead
+        let next = chars.peek().copied();
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('&', Some('&')) => Token::And,
+            ('|', Some('|')) => Token::Or,
+            ('=', Some('=')) => Token::Op(Comparison::Eq),
+            ('!', Some('=')) => Token::Op(Comparison::Ne),
+            ('<', Some('=')) => Token::Op(Comparison::Le),
+            ('>', Some('=')) => Token::Op(Comparison::Ge),
+            ('<', _) => Token::Op(Comparison::Lt),
+            ('>', _) => Token::Op(Comparison::Gt),
+            ('!', _) => Token::Not,
+            ('(', _) => Token::OpenParen,
+            (')', _) => Token::CloseParen,
+            ('&' | '|' | '=', _) => bail!("unexpected `{c}` in filter expression"),
+            _ => {
+                // Read a whole word, unwrapping any quoted sections inside it
+                let mut word = String::new();
+                let mut current = Some(c);
+                while let Some(c) = current {
+                    if c == '"' {
+                        loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some(c) => word.push(c),
+                                None => bail!("unterminated quote in filter expression"),
+                            }
+                        }
+                    } else {
+                        word.push(c);
+                    }
+
+                    current = chars.next_if(|c| !c.is_whitespace() && !"&|=!<>()".contains(*c));
+                }
+                tokens.push(Token::Word(word));
+                continue;
+            }
+        };
+        // Consume the second character of two-character tokens
+        if matches!(
+            token,
+            Token::And
+                | Token::Or
+                | Token::Op(Comparison::Eq | Comparison::Ne | Comparison::Le | Comparison::Ge)
+        ) {
+            chars.next();
+        }
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+/// Parses a series of `||`-separated terms.
+fn parse_or(tokens: &mut Tokens) -> Result<Expr> {
+    let mut expr = parse_and(tokens)?;
+    while tokens.next_if_eq(&Token::Or).is_some() {
+        expr = Expr::Or(Box::new(expr), Box::new(parse_and(tokens)?));
+    }
+    Ok(expr)
+}
+
+/// Parses a series of `&&`-separated terms.
+fn parse_and(tokens: &mut Tokens) -> Result<Expr> {
+    let mut expr = parse_unary(tokens)?;
+    while tokens.next_if_eq(&Token::And).is_some() {
+        expr = Expr::And(Box::new(expr), Box::new(parse_unary(tokens)?));
+    }
+    Ok(expr)
+}
+
+/// Parses a negation, a parenthesised group, or a single atom.
+fn parse_unary(tokens: &mut Tokens) -> Result<Expr> {
+    match tokens.next() {
+        Some(Token::Not) => Ok(Expr::Not(Box::new(parse_unary(tokens)?))),
+        Some(Token::OpenParen) => {
+            let expr = parse_or(tokens)?;
+            match tokens.next() {
+                Some(Token::CloseParen) => Ok(expr),
+                _ => bail!("unclosed `(` in filter expression"),
+            }
+        }
+        Some(Token::Word(word)) => parse_atom(word, tokens),
+        Some(token) => bail!("unexpected {token} in filter expression"),
+        None => bail!("filter expression ended unexpectedly"),
+    }
+}
+
+/// Parses a single atom, which starts with the given word.
+fn parse_atom(word: String, tokens: &mut Tokens) -> Result<Expr> {
+    if let Some((key, value)) = word.split_once(':') {
+        return match key {
+            "context" => Ok(Expr::Context(value.to_string())),
+            "person" => Ok(Expr::Person(value.to_string())),
+            "area" => Ok(Expr::Area(value.to_string())),
+            "has" => match value {
+                "scheduled" => Ok(Expr::Has(Field::Scheduled)),
+                "deadline" => Ok(Expr::Has(Field::Deadline)),
+                "timestamp" => Ok(Expr::Has(Field::Timestamp)),
+                _ => bail!("unknown field `{value}` in `has:` filter"),
+            },
+            _ => bail!("unknown filter `{key}:`"),
+        };
+    }
+
+    match word.as_str() {
+        "actionable" => Ok(Expr::Actionable),
+        "priority" | "effort" => {
+            let cmp = match tokens.next() {
+                Some(Token::Op(cmp)) => cmp,
+                _ => bail!("expected comparison operator after `{word}`"),
+            };
+            let value = match tokens.next() {
+                Some(Token::Word(value)) => value,
+                _ => bail!("expected value to compare `{word}` against"),
+            };
+
+            if word == "priority" {
+                let priority = Priority::from_str(&value, true)
+                    .map_err(|_| anyhow!("unknown priority `{value}` in filter expression"))?;
+                Ok(Expr::Priority(cmp, priority))
+            } else {
+                Ok(Expr::Effort(cmp, value.parse()?))
+            }
+        }
+        _ => bail!("unknown filter `{word}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An item with just the properties filter expressions look at.
+    struct Item {
+        priority: Priority,
+        effort: Option<Effort>,
+        contexts: &'static [&'static str],
+        scheduled: bool,
+    }
+    impl Filterable for Item {
+        fn priority(&self) -> Priority {
+            self.priority
+        }
+        fn effort(&self) -> Option<Effort> {
+            self.effort
+        }
+        fn has_context(&self, context: &str) -> bool {
+            self.contexts.contains(&context)
+        }
+        fn has_person(&self, _person: &str) -> bool {
+            false
+        }
+        fn in_area(&self, _area: &str) -> bool {
+            false
+        }
+        fn has_field(&self, field: Field) -> bool {
+            matches!(field, Field::Scheduled) && self.scheduled
+        }
+        fn actionable(&self) -> bool {
+            true
+        }
+    }
+
+    /// An important, low-effort item in the office, with nothing scheduled.
+    const OFFICE_ITEM: Item = Item {
+        priority: Priority::Important,
+        effort: Some(Effort::Low),
+        contexts: &["office"],
+        scheduled: false,
+    };
+
+    fn matches(expr: &str, item: &Item) -> bool {
+        expr.parse::<FilterExpr>().unwrap().matches(item)
+    }
+
+    #[test]
+    fn operators_are_tokenized_without_spaces() {
+        assert_eq!(
+            tokenize("priority<=high&&!(effort>low)").unwrap(),
+            vec![
+                Token::Word("priority".to_string()),
+                Token::Op(Comparison::Le),
+                Token::Word("high".to_string()),
+                Token::And,
+                Token::Not,
+                Token::OpenParen,
+                Token::Word("effort".to_string()),
+                Token::Op(Comparison::Gt),
+                Token::Word("low".to_string()),
+                Token::CloseParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_sections_are_unwrapped() {
+        assert_eq!(
+            tokenize(r#"person:"Sam Smith" || context:"a&b""#).unwrap(),
+            vec![
+                Token::Word("person:Sam Smith".to_string()),
+                Token::Or,
+                Token::Word("context:a&b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(matches(
+            "!context:office && priority >= high || effort <= low",
+            &OFFICE_ITEM
+        ));
+        assert!(!matches(
+            "!context:office && (priority >= high || effort <= low)",
+            &OFFICE_ITEM
+        ));
+    }
+
+    #[test]
+    fn comparisons_follow_level_order() {
+        assert!(matches("priority > high", &OFFICE_ITEM));
+        assert!(matches("priority != low && effort < medium", &OFFICE_ITEM));
+        assert!(!matches("effort >= medium", &OFFICE_ITEM));
+        assert!(matches("actionable && !has:scheduled", &OFFICE_ITEM));
+    }
+
+    #[test]
+    fn missing_properties_never_match() {
+        let item = Item {
+            effort: None,
+            ..OFFICE_ITEM
+        };
+        assert!(!matches("effort <= total", &item));
+        assert!(!matches("effort > minimal", &item));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expr in [
+            "",
+            "context:home &&",
+            "(context:home",
+            "context:home)",
+            "context:home & context:office",
+            "priority >= urgent",
+            "priority high",
+            "has:tags",
+            "colour:red",
+            r#"person:"Sam"#,
+        ] {
+            assert!(expr.parse::<FilterExpr>().is_err(), "{expr} was accepted");
+        }
+    }
+}
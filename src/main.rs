@@ -1,44 +1,590 @@
 mod cli;
+mod completions;
+mod config;
+mod exit;
 mod extractors;
+mod filter_expr;
+#[cfg(feature = "web")]
+mod metrics;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "notifications")]
+mod notify;
 mod parse;
+mod picker;
+#[cfg(feature = "plugins")]
+mod plugins;
+#[cfg(feature = "web")]
+mod server;
+mod state;
 mod views;
+mod warnings;
 
-use crate::cli::{Cli, Encoding};
+use crate::cli::{
+    no_views, pick_views, read_json_views, review_views, today_views, Cli, ClockCommand, Command,
+    CompletionsCommand, ConfigCommand, Encoding,
+};
+use crate::completions::{cached_values, completion_script, update_cache};
+use crate::config::{Config, ExpansionHorizons};
+use crate::exit::{exit_code, Failure, EMPTY_EXIT_CODE};
 use crate::extractors::*;
+#[cfg(feature = "mqtt")]
+use crate::mqtt::publish_views;
+#[cfg(feature = "notifications")]
+use crate::notify::send_notifications;
 use crate::parse::*;
-use crate::views::TasksFilter;
-use anyhow::{bail, Error, Result};
-use chrono::Local;
-use clap::Parser;
+use crate::picker::pick_item;
+#[cfg(feature = "plugins")]
+use crate::plugins::run_plugin;
+#[cfg(feature = "web")]
+use crate::server::serve;
+use crate::state::Changes;
+use crate::views::{AllViews, DateRange, InboxFilter, TasksFilter};
+use crate::warnings::warn;
+use anyhow::{anyhow, bail, Error, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime};
+use clap::{CommandFactory, FromArgMatches};
 use serde::Serialize;
-use std::collections::HashMap;
-use std::io::Write;
-
-fn main() -> Result<()> {
-    let mut args = Cli::parse();
-    let mut views = match args.parse_views()? {
-        Some(views) => views,
-        // This means the user asked for the help message about views, and we should terminate
-        // (it's already been printed)
-        None => return Ok(()),
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufWriter, Write};
+use std::process::ExitCode;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// The version of the data model Polaris produces. This is bumped whenever the shape of the output
+/// changes at all, including when fields or section types are only added: JSON consumers can
+/// usually ignore additions, but bincode output is decoded by position, so any change breaks them.
+///
+/// # Changelog
+///
+/// - `1`: views are wrapped in an [`Output`] alongside version information (previously the map of
+///   views was the top-level object).
+/// - `2`: each view is a list of tagged [`Section`]s, rather than an object with a key for each
+///   type of data.
+/// - `3`: goals are a list of the goals for each day in the view, rather than a single day's.
+/// - `4`: each goal is an object with its text, whether it's been done, and its subgoals, rather
+///   than a string.
+/// - `5`: `alignment` sections are added. Earlier versions weren't bumped when fields were only
+///   added, so bincode consumers can only rely on versions from this one on.
+/// - `6`: listings are tagged with whether they're `flat` or `grouped` (e.g.
+///   `{"flat": [...]}`), rather than being a bare list or map, and plugin sections are strings of
+///   JSON in bincode output.
+/// - `7`: `area_target_contexts` sections are added.
+/// - `8`: `errors` sections come before `goals`, `alignment`, and `plugin` sections, so the
+///   sections that don't depend on features are encoded the same way in bincode whatever features
+///   are enabled.
+pub const DATA_MODEL_VERSION: u32 = 8;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            exit_code(&err)
+        }
+    }
+}
+
+fn run() -> Result<ExitCode> {
+    let started = Instant::now();
+    let generated_at = Local::now();
+
+    // We need the raw matches as well as the parsed arguments to know which options were given
+    // explicitly when merging in the config file
+    let matches = Cli::command().get_matches();
+    let mut args = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let config = Config::resolve(&args, &matches)?;
+    // Items can be given by aliases or short IDs, which are resolved against all the action items
+    // (completed ones included) only if they're needed
+    let resolve = |reference: &str| {
+        resolve_id(reference, &config.aliases, || {
+            let nodes = get_raw_action_items(
+                NodeOptions {
+                    body: false,
+                    metadata: true,
+                    children: true,
+                    connections: false,
+                    child_connections: false,
+                    conn_format: Format::Markdown,
+                },
+                &*config.backend,
+            )?;
+            Ok(nodes.into_iter().map(|node| node.id).collect())
+        })
     };
 
-    let expand_until =
-        views.last_date.unwrap_or_else(|| Local::now().date_naive()) + *args.repeat_buffer;
-
-    // Fetch the raw action items from Starling and normalise them, expanding repeating timestamps
-    let raw_nodes = get_raw_action_items(
-        NodeOptions {
-            body: true,
-            metadata: true,
-            children: true,
-            connections: false,
-            child_connections: false,
-            conn_format: Format::Markdown,
-        },
-        &args.starling_address,
-    )?;
-    let action_items = normalize_action_items(raw_nodes, &args.done_keywords, expand_until)?;
+    match &args.command {
+        Some(Command::Config(ConfigCommand::Show)) => {
+            print!("{}", config.show());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Completions(CompletionsCommand::Script { shell })) => {
+            print!("{}", completion_script(*shell));
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Completions(CompletionsCommand::Values { kind })) => {
+            print!("{}", cached_values(*kind));
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "web")]
+        Some(Command::Serve {
+            listen,
+            views,
+            refresh,
+        }) => {
+            let views = views
+                .as_deref()
+                .map(|path| read_json_views(path, config.week_start))
+                .transpose()?;
+            serve(
+                &config,
+                listen,
+                views,
+                std::time::Duration::from_secs(*refresh),
+            )?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Healthcheck { timeout }) => {
+            let num_items = config
+                .backend
+                .check(std::time::Duration::from_secs(*timeout))?;
+            println!("ok: {num_items} action items from {}", config.backend);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Check) => {
+            let raw_nodes = get_raw_action_items(
+                NodeOptions {
+                    body: true,
+                    metadata: true,
+                    children: true,
+                    connections: false,
+                    child_connections: false,
+                    conn_format: Format::Markdown,
+                },
+                &*config.backend,
+            )?;
+            // There are no views, so repeats are expanded from the present date
+            let today = Local::now().date_naive();
+            let mut errors = Vec::new();
+            let mut action_items = normalize_action_items(
+                raw_nodes,
+                &config.done_keywords,
+                &config.areas,
+                |node: &Node| today + **config.repeat_buffers.for_node(node),
+                |date| config.deadline_time(date),
+                config.default_advance.as_deref().copied(),
+                &mut errors,
+            );
+            errors.extend(remove_invalid_items(&mut action_items));
+
+            for err in &errors {
+                println!("{err}");
+            }
+            if !errors.is_empty() {
+                return Err(
+                    anyhow!("found {} problem(s)", errors.len()).context(Failure::InvalidData)
+                );
+            }
+            println!("ok: {} action items", action_items.len());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Done { id, keyword }) => {
+            let keyword = keyword
+                .as_deref()
+                .or_else(|| config.done_keywords.first().map(String::as_str))
+                .unwrap_or("DONE");
+            let completion = mark_done(
+                config.backend.starling()?,
+                resolve(id)?,
+                keyword,
+                &config.done_keywords,
+                Local::now().naive_local(),
+            )?;
+            match completion {
+                Completion::Closed { title } => println!("{keyword}: {title}"),
+                Completion::Repeated { title, next } => {
+                    println!("repeated: {title} (next on {next})")
+                }
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Reschedule {
+            id,
+            scheduled,
+            deadline,
+        }) => {
+            if scheduled.is_none() && deadline.is_none() {
+                bail!("at least one of `--scheduled` and `--deadline` must be given");
+            }
+            let title = reschedule(
+                config.backend.starling()?,
+                resolve(id)?,
+                *scheduled,
+                *deadline,
+            )?;
+            println!("rescheduled: {title}");
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Snooze { id, until }) => {
+            let date = until.resolve(Local::now().date_naive());
+            let title = snooze(config.backend.starling()?, resolve(id)?, date)?;
+            println!("snoozed: {title} (until {date})");
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Capture {
+            title,
+            body,
+            keyword,
+            context,
+            file,
+        }) => {
+            let Some(path) = file.as_ref().or(config.inbox_file.as_ref()) else {
+                bail!("no inbox file to capture into, set `inbox_file` in the config file or pass `--file`");
+            };
+            let id = capture(
+                config.backend.starling()?,
+                path,
+                title,
+                keyword.as_deref(),
+                context,
+                body.as_deref(),
+                Local::now().naive_local(),
+            )?;
+            println!("{id}");
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Clock(ClockCommand::In { id })) => {
+            let title = clock_in(
+                config.backend.starling()?,
+                resolve(id)?,
+                Local::now().naive_local(),
+            )?;
+            println!("clocked in: {title}");
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Clock(ClockCommand::Out)) => {
+            let titles = clock_out(config.backend.starling()?, Local::now().naive_local())?;
+            if titles.is_empty() {
+                println!("no clock running");
+            }
+            for title in titles {
+                println!("clocked out: {title}");
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(
+            Command::Today { .. }
+            | Command::Review { .. }
+            | Command::Show { .. }
+            | Command::Pick { .. },
+        )
+        | None => {}
+    }
+
+    let views = if let Some(Command::Today { date }) = &args.command {
+        today_views(
+            date.unwrap_or_else(|| Local::now().date_naive()),
+            config.week_start,
+        )?
+    } else if let Some(Command::Show { .. }) = &args.command {
+        no_views()?
+    } else if let Some(Command::Pick { view, .. }) = &args.command {
+        pick_views(view, config.week_start)?
+    } else if let Some(Command::Review { date, stale_days }) = &args.command {
+        review_views(
+            date.unwrap_or_else(|| Local::now().date_naive()),
+            *stale_days,
+            config.week_start,
+        )?
+    } else {
+        match args.parse_views(config.week_start)? {
+            Some(views) => views,
+            // This means the user asked for the help message about views, and we should terminate
+            // (it's already been printed)
+            None => return Ok(ExitCode::SUCCESS),
+        }
+    };
+
+    let fetched = fetch_items(&config, &views)?;
+
+    if let Some(Command::Show { id, text }) = &args.command {
+        let id = resolve_id(id, &config.aliases, || Ok(fetched.node_ids.clone()))?;
+        let short_id = short_ids(fetched.node_ids).remove(&id).unwrap_or_default();
+        let details = ItemDetails::new(id, short_id, &fetched.action_items)?;
+        if *text {
+            print!("{}", details.to_text());
+        } else {
+            println!("{}", serde_json::to_string(&details)?);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // The cache is only used for shell completions, so failing to update it shouldn't stop the run
+    if let Err(err) = update_cache(&fetched.action_items, views.names()) {
+        warn(&format!("failed to update completion cache: {err:#}"));
+    }
+
+    // If we're only emitting what's changed, we need to know what was emitted last time
+    let mut changes = config
+        .changed_since
+        .as_deref()
+        .map(Changes::load)
+        .transpose()?;
+
+    let output = build_output(&config, views, fetched, &mut changes, generated_at, started)?;
+    if let Some(Command::Pick { json, .. }) = &args.command {
+        let Some(item) = pick_item(&output.views, &config.picker)? else {
+            return Ok(ExitCode::from(EMPTY_EXIT_CODE));
+        };
+        if *json {
+            println!("{item}");
+        } else {
+            println!("{}", item["id"].as_str().unwrap());
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    let all_empty = output
+        .views
+        .values()
+        .all(|sections| sections.iter().all(Section::is_empty));
+    // Serialise straight into stdout rather than building the whole document in memory first,
+    // which would double peak memory for large outputs (writes will block if the reader is slow)
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    match config.encoding {
+        Encoding::Bincode => {
+            bincode::serialize_into(&mut stdout, &output)?;
+        }
+        Encoding::Json => {
+            serde_json::to_writer(&mut stdout, &output)?;
+            writeln!(stdout)?;
+        }
+    };
+    stdout.flush()?;
+    // Notifications and MQTT messages go out after the output, but before the state is saved, so
+    // that if they fail, the items will be picked up again on the next run
+    #[cfg(feature = "notifications")]
+    send_notifications(&output.views, &config.notifications)?;
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt) = &config.mqtt {
+        publish_views(&output.views, mqtt)?;
+    }
+    // Only update the state once the output has been written, so a failed run doesn't lose
+    // anything
+    if let (Some(changes), Some(path)) = (&changes, &config.changed_since) {
+        changes.save(path)?;
+    }
+
+    if config.fail_if_empty && all_empty {
+        return Ok(ExitCode::from(EMPTY_EXIT_CODE));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Fetches the action items the given views need from Starling and normalises them (see
+/// [`prepare_items`]).
+fn fetch_items(config: &Config, views: &AllViews) -> Result<Fetched> {
+    prepare_items(config, views, stream_nodes(config)?)
+}
+
+/// Fetches the raw action items from Starling, with everything any view might need.
+#[cfg(feature = "web")]
+fn fetch_nodes(config: &Config) -> Result<Vec<Node>> {
+    stream_nodes(config)?.collect()
+}
+
+/// Starts fetching the raw action items from the configured source, with everything any view might
+/// need, so they can be processed as they're decoded.
+fn stream_nodes(config: &Config) -> Result<NodeStream> {
+    config.backend.action_items(NodeOptions {
+        body: true,
+        metadata: true,
+        children: true,
+        connections: false,
+        child_connections: false,
+        conn_format: Format::Markdown,
+    })
+}
+
+/// Fetches the candidates for the given inbox view: the top-level items in its file, or the items
+/// under headings with its tag if there's no file for it. Finding those headings by their tag only
+/// works without Starling, as Starling only looks for tags among its action items, which plain
+/// headings aren't.
+fn fetch_inbox_nodes(config: &Config, filter: &InboxFilter) -> Result<Vec<Node>> {
+    let opts = NodeOptions {
+        body: true,
+        metadata: true,
+        children: true,
+        connections: false,
+        child_connections: false,
+        conn_format: Format::Markdown,
+    };
+    let file = filter.file.clone().or_else(|| {
+        config
+            .inbox_file
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned())
+    });
+    let parents = match file {
+        Some(path) => vec![config.backend.node(config.backend.root_id(&path)?, opts)?],
+        None if config.backend.starling().is_ok() => bail!(
+            "inbox views need a file with starling, set `inbox_file` in the config file or pass \
+             `--file`"
+        ),
+        None => config.backend.tagged(&filter.tag, opts)?,
+    };
+
+    let child_ids = parents
+        .iter()
+        .flat_map(|parent| parent.children.iter().map(|(id, _)| *id))
+        .collect::<Vec<_>>();
+    config.backend.nodes(&child_ids, opts)
+}
+
+/// Normalises the given raw action items for the given views, expanding repeating timestamps up
+/// to the right buffer after the last date in the views. Invalid items are dropped, failing unless
+/// the config is lenient. The raw action items are processed one at a time, so they can be
+/// streamed in as they're fetched.
+fn prepare_items(
+    config: &Config,
+    views: &AllViews,
+    raw_nodes: impl IntoIterator<Item = Result<Node>>,
+) -> Result<Fetched> {
+    // Each type of item gets its own buffer after the last date in the views
+    let last_date = views.last_date.unwrap_or_else(|| Local::now().date_naive());
+    let expand_until = |node: &Node| last_date + **config.repeat_buffers.for_node(node);
+
+    let needs_done_items =
+        !views.boards.is_empty() || !views.completeds.is_empty() || !views.velocities.is_empty();
+    let mut done_items = Vec::new();
+    let mut time_log = TimeLogBuilder::default();
+    let mut node_ids = Vec::new();
+    let mut fetch_error = None;
+    let mut errors = Vec::new();
+    // Events from external calendars are treated like any others
+    #[cfg(feature = "ical")]
+    let raw_nodes = {
+        let calendar_nodes = read_calendar_nodes(&config.calendars, &mut errors)?;
+        raw_nodes
+            .into_iter()
+            .chain(calendar_nodes.into_iter().map(Ok))
+    };
+    let raw_nodes = raw_nodes
+        .into_iter()
+        // Stop at the first node that couldn't be fetched, and fail once normalisation is done
+        .map_while(|node| node.map_err(|err| fetch_error = Some(err)).ok())
+        // Completed items are dropped in normalisation, so pick out any we need first (time
+        // tracked on them still counts too), and short IDs need to be unambiguous against them
+        .inspect(|node| {
+            if needs_done_items {
+                done_items.extend(DoneItem::from_node(
+                    node,
+                    &config.done_keywords,
+                    &config.areas,
+                ));
+            }
+            if !views.time_reports.is_empty() {
+                time_log.add(node, &config.areas);
+            }
+            node_ids.push(node.id);
+        });
+    let mut action_items = normalize_action_items(
+        raw_nodes,
+        &config.done_keywords,
+        &config.areas,
+        expand_until,
+        |date| config.deadline_time(date),
+        config.default_advance.as_deref().copied(),
+        &mut errors,
+    );
+    if let Some(err) = fetch_error {
+        return Err(err);
+    }
+    let time_log = time_log.finish();
+    let num_nodes = node_ids.len();
+    // Drop anything the extractors would fail on up-front, so none of the views have to deal with
+    // errors, and so every problem can be reported at once if we aren't being lenient
+    errors.extend(remove_invalid_items(&mut action_items));
+    check_errors(config, &errors)?;
+
+    Ok(Fetched {
+        last_date,
+        action_items,
+        done_items,
+        time_log,
+        node_ids,
+        num_nodes,
+        errors,
+    })
+}
+
+/// Fails with every one of the given errors if the config isn't lenient, and otherwise warns that
+/// the items they're about were skipped.
+fn check_errors(config: &Config, errors: &[ItemError]) -> Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    if !config.lenient {
+        let details = errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(anyhow!(details).context(Failure::InvalidData));
+    }
+    warn(&format!(
+        "skipped {} item(s) that couldn't be processed",
+        errors.len()
+    ));
+    Ok(())
+}
+
+/// Produces the output for the given views from the action items fetched for them. If `changes`
+/// is given, views of items will only contain those that have changed since it was recorded.
+fn build_output(
+    config: &Config,
+    mut views: AllViews,
+    fetched: Fetched,
+    changes: &mut Option<Changes>,
+    generated_at: DateTime<Local>,
+    started: Instant,
+) -> Result<Output> {
+    let Fetched {
+        last_date,
+        action_items,
+        done_items,
+        time_log,
+        node_ids: _,
+        num_nodes,
+        mut errors,
+    } = fetched;
+    // Plugins only run for the views named after them, which have to be noted before the views
+    // are taken apart
+    #[cfg(feature = "plugins")]
+    let plugin_views = views.names().cloned().collect::<HashSet<_>>();
+    #[cfg(feature = "plugins")]
+    if let Some((view_name, _)) = views
+        .plugins
+        .iter()
+        .find(|(view_name, _)| !config.plugins.contains_key(view_name))
+    {
+        bail!("view `{view_name}` is for a plugin, but there's no plugin with that name");
+    }
+
+    // Holidays come from Starling and the holidays calendar, and are used across several views
+    #[cfg_attr(not(feature = "ical"), allow(unused_mut))]
+    let mut holidays = action_items
+        .values()
+        .flat_map(|item| Holiday::from_action_item(item, &action_items))
+        .collect::<Result<Vec<_>>>()?;
+    #[cfg(feature = "ical")]
+    if let Some(path) = &config.holidays_calendar {
+        holidays.extend(read_ics_holidays(path)?);
+    }
+    holidays.sort_unstable_by_key(Holiday::sort_key);
+    let holiday_dates = holidays
+        .iter()
+        .map(|holiday| holiday.date)
+        .collect::<HashSet<_>>();
 
     macro_rules! handle_items {
         ($ItemType:ty, $variant:ident, $views:expr, $views_data:expr) => {{
@@ -62,8 +608,23 @@ fn main() -> Result<()> {
                 })?
                 .into_iter()
                 .try_for_each(|(view_name, mut items)| {
-                    // Sort items by the key
+                    // Put items in a fixed order by their keys, sort them however the view asks
+                    // (or the usual way for their type), cut them down to the requested page, and
+                    // group them if the view asks for that
                     items.sort_unstable_by_key(<$ItemType>::sort_key);
+                    let mut group_by = None;
+                    if let Some((_, filter)) = $views.iter().find(|(name, _)| *name == view_name) {
+                        filter.sorting.apply(&mut items);
+                        filter.paging.apply(&mut items);
+                        if let Some(changes) = changes.as_mut() {
+                            items = changes.filter(&view_name, items)?;
+                        }
+                        group_by = filter.group_by;
+                    }
+                    let listing = match group_by {
+                        Some(by) => Listing::Grouped(group(items, by)),
+                        None => Listing::Flat(items),
+                    };
 
                     // Get the entry for this view (inserting if needed), and add the data for this
                     // type of item in (if already present, fail)
@@ -73,7 +634,7 @@ fn main() -> Result<()> {
                     if view_data.$variant.is_some() {
                         bail!("view `{}` has two filters the same type", view_name);
                     }
-                    view_data.$variant = Some(items);
+                    view_data.$variant = Some(listing);
 
                     Ok(())
                 })?;
@@ -107,7 +668,36 @@ fn main() -> Result<()> {
             TasksFilter::for_target_contexts(filter),
         ));
     }
+    // And the same for Eisenhower matrices
+    let mut eisenhower_view_names = Vec::with_capacity(views.eisenhowers.len());
+    for (name, filter) in &views.eisenhowers {
+        let interim_task_filter_name = format!("__interim_eisenhower__{name}");
+        eisenhower_view_names.push(interim_task_filter_name.clone());
+        views.tasks.push((
+            interim_task_filter_name,
+            TasksFilter::for_eisenhower(filter),
+        ));
+    }
+    // And random picks
+    let mut pick_view_names = Vec::with_capacity(views.picks.len());
+    for (name, filter) in &views.picks {
+        let interim_task_filter_name = format!("__interim_pick__{name}");
+        pick_view_names.push(interim_task_filter_name.clone());
+        views
+            .tasks
+            .push((interim_task_filter_name, TasksFilter::for_pick(filter)));
+    }
     handle_items!(Task, tasks, &views.tasks, views_data);
+    // Ages are measured from the date each view gives, so the same tasks come out the same way on
+    // every run
+    for (name, filter) in &views.tasks {
+        if let Some(listing) = views_data
+            .get_mut(name)
+            .and_then(|data| data.tasks.as_mut())
+        {
+            listing.for_each_mut(|task| task.age_days = filter.age(task));
+        }
+    }
 
     // Now go through the target contexts and accumulate
     for (interim_name, (name, filter)) in target_context_view_names
@@ -116,64 +706,428 @@ fn main() -> Result<()> {
     {
         // For each target contexts view, we inserted an extra view for the tasks that extracts
         // only those tasks with deadlines on or before the `until` date, so get those now. This is
-        // guaranteed to exist, and of course to contain tasks.
-        let relevant_tasks = views_data
-            .remove(interim_name)
-            .unwrap()
-            .tasks
-            .take()
-            .unwrap();
-
-        // Accumulate a map of contexts, and under each one have the tasks that need to be done in
-        // that context by the deadline date. Because these were sorted in the original task
-        // filtering system and we iterate in that order, they'll be in the correct order in each
-        // context bucket.
-        let mut target_contexts = HashMap::new();
-        for task in relevant_tasks {
-            if filter.first_context_only && !task.contexts.is_empty() {
-                // Get the alphabetically first context (until `orgish` supports tag ordering...)
-                let first_context = task
-                    .contexts
-                    .iter()
-                    .min()
-                    // Guaranteed to be non-empty by the above check
-                    .unwrap();
-                target_contexts
-                    .entry(first_context.clone())
-                    .or_insert_with(Vec::new)
-                    .push(task.clone());
-            } else {
-                for context in &task.contexts {
-                    target_contexts
-                        .entry(context.clone())
-                        .or_insert_with(Vec::new)
-                        .push(task.clone());
-                }
-            }
-            // If a task has no contexts, that's a special area
-            if task.contexts.is_empty() {
-                target_contexts
-                    .entry(String::new())
+        // guaranteed to exist, and of course to contain tasks (which won't be grouped, because
+        // target contexts views can't ask for that).
+        let Some(Listing::Flat(relevant_tasks)) = views_data.remove(interim_name).unwrap().tasks
+        else {
+            unreachable!()
+        };
+
+        let entry = views_data
+            .entry(name.to_string())
+            .or_insert_with(ViewData::default);
+        if entry.target_contexts.is_some() || entry.area_target_contexts.is_some() {
+            bail!("view `{}` has two filters the same type", name);
+        }
+        if filter.by_area {
+            // Split the tasks up by area, keeping their order within each one
+            let mut areas = HashMap::new();
+            for task in relevant_tasks {
+                areas
+                    .entry(task.area.clone().unwrap_or_default())
                     .or_insert_with(Vec::new)
-                    .push(task.clone());
+                    .push(task);
             }
+            let area_target_contexts = areas
+                .into_iter()
+                .map(|(area, tasks)| (area, sort_into_contexts(tasks, filter.first_context_only)))
+                .collect();
+            entry.area_target_contexts = Some(area_target_contexts);
+        } else {
+            entry.target_contexts = Some(sort_into_contexts(
+                relevant_tasks,
+                filter.first_context_only,
+            ));
         }
+    }
+
+    // Sort the tasks relevant to each Eisenhower matrix into its quadrants
+    for (interim_name, (name, filter)) in eisenhower_view_names.iter().zip(views.eisenhowers.iter())
+    {
+        // As with target contexts, these are guaranteed to exist and not be grouped
+        let Some(Listing::Flat(relevant_tasks)) = views_data.remove(interim_name).unwrap().tasks
+        else {
+            unreachable!()
+        };
+        let matrix = EisenhowerMatrix::from_tasks(
+            relevant_tasks,
+            filter.urgent_until(),
+            filter.important_priority,
+        );
 
         let entry = views_data
             .entry(name.to_string())
             .or_insert_with(ViewData::default);
-        if entry.target_contexts.is_some() {
+        if entry.eisenhower.is_some() {
             bail!("view `{}` has two filters the same type", name);
         }
-        entry.target_contexts = Some(target_contexts);
+        entry.eisenhower = Some(matrix);
+    }
+    for (interim_name, (name, filter)) in pick_view_names.iter().zip(views.picks.iter()) {
+        // As with target contexts, these are guaranteed to exist and not be grouped
+        let Some(Listing::Flat(relevant_tasks)) = views_data.remove(interim_name).unwrap().tasks
+        else {
+            unreachable!()
+        };
+        let picked = pick_tasks(relevant_tasks, filter.date, filter.count, filter.seed);
+
+        let entry = views_data
+            .entry(name.to_string())
+            .or_insert_with(ViewData::default);
+        if entry.pick.is_some() {
+            bail!("view `{}` has two filters the same type", name);
+        }
+        entry.pick = Some(picked);
+    }
+
+    for (view_name, filter) in &views.boards {
+        let mut board = build_board(&action_items, &done_items, filter.done_since)?;
+        filter.paging.apply(&mut board);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.board.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.board = Some(board);
+    }
+    for (view_name, filter) in &views.completeds {
+        let mut periods = build_completed(
+            &done_items,
+            filter.from,
+            filter.until,
+            filter.weekly.then_some(config.week_start),
+            filter.include_cancelled,
+        );
+        filter.paging.apply(&mut periods);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.completed.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.completed = Some(periods);
+    }
+    for (view_name, filter) in &views.velocities {
+        let today = Local::now().date_naive();
+        let mut velocity = build_velocity(
+            &action_items,
+            &done_items,
+            filter.from,
+            filter.until,
+            today,
+            config.week_start,
+        )?;
+        filter.paging.apply(&mut velocity.forecast);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.velocity.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.velocity = Some(velocity);
+    }
+
+    for (view_name, filter) in &views.timelines {
+        let mut timeline = build_timeline(&action_items, filter.from, filter.until)?;
+        filter.paging.apply(&mut timeline.stacks);
+        if filter.mermaid {
+            timeline.add_mermaid();
+        }
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.timeline.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.timeline = Some(timeline);
+    }
+    for (view_name, filter) in &views.overdues {
+        let mut overdue = build_overdue(&action_items, filter.date)?;
+        filter.paging.apply(&mut overdue);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.overdue.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.overdue = Some(overdue);
+    }
+
+    for (view_name, filter) in &views.clocks {
+        let now = Local::now().naive_local();
+        let clock = build_clock(&action_items, filter.date.unwrap_or(now.date()), now);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.clock.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.clock = Some(clock);
+    }
+
+    for (view_name, filter) in &views.time_reports {
+        let mut report = build_time_report(
+            &time_log,
+            filter.from,
+            filter.until,
+            Local::now().naive_local(),
+        );
+        filter.paging.apply(&mut report.tasks);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.time_report.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.time_report = Some(report);
+    }
+
+    for (view_name, filter) in &views.inboxes {
+        // These are normalised separately from the action items, so they need their own check
+        let mut inbox_errors = Vec::new();
+        let candidates = normalize_action_items(
+            fetch_inbox_nodes(config, filter)?,
+            &config.done_keywords,
+            &config.areas,
+            |_| last_date,
+            |date| config.deadline_time(date),
+            config.default_advance.as_deref().copied(),
+            &mut inbox_errors,
+        );
+        check_errors(config, &inbox_errors)?;
+        errors.extend(inbox_errors);
+        let mut items = candidates
+            .values()
+            .filter_map(|item| InboxItem::from_action_item(item, &action_items))
+            .collect::<Vec<_>>();
+        items.sort_unstable_by_key(InboxItem::sort_key);
+        filter.paging.apply(&mut items);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.inbox.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.inbox = Some(items);
+    }
+
+    for (view_name, filter) in &views.free_slots {
+        let busy = busy_intervals(
+            &action_items,
+            filter.from,
+            filter.until,
+            filter.include_tasks,
+        )?;
+        // Explicit hours apply to every day but holidays, otherwise we use the configured working
+        // hours, and fall back to nine-to-five if there aren't any
+        let hours =
+            |date: NaiveDate| match (filter.day_start, filter.day_end, &config.working_hours) {
+                _ if holiday_dates.contains(&date) => None,
+                (Some(start), Some(end), _) => Some((*start, *end)),
+                (_, _, Some(working_hours)) => working_hours
+                    .on(date.weekday())
+                    .map(|hours| (*hours.start, *hours.end)),
+                _ => Some((
+                    NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                    NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+                )),
+            };
+        let mut slots = free_slots(
+            &busy,
+            hours,
+            chrono::Duration::minutes(filter.min_minutes as i64),
+        );
+        filter.paging.apply(&mut slots);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.free_slots.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.free_slots = Some(slots);
+    }
+    for (view_name, filter) in &views.busies {
+        let mut busy = build_busy(
+            &action_items,
+            filter.from,
+            filter.until,
+            filter.include_tasks,
+            filter.granularity,
+        )?;
+        filter.paging.apply(&mut busy);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.busy.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.busy = Some(busy);
+    }
+    for (view_name, filter) in &views.travels {
+        let mut conflicts = find_travel_conflicts(
+            &action_items,
+            filter.from,
+            filter.until,
+            &config.travel_times,
+        )?;
+        filter.paging.apply(&mut conflicts);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.travel.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.travel = Some(conflicts);
+    }
+    for (view_name, filter) in &views.capacities {
+        let capacity = |date: NaiveDate| {
+            config.capacity_minutes(date, holiday_dates.contains(&date), filter.hours)
+        };
+        // Events only take time out of working hours, if there are any
+        let busy = if filter.include_events {
+            busy_intervals(&action_items, filter.from, filter.until, false)?
+        } else {
+            BTreeMap::new()
+        };
+        let event_minutes = |date: NaiveDate| {
+            let Some(intervals) = busy.get(&date) else {
+                return 0;
+            };
+            let hours = config.working_hours.as_ref().map(|working_hours| {
+                working_hours
+                    .on(date.weekday())
+                    .map_or((NaiveTime::MIN, NaiveTime::MIN), |hours| {
+                        (*hours.start, *hours.end)
+                    })
+            });
+            busy_minutes(date, intervals, hours)
+        };
+        let mut days = build_capacity(
+            &action_items,
+            filter.from,
+            filter.until,
+            capacity,
+            event_minutes,
+            filter.spread,
+        )?;
+        // Weeks are totalled before we cut out any days
+        let weeks = filter
+            .weekly
+            .then(|| weekly_capacity(&days, config.week_start));
+        if filter.over_only {
+            days.retain(|day| day.excess_minutes > 0);
+        }
+        filter.paging.apply(&mut days);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.capacity.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.capacity = Some(days);
+        entry.capacity_weeks = weeks;
+    }
+
+    // Searches score every action item against their query, so they don't fit the usual
+    // filter-then-sort approach
+    for (view_name, filter) in &views.searches {
+        let terms = filter.terms()?;
+        let mut hits = action_items
+            .values()
+            .filter_map(|item| SearchHit::from_action_item(item, &terms))
+            .collect::<Vec<_>>();
+        hits.sort_unstable_by_key(SearchHit::sort_key);
+        filter.paging.apply(&mut hits);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.search.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.search = Some(hits);
+    }
+
+    // Agendas (and weeks and months, which are built from agendas over fixed ranges) merge several
+    // types of items, so they're built separately too
+    for (view_name, filter) in &views.agendas {
+        let mut agenda = build_agenda(
+            &action_items,
+            filter.from,
+            filter.until,
+            &holidays,
+            filter.travel.then_some(&config.travel_times),
+        )?;
+        filter.paging.apply(&mut agenda);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.agenda.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.agenda = Some(agenda);
+    }
+    for (view_name, filter) in &views.weeks {
+        let (first_day, last_day) = filter.days();
+        let mut week = build_agenda(
+            &action_items,
+            first_day,
+            last_day,
+            &holidays,
+            filter.travel.then_some(&config.travel_times),
+        )?;
+        filter.paging.apply(&mut week);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.week.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.week = Some(week);
+    }
+    for (view_name, filter) in &views.months {
+        let (first, last) = filter.month();
+        let mut month = build_month(&action_items, first, last, filter.week_start(), &holidays)?;
+        filter.paging.apply(&mut month);
+
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.month.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
+        }
+        entry.month = Some(month);
     }
 
     // If the user requested goal views, run that extraction (unrelated to action items, and we
-    // shouldn't do any checking unless they request because it's a very personally-tuned system
-    // that most people will need to fork to get working)
+    // shouldn't do any checking unless they request because it depends entirely on how the goal
+    // types have been configured)
     #[cfg(feature = "goals")]
     for (view_name, filter) in views.goals {
-        let goals = Goals::extract(filter.date, &args.starling_address)?;
+        let mut goals = Goals::extract(
+            filter.date,
+            filter.until(),
+            &config.goals,
+            &config.journal_template,
+            &config.done_keywords,
+            &*config.backend,
+        )?;
+        filter.paging.apply(&mut goals);
 
         let entry = views_data
             .entry(view_name.clone())
@@ -183,41 +1137,406 @@ fn main() -> Result<()> {
         }
         entry.goals = Some(goals);
     }
+    #[cfg(feature = "goals")]
+    for (view_name, filter) in views.alignments {
+        let goals = Goals::extract(
+            filter.date,
+            filter.until(),
+            &config.goals,
+            &config.journal_template,
+            &config.done_keywords,
+            &*config.backend,
+        )?;
+        let mut alignment = Alignment::build(&goals, &action_items, &*config.backend)?;
+        alignment.page(&filter.paging);
 
-    match args.encoding {
-        Encoding::Bincode => {
-            let bytes = bincode::serialize(&views_data)?;
-            std::io::stdout().write_all(&bytes)?;
-            std::io::stdout().flush()?;
+        let entry = views_data
+            .entry(view_name.clone())
+            .or_insert_with(ViewData::default);
+        if entry.alignment.is_some() {
+            bail!("view `{}` has two filters the same type", view_name);
         }
-        Encoding::Json => {
-            println!("{}", serde_json::to_string(&views_data)?);
+        entry.alignment = Some(alignment);
+    }
+
+    // Plugins add their own views (or sections of other views, if they share names)
+    #[cfg(feature = "plugins")]
+    for (name, command) in &config.plugins {
+        if !plugin_views.contains(name) {
+            continue;
         }
+        let items = run_plugin(name, command, &action_items)?;
+
+        let entry = views_data
+            .entry(name.clone())
+            .or_insert_with(ViewData::default);
+        entry.plugin = Some(items);
+    }
+
+    // Every view could have had the skipped items in it, so make sure each one mentions them
+    if !errors.is_empty() {
+        for data in views_data.values_mut() {
+            data.errors = Some(errors.clone());
+        }
+    }
+
+    let meta = Meta {
+        generated_at,
+        starling_address: config.backend.to_string(),
+        last_date,
+        expanded_until: config.repeat_buffers.horizons(last_date),
+        ranges: views.ranges,
+        counts: ItemCounts {
+            nodes: num_nodes,
+            action_items: action_items.len(),
+        },
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    let output = Output {
+        polaris_version: env!("CARGO_PKG_VERSION"),
+        data_model_version: DATA_MODEL_VERSION,
+        meta,
+        views: views_data
+            .into_iter()
+            .map(|(name, data)| (name, data.into_sections()))
+            .collect(),
+        errors,
     };
 
-    Ok(())
+    Ok(output)
 }
 
-/// The final data for a single view, which may contain multiple data types.
-#[derive(Serialize, Default, Debug)]
+/// Accumulates a map of contexts, and under each one has the tasks that need to be done in that
+/// context. Because the tasks were sorted in the original task filtering system and we iterate in
+/// that order, they'll be in the correct order in each context bucket.
+fn sort_into_contexts(tasks: Vec<Task>, first_context_only: bool) -> HashMap<String, Vec<Task>> {
+    let mut target_contexts = HashMap::new();
+    for task in tasks {
+        if first_context_only && !task.contexts.is_empty() {
+            // Get the alphabetically first context (until `orgish` supports tag ordering...)
+            let first_context = task
+                .contexts
+                .iter()
+                .min()
+                // Guaranteed to be non-empty by the above check
+                .unwrap();
+            target_contexts
+                .entry(first_context.clone())
+                .or_insert_with(Vec::new)
+                .push(task.clone());
+        } else {
+            for context in &task.contexts {
+                target_contexts
+                    .entry(context.clone())
+                    .or_insert_with(Vec::new)
+                    .push(task.clone());
+            }
+        }
+        // If a task has no contexts, that's a special area
+        if task.contexts.is_empty() {
+            target_contexts
+                .entry(String::new())
+                .or_insert_with(Vec::new)
+                .push(task.clone());
+        }
+    }
+    target_contexts
+}
+
+/// The action items fetched from Starling for a set of views, along with everything that had to be
+/// picked out of the raw nodes before normalisation.
+struct Fetched {
+    /// The date the repeat buffers were measured from.
+    last_date: NaiveDate,
+    /// The valid, normalised action items.
+    action_items: HashMap<Uuid, ActionItem>,
+    /// The completed items, if any of the views need them.
+    done_items: Vec<DoneItem>,
+    /// The time tracked on every item, if any of the views need it.
+    time_log: Vec<TimeLogEntry>,
+    /// The IDs of every node fetched, including completed ones.
+    node_ids: Vec<Uuid>,
+    /// The number of nodes fetched.
+    num_nodes: usize,
+    /// The items which were skipped because they couldn't be processed.
+    errors: Vec<ItemError>,
+}
+
+/// The complete output of Polaris, containing every view along with version information so
+/// consumers can check they understand the format before parsing the views themselves.
+#[derive(Serialize, Debug)]
+struct Output {
+    /// The version of Polaris that produced this output.
+    polaris_version: &'static str,
+    /// The version of the data model this output follows (see [`DATA_MODEL_VERSION`]).
+    data_model_version: u32,
+    /// Details of how the views were produced.
+    meta: Meta,
+    /// The sections of data in each view, keyed by view name.
+    views: HashMap<String, Vec<Section>>,
+    /// The items which were skipped because they couldn't be processed. This will always be empty
+    /// unless Polaris was run leniently.
+    errors: Vec<ItemError>,
+}
+
+/// Details of how the views in an output were produced, which consumers need to interpret them
+/// correctly (and to work out how fresh they are).
+#[derive(Serialize, Debug)]
+struct Meta {
+    /// When this run started.
+    generated_at: DateTime<Local>,
+    /// The address of the Starling instance the action items were fetched from (or `dir:` and the
+    /// directory they were read from).
+    starling_address: String,
+    /// The date the repeat buffers were measured from: the latest date across all the views, or
+    /// the present date if none of them had dates.
+    last_date: NaiveDate,
+    /// The date up to which repeating items of each type were expanded.
+    expanded_until: ExpansionHorizons,
+    /// The range of dates each view covers, keyed by view name.
+    ranges: BTreeMap<String, DateRange>,
+    /// The numbers of items the views were produced from.
+    counts: ItemCounts,
+    /// The number of milliseconds it took to produce the views (not counting writing them out).
+    elapsed_ms: u64,
+}
+
+/// The numbers of items fetched and processed in a run.
+#[derive(Serialize, Debug)]
+struct ItemCounts {
+    /// The number of nodes fetched from Starling, including completed ones.
+    nodes: usize,
+    /// The number of action items left after normalisation, which drops completed items.
+    action_items: usize,
+}
+
+/// The items in a view, which will be a flat list unless the view asked for them to be grouped.
+/// This is tagged with which it is, since consumers decoding bincode can't tell from the data.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Listing<T> {
+    Flat(Vec<T>),
+    /// A map of group names to the items in each group, which are sorted within the group.
+    Grouped(BTreeMap<String, Vec<T>>),
+}
+impl<T> Listing<T> {
+    /// Determines whether or not there are no items in this listing.
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Flat(items) => items.is_empty(),
+            Self::Grouped(groups) => groups.is_empty(),
+        }
+    }
+
+    /// Calls the given function on every item in this listing, whatever group it's in.
+    fn for_each_mut(&mut self, f: impl FnMut(&mut T)) {
+        match self {
+            Self::Flat(items) => items.iter_mut().for_each(f),
+            Self::Grouped(groups) => groups.values_mut().flatten().for_each(f),
+        }
+    }
+}
+
+/// The data for a single view as it's accumulated, which may contain multiple data types. A view
+/// can only have one filter of each type, so each type gets at most one section.
+#[derive(Default, Debug)]
 struct ViewData {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    events: Option<Vec<Event>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    daily_notes: Option<Vec<DailyNote>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tickles: Option<Vec<Tickle>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    person_dates: Option<Vec<PersonDate>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tasks: Option<Vec<Task>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    stacks: Option<Vec<Stack>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    waitings: Option<Vec<Waiting>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<Listing<Event>>,
+    daily_notes: Option<Listing<DailyNote>>,
+    tickles: Option<Listing<Tickle>>,
+    person_dates: Option<Listing<PersonDate>>,
+    tasks: Option<Listing<Task>>,
+    stacks: Option<Listing<Stack>>,
+    waitings: Option<Listing<Waiting>>,
     target_contexts: Option<HashMap<String, Vec<Task>>>,
+    area_target_contexts: Option<HashMap<String, HashMap<String, Vec<Task>>>>,
+    eisenhower: Option<EisenhowerMatrix>,
+    pick: Option<Vec<Task>>,
+    board: Option<Vec<BoardStack>>,
+    completed: Option<Vec<CompletedPeriod>>,
+    velocity: Option<Velocity>,
+    timeline: Option<Timeline>,
+    overdue: Option<Vec<OverdueItem>>,
+    inbox: Option<Vec<InboxItem>>,
+    clock: Option<ClockSummary>,
+    time_report: Option<TimeReport>,
+    free_slots: Option<Vec<Interval>>,
+    busy: Option<Vec<BusyDay>>,
+    travel: Option<Vec<TravelConflict>>,
+    capacity: Option<Vec<CapacityDay>>,
+    capacity_weeks: Option<Vec<CapacityWeek>>,
+    search: Option<Vec<SearchHit>>,
+    agenda: Option<Vec<AgendaDay>>,
+    week: Option<Vec<AgendaDay>>,
+    month: Option<Vec<Vec<MonthDay>>>,
+    #[cfg(feature = "goals")]
+    goals: Option<Vec<Goals>>,
+    #[cfg(feature = "goals")]
+    alignment: Option<Alignment>,
+    #[cfg(feature = "plugins")]
+    plugin: Option<serde_json::Value>,
+    errors: Option<Vec<ItemError>>,
+}
+impl ViewData {
+    /// Converts this data into the sections that are output for the view, omitting any types of
+    /// data the view didn't ask for.
+    fn into_sections(self) -> Vec<Section> {
+        [
+            self.events.map(Section::Events),
+            self.daily_notes.map(Section::DailyNotes),
+            self.tickles.map(Section::Tickles),
+            self.person_dates.map(Section::PersonDates),
+            self.tasks.map(Section::Tasks),
+            self.stacks.map(Section::Stacks),
+            self.waitings.map(Section::Waitings),
+            self.target_contexts.map(Section::TargetContexts),
+            self.area_target_contexts.map(Section::AreaTargetContexts),
+            self.eisenhower.map(Section::Eisenhower),
+            self.pick.map(Section::Pick),
+            self.board.map(Section::Board),
+            self.completed.map(Section::Completed),
+            self.velocity.map(Section::Velocity),
+            self.timeline.map(Section::Timeline),
+            self.overdue.map(Section::Overdue),
+            self.inbox.map(Section::Inbox),
+            self.clock.map(Section::Clock),
+            self.time_report.map(Section::TimeReport),
+            self.free_slots.map(Section::FreeSlots),
+            self.busy.map(Section::Busy),
+            self.travel.map(Section::Travel),
+            self.capacity.map(Section::Capacity),
+            self.capacity_weeks.map(Section::CapacityWeeks),
+            self.search.map(Section::Search),
+            self.agenda.map(Section::Agenda),
+            self.week.map(Section::Week),
+            self.month.map(Section::Month),
+            #[cfg(feature = "goals")]
+            self.goals.map(Section::Goals),
+            #[cfg(feature = "goals")]
+            self.alignment.map(Section::Alignment),
+            #[cfg(feature = "plugins")]
+            self.plugin.map(Section::Plugin),
+            self.errors.map(Section::Errors),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// A single section of the output for a view, holding one type of data. Each section is
+/// serialized with a `type` tag naming the type of data and an `items` field containing it (e.g.
+/// `{"type": "tasks", "items": [...]}`), so consumers can dispatch on the tag, and skip any types
+/// they don't recognise.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", content = "items", rename_all = "snake_case")]
+enum Section {
+    Events(Listing<Event>),
+    DailyNotes(Listing<DailyNote>),
+    Tickles(Listing<Tickle>),
+    PersonDates(Listing<PersonDate>),
+    Tasks(Listing<Task>),
+    Stacks(Listing<Stack>),
+    Waitings(Listing<Waiting>),
+    TargetContexts(HashMap<String, Vec<Task>>),
+    /// Target contexts split up by area of focus first, for views that set `by_area`.
+    AreaTargetContexts(HashMap<String, HashMap<String, Vec<Task>>>),
+    Eisenhower(EisenhowerMatrix),
+    Pick(Vec<Task>),
+    Board(Vec<BoardStack>),
+    Completed(Vec<CompletedPeriod>),
+    Velocity(Velocity),
+    Timeline(Timeline),
+    Overdue(Vec<OverdueItem>),
+    Inbox(Vec<InboxItem>),
+    Clock(ClockSummary),
+    TimeReport(TimeReport),
+    FreeSlots(Vec<Interval>),
+    Busy(Vec<BusyDay>),
+    Travel(Vec<TravelConflict>),
+    Capacity(Vec<CapacityDay>),
+    CapacityWeeks(Vec<CapacityWeek>),
+    Search(Vec<SearchHit>),
+    Agenda(Vec<AgendaDay>),
+    Week(Vec<AgendaDay>),
+    Month(Vec<Vec<MonthDay>>),
+    /// The items which were skipped because they couldn't be processed, which might otherwise
+    /// have appeared in this view.
+    Errors(Vec<ItemError>),
+    // Sections which depend on features have to come after all the others, so the others are
+    // encoded the same way in bincode whatever features are enabled
     #[cfg(feature = "goals")]
-    #[serde(skip_serializing_if = "Option::is_none")]
-    goals: Option<Goals>,
+    Goals(Vec<Goals>),
+    #[cfg(feature = "goals")]
+    Alignment(Alignment),
+    /// Whatever a plugin produced for this view (see [`crate::plugins`]).
+    #[cfg(feature = "plugins")]
+    Plugin(#[serde(serialize_with = "serialize_plugin_output")] serde_json::Value),
+}
+impl Section {
+    /// Determines whether or not this section has nothing in it. Sections with an entry for every
+    /// day (like agendas) are empty if none of their days have anything on them, and errors
+    /// don't count as anything.
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Events(listing) => listing.is_empty(),
+            Self::DailyNotes(listing) => listing.is_empty(),
+            Self::Tickles(listing) => listing.is_empty(),
+            Self::PersonDates(listing) => listing.is_empty(),
+            Self::Tasks(listing) => listing.is_empty(),
+            Self::Stacks(listing) => listing.is_empty(),
+            Self::Waitings(listing) => listing.is_empty(),
+            Self::TargetContexts(contexts) => contexts.is_empty(),
+            Self::AreaTargetContexts(areas) => areas.is_empty(),
+            Self::Eisenhower(matrix) => {
+                matrix.do_first.is_empty()
+                    && matrix.schedule.is_empty()
+                    && matrix.delegate.is_empty()
+                    && matrix.eliminate.is_empty()
+            }
+            Self::Pick(tasks) => tasks.is_empty(),
+            Self::Board(stacks) => stacks.is_empty(),
+            Self::Completed(periods) => periods.iter().all(|period| period.items.is_empty()),
+            Self::Velocity(velocity) => {
+                velocity.weeks.iter().all(|week| week.items == 0) && velocity.forecast.is_empty()
+            }
+            Self::Timeline(timeline) => timeline.stacks.is_empty(),
+            Self::Overdue(items) => items.is_empty(),
+            Self::Inbox(items) => items.is_empty(),
+            Self::Clock(clock) => clock.current.is_none() && clock.items.is_empty(),
+            Self::TimeReport(report) => report.tasks.is_empty(),
+            Self::FreeSlots(slots) => slots.is_empty(),
+            Self::Busy(days) => days.iter().all(|day| day.busy.is_empty()),
+            Self::Travel(conflicts) => conflicts.is_empty(),
+            Self::Capacity(days) => days.iter().all(|day| day.tasks.is_empty()),
+            Self::CapacityWeeks(weeks) => weeks.iter().all(|week| week.load_minutes == 0),
+            Self::Search(hits) => hits.is_empty(),
+            Self::Agenda(days) | Self::Week(days) => days.iter().all(|day| day.items.is_empty()),
+            Self::Month(weeks) => weeks.iter().flatten().all(|day| {
+                day.events == 0 && day.tasks == 0 && day.deadlines == 0 && day.highlights.is_empty()
+            }),
+            #[cfg(feature = "goals")]
+            Self::Goals(days) => days.iter().all(Goals::is_empty),
+            #[cfg(feature = "goals")]
+            Self::Alignment(alignment) => alignment.is_empty(),
+            #[cfg(feature = "plugins")]
+            Self::Plugin(items) => items.is_null() || items.as_array().is_some_and(Vec::is_empty),
+            Self::Errors(_) => true,
+        }
+    }
+}
+
+/// Serializes a plugin's output as it is for human-readable formats like JSON, and as a string of
+/// JSON otherwise, since formats like bincode can't decode values whose shape isn't known ahead of
+/// time.
+#[cfg(feature = "plugins")]
+fn serialize_plugin_output<S: serde::Serializer>(
+    value: &serde_json::Value,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        value.serialize(serializer)
+    } else {
+        serializer.serialize_str(&value.to_string())
+    }
 }
@@ -0,0 +1,217 @@
+//! Workload metrics in the Prometheus text format, for charting over time (e.g. in Grafana).
+//! These are worked out from the same nodes the server builds its views from.
+
+use crate::config::Config;
+#[cfg(feature = "ical")]
+use crate::extractors::read_ics_holidays;
+use crate::extractors::{build_capacity, build_overdue, Holiday, Waiting, STALE_WAIT_DAYS};
+use crate::parse::{ActionItem, Node, Priority};
+use crate::views::AllViews;
+use crate::{prepare_items, Fetched};
+use anyhow::Result;
+use chrono::{Duration, Local, NaiveDate};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Instant;
+
+/// A snapshot of the user's workload.
+#[derive(Debug)]
+pub struct Metrics {
+    /// The number of raw nodes fetched from Starling.
+    nodes: usize,
+    /// The number of valid action items.
+    action_items: usize,
+    /// The number of action items which couldn't be processed.
+    invalid_items: usize,
+    /// The number of open tasks at each (effective) priority.
+    open_tasks: BTreeMap<Priority, usize>,
+    /// The number of tasks, stacks, and waiting items past their deadlines.
+    overdue: usize,
+    /// The number of waiting items sent more than [`STALE_WAIT_DAYS`] ago, with nothing to
+    /// resurface them.
+    stale_waits: usize,
+    /// The estimated minutes of work planned for today.
+    load_minutes: u32,
+    /// The minutes available for work today.
+    capacity_minutes: u32,
+    /// How long getting the nodes took, in seconds (which will be next to nothing if the server
+    /// had fetched them recently).
+    fetch_seconds: f64,
+    /// How long normalising and extracting took, in seconds.
+    extraction_seconds: f64,
+}
+impl Metrics {
+    /// Collects the metrics from the given raw action items, which took `fetch_seconds` to get.
+    /// These are prepared just as they would be for views (so events from calendars are included,
+    /// and invalid items fail unless the config is lenient), with nothing expanded past today.
+    pub fn collect(config: &Config, raw_nodes: Vec<Node>, fetch_seconds: f64) -> Result<Self> {
+        let today = Local::now().date_naive();
+
+        let started = Instant::now();
+        let views = AllViews {
+            last_date: Some(today),
+            ..AllViews::default()
+        };
+        let Fetched {
+            action_items,
+            num_nodes,
+            errors,
+            ..
+        } = prepare_items(config, &views, raw_nodes.into_iter().map(Ok))?;
+
+        let mut open_tasks = BTreeMap::new();
+        for item in action_items.values() {
+            if let ActionItem::Task {
+                priority,
+                computed_priority,
+                ..
+            } = item
+            {
+                *open_tasks
+                    .entry(computed_priority.unwrap_or(*priority))
+                    .or_insert(0) += 1;
+            }
+        }
+        let overdue = build_overdue(&action_items, today)?.len();
+        let stale_before = today - Duration::days(STALE_WAIT_DAYS.into());
+        let mut stale_waits = 0;
+        for item in action_items.values() {
+            for waiting in Waiting::from_action_item(item, &action_items) {
+                if waiting?.is_stale(stale_before) {
+                    stale_waits += 1;
+                }
+            }
+        }
+
+        // Today's capacity follows the same rules as capacity views, without events
+        let mut is_holiday = false;
+        for item in action_items.values() {
+            for holiday in Holiday::from_action_item(item, &action_items) {
+                is_holiday |= holiday?.date == today;
+            }
+        }
+        #[cfg(feature = "ical")]
+        if let Some(path) = &config.holidays_calendar {
+            is_holiday |= read_ics_holidays(path)?
+                .iter()
+                .any(|holiday| holiday.date == today);
+        }
+        let capacity = |date: NaiveDate| config.capacity_minutes(date, is_holiday, None);
+        let day = build_capacity(&action_items, today, today, capacity, |_| 0, false)?
+            .into_iter()
+            .next()
+            .unwrap();
+
+        Ok(Self {
+            nodes: num_nodes,
+            action_items: action_items.len(),
+            invalid_items: errors.len(),
+            open_tasks,
+            overdue,
+            stale_waits,
+            load_minutes: day.load_minutes,
+            capacity_minutes: day.capacity_minutes,
+            fetch_seconds,
+            extraction_seconds: started.elapsed().as_secs_f64(),
+        })
+    }
+
+    /// Renders these metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        // Writing to a string can't fail
+        let mut gauge = |name: &str, help: &str, samples: &[(String, f64)]| {
+            writeln!(out, "# HELP polaris_{name} {help}").unwrap();
+            writeln!(out, "# TYPE polaris_{name} gauge").unwrap();
+            for (labels, value) in samples {
+                // Prometheus spells infinity its own way
+                if value.is_infinite() {
+                    writeln!(out, "polaris_{name}{labels} +Inf").unwrap();
+                } else {
+                    writeln!(out, "polaris_{name}{labels} {value}").unwrap();
+                }
+            }
+        };
+
+        gauge(
+            "nodes",
+            "Raw nodes fetched from Starling.",
+            &[(String::new(), self.nodes as f64)],
+        );
+        gauge(
+            "action_items",
+            "Valid action items.",
+            &[(String::new(), self.action_items as f64)],
+        );
+        gauge(
+            "invalid_items",
+            "Action items which couldn't be processed.",
+            &[(String::new(), self.invalid_items as f64)],
+        );
+        // Every priority gets a sample, even with no tasks, so series don't disappear
+        let open_tasks = Priority::value_variants()
+            .iter()
+            .map(|priority| {
+                let name = priority.to_possible_value().unwrap().get_name().to_string();
+                let count = self.open_tasks.get(priority).copied().unwrap_or(0);
+                (format!("{{priority=\"{name}\"}}"), count as f64)
+            })
+            .collect::<Vec<_>>();
+        gauge(
+            "open_tasks",
+            "Open tasks by effective priority.",
+            &open_tasks,
+        );
+        gauge(
+            "overdue_items",
+            "Tasks, stacks, and waiting items past their deadlines.",
+            &[(String::new(), self.overdue as f64)],
+        );
+        gauge(
+            "stale_waits",
+            "Waiting items sent too long ago with nothing to resurface them.",
+            &[(
+                format!("{{older_than_days=\"{STALE_WAIT_DAYS}\"}}"),
+                self.stale_waits as f64,
+            )],
+        );
+        gauge(
+            "load_minutes_today",
+            "Estimated minutes of work planned for today.",
+            &[(String::new(), self.load_minutes as f64)],
+        );
+        gauge(
+            "capacity_minutes_today",
+            "Minutes available for work today.",
+            &[(String::new(), self.capacity_minutes as f64)],
+        );
+        // Above one means there's more planned than there's time for
+        let crunch = if self.capacity_minutes == 0 {
+            if self.load_minutes == 0 {
+                0.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            self.load_minutes as f64 / self.capacity_minutes as f64
+        };
+        gauge(
+            "crunch_score_today",
+            "Today's planned load as a fraction of its capacity.",
+            &[(String::new(), crunch)],
+        );
+        gauge(
+            "fetch_seconds",
+            "Time taken to fetch from Starling.",
+            &[(String::new(), self.fetch_seconds)],
+        );
+        gauge(
+            "extraction_seconds",
+            "Time taken to normalise and extract the action items.",
+            &[(String::new(), self.extraction_seconds)],
+        );
+
+        out
+    }
+}
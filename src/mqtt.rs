@@ -0,0 +1,309 @@
+//! Publishing of views to an MQTT broker, for home-automation consumers (e.g. a wall display
+//! subscribed to `polaris/agenda/today`). This is set under `mqtt` in the config file:
+//!
+//! ```json
+//! "mqtt": {
+//!     "broker": "localhost:1883",
+//!     "topics": { "today": "polaris/agenda/today" }
+//! }
+//! ```
+//!
+//! Each view with a topic is published there as JSON (retained by default, so new subscribers get
+//! the latest data straight away). Whenever a view's contents differ from what was published for
+//! it last time, an event is also published to `<topic>/changed`, so consumers that only care
+//! about changes don't need to compare anything themselves.
+//!
+//! Only as much of MQTT 3.1.1 as is needed to publish at QoS 0 over plain TCP is implemented
+//! here, which avoids pulling in a whole client for a handful of messages.
+
+use crate::config::cache_dir;
+use crate::state::content_hash;
+use crate::warnings::warn;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long to wait for the broker before giving up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The options for publishing to an MQTT broker.
+#[derive(Deserialize, Clone, Debug)]
+pub struct MqttConfig {
+    /// The address of the broker (e.g. `localhost:1883`).
+    pub broker: String,
+    /// The client ID to connect with.
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    /// The username to connect with, if the broker needs one.
+    pub username: Option<String>,
+    /// The password to connect with, if the broker needs one. MQTT 3.1.1 doesn't allow a password
+    /// without a username, so this needs `username` too.
+    pub password: Option<String>,
+    /// Whether or not view data should be retained by the broker.
+    #[serde(default = "default_retain")]
+    pub retain: bool,
+    /// The topic to publish each view to, by view name. Views without topics aren't published.
+    pub topics: BTreeMap<String, String>,
+}
+impl MqttConfig {
+    /// Makes sure there isn't a password without a username, which brokers would reject.
+    pub fn validate(&self) -> Result<()> {
+        if self.password.is_some() && self.username.is_none() {
+            bail!("an mqtt password can only be given with a username");
+        }
+        Ok(())
+    }
+}
+
+/// Publishes each of the given (produced) views which has a topic to the broker, along with
+/// change events for those whose contents have changed since they were last published. This
+/// returns the number of views published.
+pub fn publish_views(
+    views: &HashMap<String, impl Serialize>,
+    config: &MqttConfig,
+) -> Result<usize> {
+    let hashes_path = hashes_path();
+    let mut hashes: BTreeMap<String, String> = hashes_path
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    let mut client = Client::connect(config)?;
+    let mut published = 0;
+    for (view, topic) in &config.topics {
+        let Some(sections) = views.get(view) else {
+            continue;
+        };
+        client.publish(topic, &serde_json::to_vec(sections)?, config.retain)?;
+        published += 1;
+
+        let hash = content_hash(sections)?;
+        if hashes.get(view) != Some(&hash) {
+            let event = serde_json::json!({ "view": view, "topic": topic });
+            client.publish(
+                &format!("{topic}/changed"),
+                &serde_json::to_vec(&event)?,
+                false,
+            )?;
+            hashes.insert(view.clone(), hash);
+        }
+    }
+    client.disconnect()?;
+
+    // Losing these only means some extra change events next time, so this isn't fatal
+    if let Some(path) = hashes_path {
+        let contents = serde_json::to_string(&hashes)?;
+        let res = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, contents));
+        if let Err(err) = res {
+            warn(&format!(
+                "failed to save mqtt state {}: {err}",
+                path.display()
+            ));
+        }
+    }
+
+    Ok(published)
+}
+
+/// A connection to an MQTT broker.
+struct Client {
+    stream: TcpStream,
+    broker: String,
+}
+impl Client {
+    /// Connects to the broker in the given configuration, waiting for it to accept the
+    /// connection.
+    fn connect(config: &MqttConfig) -> Result<Self> {
+        let stream = TcpStream::connect(&config.broker)
+            .with_context(|| format!("failed to connect to mqtt broker {}", config.broker))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut client = Self {
+            stream,
+            broker: config.broker.clone(),
+        };
+
+        // Protocol name and level (4 is 3.1.1), then the flags, and a keep-alive of a minute
+        let mut body = Vec::new();
+        push_str(&mut body, "MQTT")?;
+        body.push(4);
+        let mut flags = 0x02; // Clean session
+        if config.username.is_some() {
+            flags |= 0x80;
+        }
+        if config.password.is_some() {
+            flags |= 0x40;
+        }
+        body.push(flags);
+        body.extend_from_slice(&60u16.to_be_bytes());
+        push_str(&mut body, &config.client_id)?;
+        if let Some(username) = &config.username {
+            push_str(&mut body, username)?;
+        }
+        if let Some(password) = &config.password {
+            push_str(&mut body, password)?;
+        }
+        client.send(0x10, &body)?;
+
+        let mut connack = [0; 4];
+        client
+            .stream
+            .read_exact(&mut connack)
+            .with_context(|| format!("mqtt broker {} didn't accept connection", client.broker))?;
+        if connack[0] != 0x20 {
+            bail!(
+                "mqtt broker {} sent an unexpected response to connecting",
+                client.broker
+            );
+        }
+        match connack[3] {
+            0 => Ok(client),
+            4 | 5 => bail!("mqtt broker {} rejected the credentials", client.broker),
+            code => bail!(
+                "mqtt broker {} refused the connection with code {code}",
+                client.broker
+            ),
+        }
+    }
+
+    /// Publishes the given payload to the given topic at QoS 0.
+    fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> Result<()> {
+        let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+        push_str(&mut body, topic)?;
+        body.extend_from_slice(payload);
+        self.send(0x30 | u8::from(retain), &body)
+            .with_context(|| format!("failed to publish to mqtt topic {topic}"))
+    }
+
+    /// Disconnects cleanly from the broker.
+    fn disconnect(mut self) -> Result<()> {
+        self.send(0xe0, &[])
+    }
+
+    /// Sends a single packet with the given first header byte and body.
+    fn send(&mut self, header: u8, body: &[u8]) -> Result<()> {
+        // The length is encoded seven bits at a time, with the top bit marking that more follow
+        let mut packet = vec![header];
+        let mut len = body.len();
+        if len > 268_435_455 {
+            bail!("mqtt packet is too large ({len} bytes)");
+        }
+        loop {
+            let mut byte = (len % 128) as u8;
+            len /= 128;
+            if len > 0 {
+                byte |= 0x80;
+            }
+            packet.push(byte);
+            if len == 0 {
+                break;
+            }
+        }
+        packet.extend_from_slice(body);
+
+        self.stream
+            .write_all(&packet)
+            .with_context(|| format!("failed to send to mqtt broker {}", self.broker))
+    }
+}
+
+/// Appends the given string to the given packet body, prefixed by its length as MQTT requires.
+/// That length is only two bytes, so this fails for strings longer than 65,535 bytes.
+fn push_str(body: &mut Vec<u8>, s: &str) -> Result<()> {
+    let Ok(len) = u16::try_from(s.len()) else {
+        bail!("mqtt string is too long ({} bytes)", s.len());
+    };
+    body.extend_from_slice(&len.to_be_bytes());
+    body.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+/// Gets the location of the hashes of the views last published.
+fn hashes_path() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("mqtt.json"))
+}
+
+/// Gets the default client ID.
+fn default_client_id() -> String {
+    "polaris".to_string()
+}
+
+/// Gets the default for whether or not to retain view data (which is to).
+fn default_retain() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Sends a packet with a body of the given length to a local listener, returning exactly what
+    /// it received before the body.
+    fn sent_header(len: usize) -> Vec<u8> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut broker, _) = listener.accept().unwrap();
+        // Large bodies won't fit in the socket buffers, so they have to be read as they're sent
+        let received = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            broker.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut client = Client {
+            stream,
+            broker: "test".to_string(),
+        };
+        client.send(0x30, &vec![0xaa; len]).unwrap();
+        drop(client);
+        let mut received = received.join().unwrap();
+        assert!(received[received.len() - len..].iter().all(|b| *b == 0xaa));
+        received.truncate(received.len() - len);
+        received
+    }
+
+    #[test]
+    fn lengths_are_encoded_seven_bits_at_a_time() {
+        assert_eq!(sent_header(0), [0x30, 0x00]);
+        assert_eq!(sent_header(127), [0x30, 0x7f]);
+        assert_eq!(sent_header(128), [0x30, 0x80, 0x01]);
+        assert_eq!(sent_header(16_383), [0x30, 0xff, 0x7f]);
+        assert_eq!(sent_header(16_384), [0x30, 0x80, 0x80, 0x01]);
+        assert_eq!(sent_header(2_097_152), [0x30, 0x80, 0x80, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn strings_are_prefixed_with_their_lengths() {
+        let mut body = Vec::new();
+        push_str(&mut body, "MQTT").unwrap();
+        assert_eq!(body, [0x00, 0x04, b'M', b'Q', b'T', b'T']);
+
+        assert!(push_str(&mut body, &"a".repeat(65_535)).is_ok());
+        assert!(push_str(&mut body, &"a".repeat(65_536)).is_err());
+    }
+
+    #[test]
+    fn passwords_need_usernames() {
+        let config = |credentials: &str| {
+            serde_json::from_str::<MqttConfig>(&format!(
+                r#"{{ "broker": "localhost:1883", "topics": {{}}{credentials} }}"#
+            ))
+            .unwrap()
+        };
+        assert!(config("").validate().is_ok());
+        assert!(config(r#", "username": "me""#).validate().is_ok());
+        assert!(config(r#", "username": "me", "password": "pw""#)
+            .validate()
+            .is_ok());
+        assert!(config(r#", "password": "pw""#).validate().is_err());
+    }
+}
@@ -0,0 +1,367 @@
+//! Push notifications for the items in views, so reminders (like upcoming events, newly-due tasks,
+//! or fired tickles) can reach a phone. Which views send notifications, and where to, is set
+//! under `notifications` in the config file, as a map of view names to lists of targets:
+//!
+//! ```json
+//! "notifications": {
+//!     "due_tasks": [{ "type": "ntfy", "topic": "my-reminders" }],
+//!     "events": [{ "type": "webhook", "url": "https://example.com/hook" }],
+//!     "availability": [{ "type": "slack", "url": "https://hooks.slack.com/services/..." }]
+//! }
+//! ```
+//!
+//! For ntfy and webhook targets, a notification is sent for every item in each of those views, so
+//! these are best combined with `--changed-since`, which cuts views down to only the items that
+//! are new since the last run. Slack and Discord targets instead get a single compact digest of
+//! each view, which can be laid out with a template.
+
+use crate::picker::collect_items;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use ureq::Agent;
+
+/// A notification about a single item in a view.
+#[derive(Serialize, Debug)]
+pub struct Notification<'a> {
+    /// The name of the view the item is in.
+    pub view: &'a str,
+    /// A short description of the item (its title, the type of section it's in, and its date, if
+    /// it has one).
+    pub message: String,
+    /// The item itself, as it appears in the output.
+    pub item: Value,
+}
+
+/// Somewhere notifications can be sent.
+pub trait NotificationBackend {
+    /// Sends the given notification through the given agent (which is shared across every
+    /// notification in a run, so connections can be reused).
+    fn send(&self, agent: &Agent, notification: &Notification) -> Result<()>;
+}
+
+/// A topic on an [ntfy](https://ntfy.sh) server, either the public one or a self-hosted one.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Ntfy {
+    /// The base URL of the server, which defaults to `https://ntfy.sh`.
+    #[serde(default = "default_ntfy_server")]
+    pub server: String,
+    /// The topic to publish to.
+    pub topic: String,
+    /// The priority to send notifications with, from 1 (lowest) to 5 (highest).
+    pub priority: Option<u8>,
+    /// An access token for the topic, if it's protected.
+    pub token: Option<String>,
+}
+impl NotificationBackend for Ntfy {
+    fn send(&self, agent: &Agent, notification: &Notification) -> Result<()> {
+        let url = format!("{}/{}", self.server.trim_end_matches('/'), self.topic);
+        let mut req = agent
+            .post(&url)
+            .config()
+            .http_status_as_error(false)
+            .build()
+            .header("Title", notification.view);
+        if let Some(priority) = self.priority {
+            req = req.header("Priority", priority.to_string());
+        }
+        if let Some(token) = &self.token {
+            req = req.header("Authorization", format!("Bearer {token}"));
+        }
+        let res = req
+            .send(notification.message.as_str())
+            .with_context(|| format!("failed to connect to {url}"))?;
+        if !res.status().is_success() {
+            bail!(
+                "failed to publish to {url}, received status {}",
+                res.status()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// A URL which receives each notification as a JSON `POST` request.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Webhook {
+    /// The URL to send notifications to.
+    pub url: String,
+}
+impl NotificationBackend for Webhook {
+    fn send(&self, agent: &Agent, notification: &Notification) -> Result<()> {
+        let res = agent
+            .post(&self.url)
+            .config()
+            .http_status_as_error(false)
+            .build()
+            .send_json(notification)
+            .with_context(|| format!("failed to connect to {}", self.url))?;
+        if !res.status().is_success() {
+            bail!(
+                "failed to send to webhook {}, received status {}",
+                self.url,
+                res.status()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// A Slack or Discord incoming webhook, which gets a single compact digest of each view, rather
+/// than a message for every item.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChatWebhook {
+    /// The URL of the incoming webhook.
+    pub url: String,
+    /// The template for the message, in which `{view}` will be replaced with the name of the
+    /// view, `{count}` with the number of items in it, `{summary}` with a list of them under the
+    /// types of the sections they're in, and `{bold}` with the service's markup for bold text.
+    /// This defaults to the name of the view in bold, followed by the summary.
+    pub template: Option<String>,
+    /// The most items to list from each section, after which the rest will only be counted.
+    #[serde(default = "default_max_items")]
+    pub max_items: usize,
+}
+impl ChatWebhook {
+    /// Sends a digest of the given (serialised) view through this webhook, formatted for the given
+    /// service. Nothing will be sent if the view is empty, so channels don't fill up with empty
+    /// digests.
+    fn send_digest(
+        &self,
+        agent: &Agent,
+        service: ChatService,
+        view: &str,
+        sections: &Value,
+    ) -> Result<bool> {
+        let mut summary = String::new();
+        let mut count = 0;
+        for (section, labels) in digest_entries(sections) {
+            count += labels.len();
+            if labels.is_empty() {
+                continue;
+            }
+            let bold = service.bold();
+            summary.push_str(&format!("{bold}{section}{bold} ({})\n", labels.len()));
+            for label in labels.iter().take(self.max_items) {
+                summary.push_str(&format!("• {label}\n"));
+            }
+            if labels.len() > self.max_items {
+                summary.push_str(&format!("…and {} more\n", labels.len() - self.max_items));
+            }
+        }
+        if count == 0 {
+            return Ok(false);
+        }
+
+        let bold = service.bold();
+        let text = self
+            .template
+            .as_deref()
+            .unwrap_or("{bold}{view}{bold}\n{summary}")
+            .replace("{bold}", bold)
+            .replace("{view}", view)
+            .replace("{count}", &count.to_string())
+            .replace("{summary}", summary.trim_end());
+        let text = match service.max_len() {
+            Some(max_len) if text.chars().count() > max_len => {
+                text.chars().take(max_len - 1).chain(['…']).collect()
+            }
+            _ => text,
+        };
+
+        let res = agent
+            .post(&self.url)
+            .config()
+            .http_status_as_error(false)
+            .build()
+            .send_json(serde_json::json!({ service.text_field(): text }))
+            .with_context(|| format!("failed to connect to {}", self.url))?;
+        if !res.status().is_success() {
+            bail!(
+                "failed to send digest to {}, received status {}",
+                self.url,
+                res.status()
+            );
+        }
+
+        Ok(true)
+    }
+}
+
+/// A chat service which can receive digests through an incoming webhook.
+#[derive(Clone, Copy, Debug)]
+enum ChatService {
+    Slack,
+    Discord,
+}
+impl ChatService {
+    /// Gets the markup for bold text on this service.
+    fn bold(self) -> &'static str {
+        match self {
+            Self::Slack => "*",
+            Self::Discord => "**",
+        }
+    }
+
+    /// Gets the field of the webhook payload the message goes in.
+    fn text_field(self) -> &'static str {
+        match self {
+            Self::Slack => "text",
+            Self::Discord => "content",
+        }
+    }
+
+    /// Gets the longest message this service will accept, if there's a limit worth worrying
+    /// about.
+    fn max_len(self) -> Option<usize> {
+        match self {
+            Self::Slack => None,
+            Self::Discord => Some(2000),
+        }
+    }
+}
+
+/// A place to send notifications, as given in the config file.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationTarget {
+    Ntfy(Ntfy),
+    Webhook(Webhook),
+    Slack(ChatWebhook),
+    Discord(ChatWebhook),
+}
+impl fmt::Display for NotificationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ntfy(ntfy) => write!(f, "ntfy {}/{}", ntfy.server, ntfy.topic),
+            Self::Webhook(webhook) => write!(f, "webhook {}", webhook.url),
+            Self::Slack(hook) => write!(f, "slack {}", hook.url),
+            Self::Discord(hook) => write!(f, "discord {}", hook.url),
+        }
+    }
+}
+
+/// Sends notifications for each of the given (produced) views to the targets set for it,
+/// returning the number of messages sent. Views without targets are ignored.
+pub fn send_notifications(
+    views: &HashMap<String, impl Serialize>,
+    targets: &BTreeMap<String, Vec<NotificationTarget>>,
+) -> Result<usize> {
+    let agent = Agent::new_with_defaults();
+    let mut sent = 0;
+    for (view, view_targets) in targets {
+        let Some(sections) = views.get(view) else {
+            continue;
+        };
+        let sections = serde_json::to_value(sections)?;
+        let mut notifications = Vec::new();
+        collect_items(sections.clone(), None, &mut notifications);
+        let notifications = notifications
+            .into_iter()
+            .map(|(message, item)| Notification {
+                view,
+                message,
+                item,
+            })
+            .collect::<Vec<_>>();
+
+        for target in view_targets {
+            let res = match target {
+                NotificationTarget::Ntfy(ntfy) => send_each(&agent, ntfy, &notifications),
+                NotificationTarget::Webhook(webhook) => send_each(&agent, webhook, &notifications),
+                NotificationTarget::Slack(hook) => hook
+                    .send_digest(&agent, ChatService::Slack, view, &sections)
+                    .map(usize::from),
+                NotificationTarget::Discord(hook) => hook
+                    .send_digest(&agent, ChatService::Discord, view, &sections)
+                    .map(usize::from),
+            };
+            sent += res.with_context(|| format!("failed to send notification to {target}"))?;
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Sends each of the given notifications through the given backend, returning how many were sent.
+fn send_each(
+    agent: &Agent,
+    backend: &dyn NotificationBackend,
+    notifications: &[Notification],
+) -> Result<usize> {
+    for notification in notifications {
+        backend.send(agent, notification)?;
+    }
+    Ok(notifications.len())
+}
+
+/// Gets short labels for everything in each section of the given (serialised) view, by the type of
+/// the section. Unlike notifications, digests cover things without IDs too, like free slots.
+fn digest_entries(sections: &Value) -> Vec<(String, Vec<String>)> {
+    let Value::Array(sections) = sections else {
+        return Vec::new();
+    };
+    sections
+        .iter()
+        .filter_map(|section| {
+            let ty = section.get("type")?.as_str()?;
+            let mut labels = Vec::new();
+            if let Some(items) = section.get("items") {
+                collect_labels(items, &mut labels);
+            }
+            Some((ty.replace('_', " "), labels))
+        })
+        .collect()
+}
+
+/// Recursively collects labels for the entries in the given value. An entry is anything with a
+/// title, or with a start and an end (like a free slot), and entries nested in others aren't
+/// labelled separately.
+fn collect_labels(value: &Value, labels: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(title) = map.get("title").and_then(Value::as_str) {
+                let date = ["date", "deadline", "scheduled"]
+                    .iter()
+                    .find_map(|key| map.get(*key).and_then(Value::as_str));
+                labels.push(match date {
+                    Some(date) => format!("{title} ({})", date.replace('T', " ")),
+                    None => title.to_string(),
+                });
+            } else if let (Some(start), Some(end)) = (
+                map.get("start").and_then(Value::as_str),
+                map.get("end").and_then(Value::as_str),
+            ) {
+                labels.push(format!(
+                    "{} to {}",
+                    start.replace('T', " "),
+                    end.replace('T', " ")
+                ));
+            } else {
+                for value in map.values() {
+                    collect_labels(value, labels);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_labels(value, labels);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Gets the default number of items to list from each section of a digest.
+fn default_max_items() -> usize {
+    10
+}
+
+/// Gets the default ntfy server, the public one.
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
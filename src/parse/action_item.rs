@@ -1,38 +1,60 @@
 use super::node::Node;
+use crate::warnings::warn_once;
 use anyhow::{anyhow, bail, Result};
 use chrono::{NaiveDate, NaiveDateTime};
 use clap::ValueEnum;
 use orgish::timestamp::DateTime;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::str::FromStr;
 use uuid::Uuid;
 
 /// Converts the given node into its corresponding action item. This does not complete the process,
 /// and a second passthrough against a map of all the action items will be needed to fill in
 /// connecting details and computed relative properties.
-pub fn node_to_action_item(node: Node, repeats: Vec<ActionItemRepeat>) -> Result<ActionItem> {
+///
+/// The area of focus of the item will be the first of the given area tags it's under.
+pub fn node_to_action_item(
+    mut node: Node,
+    repeats: Vec<ActionItemRepeat>,
+    areas: &[String],
+) -> Result<ActionItem> {
+    // We own the node, so take the potentially large fields rather than copying them (bodies in
+    // particular can be huge)
     let base = BaseActionItem {
         id: node.id,
-        title: node.title.clone(),
-        body: node.body.clone(),
-        parent_tags: node.parent_tags.clone(),
+        title: std::mem::take(&mut node.title),
+        body: node.body.take(),
+        area: area_from_node(&node, areas),
+        parent_tags: std::mem::take(&mut node.parent_tags),
         parent_id: node.parent_id,
+        path: std::mem::take(&mut node.path),
+        keyword: node.metadata.as_ref().unwrap().keyword.clone(),
         repeats,
     };
 
     match &node.metadata.as_ref().unwrap().keyword {
         Some(kw) => {
             match kw.as_str() {
-                "TODO" | "NEXT" => Ok(ActionItem::Task {
-                    base,
+                "TODO" | "NEXT" | "HOLD" => {
+                    let (effort, effort_minutes) = Effort::from_node(&node)?;
+                    Ok(ActionItem::Task {
+                        base,
 
-                    people: people_from_node(&node)?,
-                    priority: Priority::from_node(&node)?,
-                    computed_priority: None, // Later
-                    effort: Effort::from_node(&node)?,
-                    contexts: node.tags.clone(),
-                    can_start: kw == "TODO",
-                }),
+                        people: people_from_node(&node)?,
+                        priority: Priority::from_node(&node)?,
+                        computed_priority: None, // Later
+                        effort,
+                        effort_minutes,
+                        contexts: node.tags.clone(),
+                        can_start: kw == "TODO",
+                        held: kw == "HOLD",
+                        created: created_from_node(&node)?,
+                        blocked_by: links_from_node(&node, "DEPENDS")?, // Later
+                        blocks: links_from_node(&node, "BLOCKS")?,      // Later
+                    })
+                }
                 "WAIT" => Ok(ActionItem::Waiting {
                     base,
                     sent: node
@@ -54,6 +76,7 @@ pub fn node_to_action_item(node: Node, repeats: Vec<ActionItemRepeat>) -> Result
                         priority: Priority::from_node(&node)?,
                         computed_priority: None, // Later
                         child_items: node.children.iter().map(|(id, _)| *id).collect(), // Later
+                        completed_tasks: 0,      // Later
                     })
                 }
                 _ => bail!("unknown keyword: {kw}"),
@@ -98,6 +121,7 @@ pub fn fill_action_item(id: Uuid, map: &mut HashMap<Uuid, ActionItem>) {
             priority,
             computed_priority,
             child_items,
+            ..
         } => {
             // If there's a parent node, try to compute its priority recursively, and if that's
             // higher than our own, set our computed priority
@@ -141,6 +165,10 @@ pub enum ActionItem {
         /// In the first pass, the IDs of all the children will be listed, and this will be
         /// filtered and resolved to real tasks in the second pass.
         child_items: Vec<Uuid>,
+        /// The number of direct children of this stack which are completed tasks (cancelled tasks
+        /// and completed stacks aren't counted). Completed items are dropped before they become
+        /// action items, so these are counted beforehand, and this will initially be zero.
+        completed_tasks: usize,
     },
     Task {
         base: BaseActionItem,
@@ -153,8 +181,12 @@ pub enum ActionItem {
         ///
         /// This is computed in the second passthrough, and will initially be `false`.
         computed_priority: Option<Priority>,
-        /// The effort required to complete this task.
+        /// The effort required to complete this task. If the effort was given as a duration, this
+        /// will be the level closest to it.
         effort: Effort,
+        /// The number of minutes this task is estimated to take, if its effort was given as a
+        /// duration (e.g. `0:30`, `2h`, or `45m`) rather than a level.
+        effort_minutes: Option<u32>,
         /// The contexts required to complete this task.
         contexts: HashSet<String>,
         /// The people needed to complete this task, listed by their IDs in the system and their
@@ -163,6 +195,21 @@ pub enum ActionItem {
         /// Whether or not this task can be immediately started yet or not. Those which can be have
         /// the keyword `TODO`, and those which don't have the keyword `NEXT`.
         can_start: bool,
+        /// Whether or not this task is on hold (with the keyword `HOLD`), meaning it's blocked
+        /// indefinitely. Held tasks can't be started, but unlike `NEXT` tasks, they aren't expected
+        /// to become actionable at any particular point.
+        held: bool,
+        /// The date this task was created, if it has a `CREATED` property.
+        created: Option<NaiveDate>,
+        /// The items which need to be completed before this task can be, by their IDs and titles.
+        ///
+        /// In the first pass, these will be taken from the `DEPENDS` property, and in the second
+        /// pass, they'll be resolved against the `BLOCKS` properties of other tasks, leaving only
+        /// those which haven't been completed yet.
+        blocked_by: Vec<(Uuid, String)>,
+        /// The items which can't be completed until this task is, by their IDs and titles. These
+        /// are resolved in the same way as `blocked_by`, from the `BLOCKS` property.
+        blocks: Vec<(Uuid, String)>,
     },
     Waiting {
         base: BaseActionItem,
@@ -195,6 +242,25 @@ impl ActionItem {
             | Self::None { base, .. } => base,
         }
     }
+
+    /// Gets the name of the kind of this action item, as it would be serialized.
+    pub fn kind(&self) -> &'static str {
+        match &self {
+            Self::Stack { .. } => "stack",
+            Self::Task { .. } => "task",
+            Self::Waiting { .. } => "waiting",
+            Self::Note { .. } => "note",
+            Self::None { .. } => "none",
+        }
+    }
+
+    /// Gets the people associated with this action item, if it's of a type that has them.
+    pub fn people_mut(&mut self) -> Option<&mut Vec<(Uuid, String)>> {
+        match self {
+            Self::Task { people, .. } | Self::None { people, .. } => Some(people),
+            _ => None,
+        }
+    }
 }
 
 /// The base properties all action items have.
@@ -206,10 +272,16 @@ pub struct BaseActionItem {
     pub title: Vec<String>,
     /// The body of the item, if present.
     pub body: Option<String>,
+    /// The area of focus this item falls under (e.g. `work` or `health`), if any.
+    pub area: Option<String>,
     /// Any tags on the parent nodes of this action item.
     pub parent_tags: HashSet<String>,
     /// The ID of the parent node, if there is one.
     pub parent_id: Option<Uuid>,
+    /// The path of the file this item came from.
+    pub path: PathBuf,
+    /// The raw keyword on the item (e.g. `TODO`), if it has one.
+    pub keyword: Option<String>,
     /// The repeats of this action item. There is guaranteed to be at least one repeat (even if it
     /// doesn't have any timestamps associated with it) for every action item.
     pub repeats: Vec<ActionItemRepeat>,
@@ -248,9 +320,11 @@ pub enum Effort {
     Minimal = 0,
 }
 impl Effort {
-    /// Parses an effort level from the given node.
-    fn from_node(node: &Node) -> Result<Self> {
-        match node
+    /// Parses an effort level from the given node. Efforts can also be given as Org-style
+    /// durations, in which case the number of minutes will be returned too, along with the level
+    /// that fits it best.
+    pub fn from_node(node: &Node) -> Result<(Self, Option<u32>)> {
+        let level = match node
             .metadata
             .as_ref()
             .unwrap()
@@ -258,35 +332,85 @@ impl Effort {
             .get("EFFORT")
             .map(|s| s.as_str())
         {
-            Some("total") => Ok(Self::Total),
-            Some("high") => Ok(Self::High),
-            Some("medium") => Ok(Self::Medium),
-            Some("med") => Ok(Self::Medium),
-            Some("low") => Ok(Self::Low),
-            Some("minimal") => Ok(Self::Minimal),
-            Some("min") => Ok(Self::Minimal),
-            Some(e) => bail!("unknown effort '{e}' on node {}", node.id),
-            None => Ok(Self::Medium),
+            Some("total") => Self::Total,
+            Some("high") => Self::High,
+            Some("medium") => Self::Medium,
+            Some("med") => Self::Medium,
+            Some("low") => Self::Low,
+            Some("minimal") => Self::Minimal,
+            Some("min") => Self::Minimal,
+            Some(e) => match parse_duration(e) {
+                Some(minutes) => return Ok((Self::from_minutes(minutes), Some(minutes))),
+                None => bail!("unknown effort '{e}' on node {}", node.id),
+            },
+            None => Self::Medium,
             // None => bail!("no effort level specified for node {}", node.id),
+        };
+        Ok((level, None))
+    }
+
+    /// Gets the lowest effort level whose estimate (see [`Self::minutes`]) covers the given
+    /// number of minutes.
+    pub fn from_minutes(minutes: u32) -> Self {
+        [Self::Minimal, Self::Low, Self::Medium, Self::High]
+            .into_iter()
+            .find(|level| minutes <= level.minutes())
+            .unwrap_or(Self::Total)
+    }
+
+    /// Gets a numeric weight for this effort level, for adding efforts together. Each level is
+    /// taken to be roughly twice as much work as the one below it.
+    pub fn weight(self) -> u32 {
+        match self {
+            Self::Total => 16,
+            Self::High => 8,
+            Self::Medium => 4,
+            Self::Low => 2,
+            Self::Minimal => 1,
         }
     }
 
-    // NOTE: This was used for the CLI effort filters, might add them back in future, so keeping
-    // for now.
-    //
-    // /// Converts the given string into an effort, if possible.
-    // pub fn from_str(s: &str) -> Result<Self> {
-    //     match s.to_lowercase().as_str() {
-    //         "total" => Ok(Self::Total),
-    //         "high" => Ok(Self::High),
-    //         "medium" => Ok(Self::Medium),
-    //         "med" => Ok(Self::Medium),
-    //         "low" => Ok(Self::Low),
-    //         "minimal" => Ok(Self::Minimal),
-    //         "min" => Ok(Self::Minimal),
-    //         e => bail!("unknown effort '{e}'"),
-    //     }
-    // }
+    /// Gets a rough estimate of the number of minutes of work this effort level represents, for
+    /// comparing against the time available in a day. Total effort is taken to be a full working
+    /// day, and each level below is half the one above it.
+    pub fn minutes(self) -> u32 {
+        self.weight() * 30
+    }
+}
+impl FromStr for Effort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "total" => Ok(Self::Total),
+            "high" => Ok(Self::High),
+            "medium" => Ok(Self::Medium),
+            "med" => Ok(Self::Medium),
+            "low" => Ok(Self::Low),
+            "minimal" => Ok(Self::Minimal),
+            "min" => Ok(Self::Minimal),
+            e => bail!("unknown effort '{e}'"),
+        }
+    }
+}
+
+/// Parses an Org-style duration, like `0:30`, `2h`, `45m`, or `1h30m`, into a number of minutes.
+fn parse_duration(s: &str) -> Option<u32> {
+    if let Some((hours, minutes)) = s.split_once(':') {
+        let minutes = minutes.parse::<u32>().ok().filter(|m| *m < 60)?;
+        return Some(hours.parse::<u32>().ok()? * 60 + minutes);
+    }
+
+    let (hours, rest) = match s.split_once('h') {
+        Some((hours, rest)) => (hours.trim().parse::<u32>().ok()?, rest.trim()),
+        None => (0, s),
+    };
+    let minutes = if rest.is_empty() && s.contains('h') {
+        0
+    } else {
+        rest.strip_suffix('m')?.trim().parse::<u32>().ok()?
+    };
+    Some(hours * 60 + minutes)
 }
 
 /// The priority of a task or stack.
@@ -320,8 +444,22 @@ impl Priority {
 /// People should be given in a `PEOPLE` property of the form `[Person 1](their-id), [Person
 /// 2](their-id)`.
 fn people_from_node(node: &Node) -> Result<Vec<(Uuid, String)>> {
-    match node.metadata.as_ref().unwrap().properties.get("PEOPLE") {
-        Some(people) => people
+    let people = links_from_node(node, "PEOPLE")?
+        .into_iter()
+        .map(|(id, name)| {
+            // A convention in my personal systems for people nodes
+            let name = name.strip_prefix("(Person) ").unwrap_or(&name).to_string();
+            (id, name)
+        })
+        .collect();
+    Ok(people)
+}
+
+/// Parses a list of links to other nodes, by their IDs and titles, from the given property of the
+/// given node. These should be of the form `[Title 1](id-1), [Title 2](id-2)`.
+fn links_from_node(node: &Node, property: &str) -> Result<Vec<(Uuid, String)>> {
+    match node.metadata.as_ref().unwrap().properties.get(property) {
+        Some(links) => links
             .split(", ")
             .map(|p| {
                 let mut parts = p.splitn(2, "](");
@@ -329,19 +467,25 @@ fn people_from_node(node: &Node) -> Result<Vec<(Uuid, String)>> {
                     .next()
                     .unwrap() // Guaranteed in a split
                     .strip_prefix("[")
-                    .ok_or(anyhow!("invalid people link format in node {}", node.id))?
+                    .ok_or(anyhow!(
+                        "invalid link format in {property} on node {}",
+                        node.id
+                    ))?
                     .to_string();
                 let id = Uuid::parse_str(
                     parts
                         .next()
-                        .ok_or(anyhow!("invalid people link format in node {}", node.id))?
+                        .ok_or(anyhow!(
+                            "invalid link format in {property} on node {}",
+                            node.id
+                        ))?
                         .strip_suffix(")")
-                        .ok_or(anyhow!("invalid people link format in node {}", node.id))?,
+                        .ok_or(anyhow!(
+                            "invalid link format in {property} on node {}",
+                            node.id
+                        ))?,
                 )?;
 
-                // A convention in my personal systems for people nodes
-                let name = name.strip_prefix("(Person) ").unwrap_or(&name).to_string();
-
                 Ok::<_, anyhow::Error>((id, name))
             })
             .collect(),
@@ -349,6 +493,116 @@ fn people_from_node(node: &Node) -> Result<Vec<(Uuid, String)>> {
     }
 }
 
+/// Gets the area of focus for the given node, which is the first of the given area tags that's on
+/// one of its parents or the node itself. Areas can be nested (e.g. `health` under `personal`), so
+/// they should be given from the most specific to the least.
+pub fn area_from_node(node: &Node, areas: &[String]) -> Option<String> {
+    areas
+        .iter()
+        .find(|area| node.parent_tags.contains(*area) || node.tags.contains(*area))
+        .cloned()
+}
+
+/// Parses the date a node was created from its `CREATED` property, if it has one. This can either
+/// be a plain date or a timestamp (e.g. `[2025-01-01 Wed 09:00]`), of which only the date is
+/// used.
+fn created_from_node(node: &Node) -> Result<Option<NaiveDate>> {
+    match node.metadata.as_ref().unwrap().properties.get("CREATED") {
+        Some(created) => {
+            let date = created
+                .trim_start_matches(['[', '<'])
+                .get(..10)
+                .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+                .ok_or(anyhow!("invalid CREATED property on node {}", node.id))?;
+            Ok(Some(date))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Makes sure different people with the same name can be told apart, by appending the start of
+/// their IDs to their names everywhere they appear (e.g. `Sam (1a2b3c4d)`). Otherwise, anything
+/// matching by name would treat them as the same person. This will warn about each such name.
+pub fn disambiguate_people(map: &mut HashMap<Uuid, ActionItem>) {
+    let mut ids_by_name: HashMap<String, HashSet<Uuid>> = HashMap::new();
+    for item in map.values_mut() {
+        for (id, name) in item.people_mut().into_iter().flatten() {
+            ids_by_name.entry(name.clone()).or_default().insert(*id);
+        }
+    }
+    let ambiguous = ids_by_name
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(name, _)| name)
+        .collect::<HashSet<_>>();
+    if ambiguous.is_empty() {
+        return;
+    }
+
+    for name in &ambiguous {
+        warn_once(&format!(
+            "multiple people are named '{name}', they will be shown with their IDs"
+        ));
+    }
+    for item in map.values_mut() {
+        for (id, name) in item.people_mut().into_iter().flatten() {
+            if ambiguous.contains(name) {
+                *name = format!("{name} ({})", &id.to_string()[..8]);
+            }
+        }
+    }
+}
+
+/// Resolves the dependencies between tasks, so that every task knows both what it's blocked by and
+/// what it blocks, whichever side the dependency was declared on. Completed items aren't in the
+/// map, so dependencies on them are satisfied and get dropped here.
+pub fn resolve_dependencies(map: &mut HashMap<Uuid, ActionItem>) {
+    // Collect every dependency as a pair of `(blocker, blocked)`
+    let mut pairs = BTreeSet::new();
+    for item in map.values() {
+        if let ActionItem::Task {
+            base,
+            blocked_by,
+            blocks,
+            ..
+        } = item
+        {
+            pairs.extend(blocked_by.iter().map(|(id, _)| (*id, base.id)));
+            pairs.extend(blocks.iter().map(|(id, _)| (base.id, *id)));
+        }
+    }
+    pairs.retain(|(blocker, blocked)| map.contains_key(blocker) && map.contains_key(blocked));
+    if pairs.is_empty() {
+        return;
+    }
+    let titles = pairs
+        .iter()
+        .flat_map(|(blocker, blocked)| [*blocker, *blocked])
+        .map(|id| (id, map[&id].base().title.last().cloned().unwrap()))
+        .collect::<HashMap<_, _>>();
+
+    for item in map.values_mut() {
+        if let ActionItem::Task {
+            base,
+            blocked_by,
+            blocks,
+            ..
+        } = item
+        {
+            *blocked_by = pairs
+                .iter()
+                .filter(|(_, blocked)| *blocked == base.id)
+                .map(|(blocker, _)| (*blocker, titles[blocker].clone()))
+                .collect();
+            *blocks = pairs
+                .iter()
+                .filter(|(blocker, _)| *blocker == base.id)
+                .map(|(_, blocked)| (*blocked, titles[blocked].clone()))
+                .collect();
+        }
+    }
+}
+
 /// Computes the priority of the action item with the given ID by looking recursively through its
 /// parent stacks to find the highest priority. Even though recursive schedule-involved stacks
 /// are not used in the system, this is done to allow "meta-stack" to be given priorities that
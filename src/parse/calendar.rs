@@ -0,0 +1,750 @@
+use super::item_error::{ItemError, ItemErrorKind};
+use super::local::stable_id;
+use super::node::{Node, NodeMetadata};
+use crate::warnings::warn;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{
+    Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use orgish::Timestamp;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long fetching a calendar from a URL can take before it fails, so an unreachable calendar
+/// can't hold up every run.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads the events from the given ICS calendars, which can be files or `http(s)://` URLs, as
+/// nodes Starling could have given with the same details. They go through the same pipeline as
+/// everything else, so they show up alongside events from Starling, repeat in the same way, and
+/// make the user busy.
+///
+/// Recurrence rules can end on a date or after a number of occurrences, and can have exceptions
+/// (from `EXDATE`s or occurrences that were moved). Weekly rules can repeat on several days, but
+/// rules which pick out occurrences in other ways (e.g. the second Tuesday of each month) can't be
+/// expanded exactly, so events with those are skipped with a warning. Events that can't be read at
+/// all are added to the given errors, like any other item that can't be processed. Times in UTC
+/// are converted to local time, but times in other timezones are taken as local.
+pub fn read_calendar_nodes(sources: &[String], errors: &mut Vec<ItemError>) -> Result<Vec<Node>> {
+    let mut nodes = Vec::new();
+    for source in sources {
+        let contents = if source.starts_with("http://") || source.starts_with("https://") {
+            ureq::get(source)
+                .config()
+                .timeout_global(Some(FETCH_TIMEOUT))
+                .build()
+                .call()
+                .and_then(|mut res| res.body_mut().read_to_string())
+                .with_context(|| format!("failed to fetch calendar {source}"))?
+        } else {
+            std::fs::read_to_string(source)
+                .with_context(|| format!("failed to read calendar {source}"))?
+        };
+        nodes.extend(parse_events(&contents, source, errors));
+    }
+
+    Ok(nodes)
+}
+
+/// Splits the given ICS calendar into its content lines, undoing the folding of long lines (whose
+/// continuations start with whitespace).
+pub fn unfold_ics(contents: &str) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    for line in contents.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
+
+/// Parses the date from an ICS date or datetime value (e.g. `20251225` or `20251225T090000Z`).
+pub fn parse_ics_date(value: &str) -> Result<NaiveDate> {
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| anyhow!("invalid calendar date `{value}`"))
+}
+
+/// Undoes the escaping of special characters in ICS text values.
+pub fn unescape_ics(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Parses the events in the given ICS calendar, which came from the given source, into nodes.
+/// Cancelled events are left out, and events which can't be converted are added to the given
+/// errors.
+fn parse_events(contents: &str, source: &str, errors: &mut Vec<ItemError>) -> Vec<Node> {
+    let mut events = Vec::new();
+    let mut event: Option<HashMap<String, String>> = None;
+    for line in unfold_ics(contents) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters (like `;VALUE=DATE` or `;TZID=...`) don't matter to us
+        let name = name.split(';').next().unwrap();
+        match (name, &mut event) {
+            ("BEGIN", None) if value == "VEVENT" => event = Some(HashMap::new()),
+            ("END", Some(_)) if value == "VEVENT" => events.push(event.take().unwrap()),
+            // Exceptions can be spread over several lines, so they're all kept
+            ("EXDATE", Some(props)) => {
+                props
+                    .entry(name.to_string())
+                    .and_modify(|dates| {
+                        dates.push(',');
+                        dates.push_str(value);
+                    })
+                    .or_insert_with(|| value.to_string());
+            }
+            // Only the first of any other repeated property is used
+            (_, Some(props)) => {
+                props
+                    .entry(name.to_string())
+                    .or_insert_with(|| value.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    // Occurrences of repeating events which were moved or cancelled have events of their own,
+    // which share the UID of the original and say which occurrence they replace
+    let mut overrides = HashMap::<&str, Vec<&str>>::new();
+    for props in &events {
+        if let (Some(uid), Some(occurrence)) = (props.get("UID"), props.get("RECURRENCE-ID")) {
+            overrides.entry(uid).or_default().push(occurrence);
+        }
+    }
+
+    let mut nodes = Vec::new();
+    for props in &events {
+        if props
+            .get("STATUS")
+            .is_some_and(|status| status == "CANCELLED")
+        {
+            continue;
+        }
+        let title = props
+            .get("SUMMARY")
+            .map(|summary| unescape_ics(summary))
+            .unwrap_or_else(|| "Event".to_string());
+        // Modified occurrences of repeating events share the UID of the original
+        let key = format!(
+            "{source}\0{}\0{}",
+            props.get("UID").map_or(title.as_str(), String::as_str),
+            props.get("RECURRENCE-ID").map_or("", String::as_str)
+        );
+        let mut exceptions = props
+            .get("EXDATE")
+            .map(|dates| dates.split(',').collect::<Vec<_>>())
+            .unwrap_or_default();
+        if !props.contains_key("RECURRENCE-ID") {
+            exceptions.extend(
+                props
+                    .get("UID")
+                    .and_then(|uid| overrides.get(uid.as_str()))
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+
+        match event_node(props, title.clone(), &key, &exceptions, source) {
+            Ok(Some(node)) => nodes.push(node),
+            // Every occurrence was an exception
+            Ok(None) => {}
+            // These are perfectly valid, so they shouldn't fail strict runs
+            Err(err) if err.is::<UnsupportedRule>() => warn(&format!(
+                "skipped calendar event \"{title}\" in {source}: {err}"
+            )),
+            Err(err) => errors.push(ItemError {
+                id: stable_id(&key),
+                title,
+                path: PathBuf::from(source),
+                kind: ItemErrorKind::Parse,
+                message: format!("{err:#}"),
+            }),
+        }
+    }
+
+    nodes
+}
+
+/// Converts the properties of a single event from the given source, with the given title and key
+/// for its ID, into a node. If the event repeats, the given exceptions (ICS dates or datetimes) are
+/// occurrences that don't happen. This returns `None` if no occurrences happen at all.
+fn event_node(
+    props: &HashMap<String, String>,
+    title: String,
+    key: &str,
+    exceptions: &[&str],
+    source: &str,
+) -> Result<Option<Node>> {
+    let Some(start) = props.get("DTSTART") else {
+        bail!("calendar event has no start date");
+    };
+    let start = parse_ics_date_time(start)?;
+    let end = props
+        .get("DTEND")
+        .map(|end| parse_ics_date_time(end))
+        .transpose()?;
+    let timestamps = match props.get("RRULE") {
+        Some(rule) => {
+            // Occurrences are told apart by their dates, since none of the rules we expand can
+            // have two on the same day
+            let exceptions = exceptions
+                .iter()
+                .map(|exception| parse_ics_date_time(exception).map(|(date, _)| date))
+                .collect::<Result<BTreeSet<_>>>()?;
+            Recurrence::parse(rule, start.0)?.timestamps(start, end, &exceptions)
+        }
+        None => vec![org_timestamp(start, end, None)],
+    };
+    if timestamps.is_empty() {
+        return Ok(None);
+    }
+    let timestamps = timestamps
+        .into_iter()
+        .map(|timestamp| {
+            timestamp
+                .parse::<Timestamp>()
+                .map_err(|_| anyhow!("failed to convert calendar event to timestamp {timestamp}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut properties = HashMap::new();
+    if let Some(location) = props.get("LOCATION") {
+        properties.insert("LOCATION".to_string(), unescape_ics(location));
+    }
+
+    Ok(Some(Node {
+        id: stable_id(key),
+        title: vec![title],
+        path: PathBuf::from(source),
+        tags: Default::default(),
+        parent_tags: Default::default(),
+        parent_id: None,
+        metadata: Some(NodeMetadata {
+            level: 1,
+            properties,
+            timestamps,
+            ..Default::default()
+        }),
+        body: props
+            .get("DESCRIPTION")
+            .map(|description| unescape_ics(description)),
+        children: Vec::new(),
+        connections: HashMap::new(),
+        child_connections: HashMap::new(),
+        backlinks: HashMap::new(),
+        child_backlinks: HashMap::new(),
+    }))
+}
+
+/// Parses an ICS date or datetime value into a date, and a time if it has one. Times in UTC
+/// (ending in `Z`) are converted to local time.
+fn parse_ics_date_time(value: &str) -> Result<(NaiveDate, Option<NaiveTime>)> {
+    let Some(time) = value.get(9..15) else {
+        return Ok((parse_ics_date(value)?, None));
+    };
+    let date_time = NaiveDateTime::new(
+        parse_ics_date(value)?,
+        NaiveTime::parse_from_str(time, "%H%M%S")
+            .map_err(|_| anyhow!("invalid calendar time `{value}`"))?,
+    );
+    let date_time = if value.ends_with('Z') {
+        Utc.from_utc_datetime(&date_time)
+            .with_timezone(&Local)
+            .naive_local()
+    } else {
+        date_time
+    };
+
+    Ok((date_time.date(), Some(date_time.time())))
+}
+
+/// How often a recurrence rule repeats.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A recurrence rule which is valid, but which picks out occurrences in a way that can't be
+/// expanded exactly (e.g. `BYDAY=2TU`, for the second Tuesday of each month).
+#[derive(Debug)]
+struct UnsupportedRule(String);
+impl fmt::Display for UnsupportedRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "recurrence rule `{}` isn't supported", self.0)
+    }
+}
+impl std::error::Error for UnsupportedRule {}
+
+/// An ICS recurrence rule (e.g. `FREQ=WEEKLY;INTERVAL=2;COUNT=5`), limited to the rules that can
+/// be expanded exactly: those that repeat at a fixed interval (on several days of the week, for
+/// weekly rules), and maybe stop.
+#[derive(Debug)]
+struct Recurrence {
+    frequency: Frequency,
+    /// The number of days, weeks, months, or years between each occurrence.
+    interval: u32,
+    /// The days of the week weekly rules repeat on, in the order they come in each week. This is
+    /// empty for other rules.
+    days: Vec<Weekday>,
+    /// The day weeks start on, which decides which days are in the same week when weekly rules
+    /// skip weeks.
+    week_start: Weekday,
+    /// The latest an occurrence can start, if the rule ends on a date.
+    until: Option<NaiveDateTime>,
+    /// The number of occurrences, if the rule ends after a certain number of them.
+    count: Option<u32>,
+}
+impl Recurrence {
+    /// Parses the given recurrence rule for an event starting on the given date. Rules that pick
+    /// out occurrences in ways that can't be expanded exactly fail with [`UnsupportedRule`], but
+    /// weekly (or daily) rules on particular days of the week, and rules that just name the start's
+    /// own day of the month or month, are fine.
+    fn parse(rule: &str, start: NaiveDate) -> Result<Self> {
+        let unsupported = || anyhow!(UnsupportedRule(rule.to_string()));
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut until = None;
+        let mut count = None;
+        let mut week_start = Weekday::Mon;
+        let mut by_day = None;
+        let mut by_month_day = None;
+        let mut by_month = None;
+        for part in rule.split(';') {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid recurrence rule `{rule}`"))?;
+            match name {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(unsupported()),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| anyhow!("invalid interval in recurrence rule `{rule}`"))?
+                }
+                "UNTIL" => {
+                    let (date, time) = parse_ics_date_time(value)?;
+                    // Rules ending on a date include everything on that date
+                    until = Some(
+                        date.and_time(time.unwrap_or(NaiveTime::from_hms_opt(23, 59, 59).unwrap())),
+                    );
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse()
+                            .map_err(|_| anyhow!("invalid count in recurrence rule `{rule}`"))?,
+                    )
+                }
+                "WKST" => {
+                    week_start = ics_weekday(value)
+                        .ok_or_else(|| anyhow!("invalid week start in recurrence rule `{rule}`"))?
+                }
+                "BYDAY" => by_day = Some(value),
+                "BYMONTHDAY" => by_month_day = Some(value),
+                "BYMONTH" => by_month = Some(value),
+                _ => return Err(unsupported()),
+            }
+        }
+        let mut frequency =
+            frequency.ok_or_else(|| anyhow!("recurrence rule `{rule}` has no frequency"))?;
+
+        let mut days = Vec::new();
+        if let Some(by_day) = by_day {
+            // Daily rules on particular days (e.g. weekdays) are weekly rules on each of them
+            if frequency == Frequency::Daily && interval == 1 {
+                frequency = Frequency::Weekly;
+            }
+            if frequency != Frequency::Weekly {
+                return Err(unsupported());
+            }
+            for day in by_day.split(',') {
+                // Days with numbers in front (e.g. `2TU`) only mean anything in monthly and
+                // yearly rules
+                days.push(ics_weekday(day).ok_or_else(unsupported)?);
+            }
+        } else if frequency == Frequency::Weekly {
+            days.push(start.weekday());
+        }
+        days.sort_by_key(|day| days_since(*day, week_start));
+        days.dedup();
+        // Calendars often name the start's own day of the month or month, which changes nothing
+        let names_start = |value: Option<&str>, start_value: u32| {
+            value.is_none_or(|value| value.parse::<u32>().ok() == Some(start_value))
+        };
+        let month_day_ok =
+            matches!(frequency, Frequency::Monthly | Frequency::Yearly) || by_month_day.is_none();
+        let month_ok = frequency == Frequency::Yearly || by_month.is_none();
+        if !month_day_ok
+            || !month_ok
+            || !names_start(by_month_day, start.day())
+            || !names_start(by_month, start.month())
+        {
+            return Err(unsupported());
+        }
+
+        Ok(Self {
+            frequency,
+            interval,
+            days,
+            week_start,
+            until,
+            count,
+        })
+    }
+
+    /// Returns the date `steps` intervals after the given start date, clamped to the end of the
+    /// month for monthly and yearly rules, or `None` if it's out of range.
+    fn step(&self, start: NaiveDate, steps: u32) -> Option<NaiveDate> {
+        let steps = steps.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => start.checked_add_days(Days::new(steps.into())),
+            Frequency::Weekly => start.checked_add_days(Days::new(u64::from(steps) * 7)),
+            Frequency::Monthly => start.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => start.checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+
+    /// Gets the dates of the occurrences of an event starting on the given date which repeats by
+    /// this rule, in order. Each one comes with the index of the day of the week it's on in
+    /// [`Self::days`] for weekly rules (which repeat separately on each day), or zero for others.
+    fn occurrences(&self, start: NaiveDate) -> impl Iterator<Item = (usize, NaiveDate)> + '_ {
+        // Weekly rules count weeks from the start of the start's week, and other rules count from
+        // the start itself
+        let first = match self.frequency {
+            Frequency::Weekly => {
+                start - Days::new(days_since(start.weekday(), self.week_start).into())
+            }
+            _ => start,
+        };
+        let slots = self.days.len().max(1);
+        (0..)
+            .map_while(move |steps| self.step(first, steps))
+            .flat_map(move |date| {
+                (0..slots).map(move |slot| match self.days.get(slot) {
+                    Some(day) => (
+                        slot,
+                        date + Days::new(days_since(*day, self.week_start).into()),
+                    ),
+                    None => (slot, date),
+                })
+            })
+            .filter(move |(_, date)| *date >= start)
+            // Months without the start's day (e.g. the 31st) are skipped, not counted
+            .filter(move |(_, date)| {
+                !matches!(self.frequency, Frequency::Monthly | Frequency::Yearly)
+                    || date.day() == start.day()
+            })
+    }
+
+    /// Formats the occurrences of an event with the given start and (exclusive) end which repeats
+    /// by this rule as Org timestamps, leaving out those on the given dates.
+    ///
+    /// Rules that end have each occurrence listed, up to the end of the year two years from now
+    /// (after which repeat expansion would call them a mistake). Rules that don't end have each
+    /// occurrence up to their last exception listed, and a single repeating timestamp for the
+    /// rest (one for each day of the week weekly rules repeat on).
+    fn timestamps(
+        &self,
+        start: (NaiveDate, Option<NaiveTime>),
+        end: Option<(NaiveDate, Option<NaiveTime>)>,
+        exceptions: &BTreeSet<NaiveDate>,
+    ) -> Vec<String> {
+        let ends = self.until.is_some() || self.count.is_some();
+        let horizon = NaiveDate::from_ymd_opt(Local::now().year() + 2, 12, 31).unwrap();
+        let repeater = format!(
+            "+{}{}",
+            self.interval,
+            match self.frequency {
+                Frequency::Daily => "d",
+                Frequency::Weekly => "w",
+                Frequency::Monthly => "m",
+                Frequency::Yearly => "y",
+            }
+        );
+
+        let mut timestamps = Vec::new();
+        let mut occurrences = 0;
+        // The days of the week (or the only slot, for other rules) which already have a repeating
+        // timestamp for every occurrence after this
+        let mut repeating = BTreeSet::new();
+        for (slot, date) in self.occurrences(start.0) {
+            let date_time = date.and_time(start.1.unwrap_or_default());
+            if self.count.is_some_and(|count| occurrences >= count)
+                || self.until.is_some_and(|until| date_time > until)
+                || date > horizon
+                || repeating.len() == self.days.len().max(1)
+            {
+                break;
+            }
+            occurrences += 1;
+            if repeating.contains(&slot) {
+                continue;
+            }
+
+            let offset = date - start.0;
+            let shifted_end = end.map(|(end_date, end_time)| (end_date + offset, end_time));
+            if !ends && exceptions.last().is_none_or(|last| date > *last) {
+                // Every occurrence from here on happens, so one repeater covers them all
+                timestamps.push(org_timestamp((date, start.1), shifted_end, Some(&repeater)));
+                repeating.insert(slot);
+            } else if !exceptions.contains(&date) {
+                timestamps.push(org_timestamp((date, start.1), shifted_end, None));
+            }
+        }
+
+        timestamps
+    }
+}
+
+/// Gets the number of days from the last `from` to `day` (e.g. two from Monday to Wednesday).
+fn days_since(day: Weekday, from: Weekday) -> u32 {
+    (7 + day.num_days_from_monday() - from.num_days_from_monday()) % 7
+}
+
+/// Parses an ICS weekday (e.g. `MO`).
+fn ics_weekday(day: &str) -> Option<Weekday> {
+    Some(match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// Formats the given start and (exclusive) end of an event as an active Org timestamp, with the
+/// given repeater if there is one.
+fn org_timestamp(
+    start: (NaiveDate, Option<NaiveTime>),
+    end: Option<(NaiveDate, Option<NaiveTime>)>,
+    repeater: Option<&str>,
+) -> String {
+    let repeater = repeater.map(|r| format!(" {r}")).unwrap_or_default();
+    let date = |date: NaiveDate| date.format("%Y-%m-%d %a");
+    let time = |time: NaiveTime| time.format("%H:%M");
+    match (start, end) {
+        ((date_s, Some(time_s)), Some((date_e, Some(time_e)))) if date_s == date_e => {
+            format!(
+                "<{} {}-{}{repeater}>",
+                date(date_s),
+                time(time_s),
+                time(time_e)
+            )
+        }
+        ((date_s, Some(time_s)), Some((date_e, Some(time_e)))) => format!(
+            "<{} {}{repeater}>--<{} {}{repeater}>",
+            date(date_s),
+            time(time_s),
+            date(date_e),
+            time(time_e)
+        ),
+        // All-day events end on the day after their last one
+        ((date_s, None), Some((date_e, None))) if date_e > date_s.succ_opt().unwrap() => format!(
+            "<{}{repeater}>--<{}{repeater}>",
+            date(date_s),
+            date(date_e.pred_opt().unwrap())
+        ),
+        ((date_s, Some(time_s)), _) => format!("<{} {}{repeater}>", date(date_s), time(time_s)),
+        ((date_s, None), _) => format!("<{}{repeater}>", date(date_s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn timestamps(rule: &str, start: &str, exceptions: &[&str]) -> Vec<String> {
+        let start = date(start);
+        let exceptions = exceptions.iter().map(|exception| date(exception)).collect();
+        Recurrence::parse(rule, start)
+            .unwrap()
+            .timestamps((start, None), None, &exceptions)
+    }
+
+    #[test]
+    fn rules_without_an_end_repeat() {
+        assert_eq!(
+            timestamps("FREQ=WEEKLY;INTERVAL=2", "2025-01-06", &[]),
+            ["<2025-01-06 Mon +2w>"]
+        );
+        // Naming the start's own day changes nothing
+        assert_eq!(
+            timestamps("FREQ=WEEKLY;BYDAY=MO;WKST=MO", "2025-01-06", &[]),
+            ["<2025-01-06 Mon +1w>"]
+        );
+    }
+
+    #[test]
+    fn rules_with_an_end_are_listed() {
+        assert_eq!(
+            timestamps("FREQ=DAILY;COUNT=3", "2025-01-06", &[]),
+            ["<2025-01-06 Mon>", "<2025-01-07 Tue>", "<2025-01-08 Wed>"]
+        );
+        assert_eq!(
+            timestamps("FREQ=WEEKLY;UNTIL=20250119", "2025-01-06", &[]),
+            ["<2025-01-06 Mon>", "<2025-01-13 Mon>"]
+        );
+        // Months without the start's day are skipped without being counted
+        assert_eq!(
+            timestamps("FREQ=MONTHLY;COUNT=2", "2025-01-31", &[]),
+            ["<2025-01-31 Fri>", "<2025-03-31 Mon>"]
+        );
+    }
+
+    #[test]
+    fn exceptions_are_left_out() {
+        // Exceptions still count towards the number of occurrences
+        assert_eq!(
+            timestamps("FREQ=DAILY;COUNT=3", "2025-01-06", &["2025-01-07"]),
+            ["<2025-01-06 Mon>", "<2025-01-08 Wed>"]
+        );
+        // Rules without an end are listed up to their last exception, then repeat
+        assert_eq!(
+            timestamps("FREQ=WEEKLY", "2025-01-06", &["2025-01-13"]),
+            ["<2025-01-06 Mon>", "<2025-01-20 Mon +1w>"]
+        );
+    }
+
+    #[test]
+    fn weekly_rules_repeat_on_each_day() {
+        assert_eq!(
+            timestamps("FREQ=WEEKLY;BYDAY=MO,WE,FR", "2025-01-06", &[]),
+            [
+                "<2025-01-06 Mon +1w>",
+                "<2025-01-08 Wed +1w>",
+                "<2025-01-10 Fri +1w>"
+            ]
+        );
+        // Weeks are counted from the start of the start's week
+        assert_eq!(
+            timestamps(
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=3",
+                "2025-01-08",
+                &[]
+            ),
+            ["<2025-01-08 Wed>", "<2025-01-20 Mon>", "<2025-01-22 Wed>"]
+        );
+        // Daily rules on particular days are weekly ones
+        assert_eq!(
+            timestamps("FREQ=DAILY;BYDAY=MO,TU;UNTIL=20250114", "2025-01-06", &[]),
+            [
+                "<2025-01-06 Mon>",
+                "<2025-01-07 Tue>",
+                "<2025-01-13 Mon>",
+                "<2025-01-14 Tue>"
+            ]
+        );
+        assert_eq!(
+            timestamps("FREQ=WEEKLY;BYDAY=MO,TU", "2025-01-06", &["2025-01-07"]),
+            [
+                "<2025-01-06 Mon>",
+                "<2025-01-13 Mon +1w>",
+                "<2025-01-14 Tue +1w>"
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_naming_the_start_are_expanded() {
+        assert_eq!(
+            timestamps("FREQ=MONTHLY;BYMONTHDAY=15", "2025-01-15", &[]),
+            ["<2025-01-15 Wed +1m>"]
+        );
+        assert_eq!(
+            timestamps("FREQ=YEARLY;BYMONTH=3;BYMONTHDAY=1", "2025-03-01", &[]),
+            ["<2025-03-01 Sat +1y>"]
+        );
+    }
+
+    #[test]
+    fn rules_picking_out_occurrences_are_unsupported() {
+        let start = date("2025-01-06");
+        for rule in [
+            "FREQ=MONTHLY;BYDAY=2TU",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=MONTHLY;BYMONTHDAY=15",
+            "FREQ=YEARLY;BYMONTH=3",
+            "FREQ=WEEKLY;BYSETPOS=1",
+        ] {
+            let err = Recurrence::parse(rule, start).unwrap_err();
+            assert!(err.is::<UnsupportedRule>(), "{rule}");
+        }
+        let err = Recurrence::parse("INTERVAL=2", start).unwrap_err();
+        assert!(!err.is::<UnsupportedRule>());
+    }
+
+    #[test]
+    fn overridden_occurrences_are_left_out() {
+        let contents = "\
+BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup
+DTSTART;VALUE=DATE:20250106
+RRULE:FREQ=DAILY;COUNT=3
+EXDATE;VALUE=DATE:20250108
+END:VEVENT
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup (moved)
+RECURRENCE-ID;VALUE=DATE:20250107
+DTSTART;VALUE=DATE:20250110
+END:VEVENT
+BEGIN:VEVENT
+UID:gym
+SUMMARY:Gym
+DTSTART;VALUE=DATE:20250106
+RRULE:FREQ=MONTHLY;BYDAY=1MO
+END:VEVENT
+BEGIN:VEVENT
+UID:broken
+SUMMARY:Broken
+RRULE:FREQ=WEEKLY
+END:VEVENT
+END:VCALENDAR
+";
+        let mut errors = Vec::new();
+        let nodes = parse_events(contents, "test.ics", &mut errors);
+
+        // Only the first occurrence is left of the original
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].title, ["Standup"]);
+        assert_eq!(nodes[0].metadata.as_ref().unwrap().timestamps.len(), 1);
+        assert_eq!(nodes[1].title, ["Standup (moved)"]);
+
+        // Unsupported rules are skipped, but events that can't be read at all are errors
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].title, "Broken");
+    }
+}
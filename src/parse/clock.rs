@@ -0,0 +1,117 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+
+/// The format of the timestamps in `CLOCK` entries (these are always inactive).
+const CLOCK_FORMAT: &str = "[%Y-%m-%d %a %H:%M]";
+
+/// A single Org-style `CLOCK` entry from the logbook of a node, recording a period of time spent
+/// on it (e.g. `CLOCK: [2025-01-01 Wed 09:00]--[2025-01-01 Wed 10:30] =>  1:30`).
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct ClockEntry {
+    /// When the clock was started.
+    pub start: NaiveDateTime,
+    /// When the clock was stopped, or `None` if it's still running.
+    pub end: Option<NaiveDateTime>,
+}
+impl ClockEntry {
+    /// Gets the number of minutes of this entry which fall on the given date, treating a running
+    /// clock as ending at `now`.
+    pub fn minutes_on(&self, date: NaiveDate, now: NaiveDateTime) -> u32 {
+        self.minutes_in(date, date, now)
+    }
+
+    /// Gets the number of minutes of this entry which fall between `from` and `until`
+    /// (inclusive), treating a running clock as ending at `now`.
+    pub fn minutes_in(&self, from: NaiveDate, until: NaiveDate, now: NaiveDateTime) -> u32 {
+        let range_start = from.and_time(NaiveTime::MIN);
+        let range_end = until.and_time(NaiveTime::MIN) + chrono::Duration::days(1);
+        let start = self.start.max(range_start);
+        let end = self.end.unwrap_or(now).min(range_end);
+        (end - start).num_minutes().max(0) as u32
+    }
+}
+
+/// Parses every `CLOCK` entry in the given body. Malformed entries are ignored.
+pub fn clock_entries(body: &str) -> Vec<ClockEntry> {
+    body.lines()
+        .filter_map(|line| line.trim().strip_prefix("CLOCK:"))
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            match entry.split_once("--") {
+                Some((start, rest)) => {
+                    // The duration after the end isn't needed, because we can work it out
+                    let end = rest.split("=>").next().unwrap().trim();
+                    Some(ClockEntry {
+                        start: parse_clock_ts(start)?,
+                        end: Some(parse_clock_ts(end)?),
+                    })
+                }
+                None => Some(ClockEntry {
+                    start: parse_clock_ts(entry)?,
+                    end: None,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Adds a running clock entry starting at `now` to the logbook at the start of the given body,
+/// creating the logbook if there isn't one.
+pub fn clock_in_body(body: Option<&str>, now: NaiveDateTime) -> String {
+    let entry = format!("CLOCK: {}", now.format(CLOCK_FORMAT));
+    let body = body.unwrap_or("");
+    match body.find(":LOGBOOK:") {
+        // New entries go at the top of the logbook, as Org mode does it
+        Some(idx) => {
+            let after = idx + ":LOGBOOK:".len();
+            format!("{}\n{entry}{}", &body[..after], &body[after..])
+        }
+        None => format!(":LOGBOOK:\n{entry}\n:END:\n{body}"),
+    }
+}
+
+/// Stops any running clock entries in the given body at `now`, returning the new body, or `None`
+/// if no clocks were running.
+pub fn clock_out_body(body: &str, now: NaiveDateTime) -> Option<String> {
+    let mut stopped = false;
+    let lines = body
+        .lines()
+        .map(|line| {
+            let Some(entry) = line.trim().strip_prefix("CLOCK:") else {
+                return line.to_string();
+            };
+            let entry = entry.trim();
+            match parse_clock_ts(entry) {
+                Some(start) if !entry.contains("--") => {
+                    stopped = true;
+                    let minutes = (now - start).num_minutes().max(0);
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    format!(
+                        "{indent}CLOCK: {entry}--{} => {:2}:{:02}",
+                        now.format(CLOCK_FORMAT),
+                        minutes / 60,
+                        minutes % 60
+                    )
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Splitting into lines loses any trailing newline
+    stopped.then(|| {
+        let mut new_body = lines.join("\n");
+        if body.ends_with('\n') {
+            new_body.push('\n');
+        }
+        new_body
+    })
+}
+
+/// Parses an inactive Org timestamp from a `CLOCK` entry. The day of the week is ignored.
+fn parse_clock_ts(s: &str) -> Option<NaiveDateTime> {
+    let inner = s.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let date = NaiveDate::parse_from_str(inner.get(..10)?, "%Y-%m-%d").ok()?;
+    let time = NaiveTime::parse_from_str(inner.rsplit(' ').next()?, "%H:%M").ok()?;
+    Some(date.and_time(time))
+}
@@ -1,34 +1,201 @@
 use super::node::{Format, Node, NodeOptions};
-use anyhow::{bail, Context, Result};
+use super::response_cache::ResponseCache;
+use super::source::NodeSource;
+use super::starling::Starling;
+use crate::exit::Failure;
+use crate::warnings::warn;
+use anyhow::{anyhow, Context, Result};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::time::Duration;
+use ureq::BodyReader;
 
-/// Gets the raw nodes from the given Starling endpoint, filtering automatically to those that meet
+/// Gets the raw nodes from the given source, filtered automatically by Starling to those that meet
 /// the next actions filter (i.e. those with timestamps, keywords, etc.). This will override part
 /// of the provided [`NodeOptions`] to fetch metadata and children, also formatting connections in
 /// Markdown (later parsing requires this).
-pub fn get_raw_action_items(mut opts: NodeOptions, starling_addr: &str) -> Result<Vec<Node>> {
+pub fn get_raw_action_items(opts: NodeOptions, source: &dyn NodeSource) -> Result<Vec<Node>> {
+    source.action_items(opts)?.collect()
+}
+
+/// Fetches the raw action item nodes from Starling. If a timeout is given, it overrides the usual
+/// one, and the request won't be retried (so healthchecks fail fast), or answered from the cache.
+/// Otherwise, the request is made conditional on the last response cached for it, which is used
+/// instead if Starling says nothing has changed.
+pub(super) fn fetch_nodes(
+    mut opts: NodeOptions,
+    starling: &Starling,
+    timeout: Option<Duration>,
+) -> Result<NodeStream> {
     opts.conn_format = Format::Markdown;
     opts.children = true;
     opts.metadata = true;
 
-    let mut res = ureq::get(&format!(
-        "http://{}/index/action_items/nodes",
-        starling_addr
-    ))
-    .config()
-    .http_status_as_error(false)
-    .build()
-    .query("use_bincode", "true")
-    .force_send_body()
-    .send_json(opts)?;
-    if res.status() != 200 {
-        bail!(
-            "failed to fetch nodes from {starling_addr}, received status {}",
-            res.status()
-        );
-    }
+    let cache = timeout
+        .is_none()
+        .then(|| ResponseCache::new(starling, &opts))
+        .flatten();
+    let etag = cache.as_ref().and_then(ResponseCache::etag);
+    let send = || {
+        let mut req = starling.get("index/action_items/nodes");
+        if let Some(etag) = &etag {
+            req = req.header("If-None-Match", etag);
+        }
+        let mut config = req.config().http_status_as_error(false);
+        if let Some(timeout) = timeout {
+            config = config.timeout_global(Some(timeout));
+        }
+        config
+            .build()
+            .query("use_bincode", "true")
+            .force_send_body()
+            .send_json(&opts)
+    };
+    let res = match timeout {
+        Some(_) => send(),
+        None => starling.retry(send),
+    };
+    let res = res
+        .with_context(|| format!("failed to connect to {starling}"))
+        .context(Failure::Starling)?;
+    let reader = match (res.status().as_u16(), cache) {
+        (304, Some(cache)) if etag.is_some() => StreamReader::Cached(cache.open()?),
+        (200, cache) => {
+            let new_etag = res
+                .headers()
+                .get("ETag")
+                .and_then(|etag| etag.to_str().ok())
+                .map(str::to_string);
+            let response = res.into_body().into_reader();
+            match (cache, new_etag) {
+                // The cache is only there to save time, so it's fine if it can't be written
+                (Some(cache), Some(etag)) => match cache.create() {
+                    Ok(file) => StreamReader::Caching {
+                        response,
+                        file,
+                        cache,
+                        etag,
+                    },
+                    Err(err) => {
+                        warn(&format!("failed to cache nodes: {err:#}"));
+                        StreamReader::Response(response)
+                    }
+                },
+                _ => StreamReader::Response(response),
+            }
+        }
+        (status, _) => {
+            return Err(
+                anyhow!("failed to fetch nodes from {starling}, received status {status}")
+                    .context(Failure::Starling),
+            )
+        }
+    };
 
-    bincode::deserialize_from(res.body_mut().as_reader())
+    // Bincode encodes a list as its length, followed by each of its elements in turn
+    let mut stream = NodeStream {
+        reader,
+        remaining: 0,
+        parsed: None,
+    };
+    stream.remaining = bincode::deserialize_from(&mut stream.reader)
         .with_context(|| "failed to deserialize next actions from starling")
+        .context(Failure::Starling)?;
+    if stream.remaining == 0 {
+        stream.finish();
+    }
+    Ok(stream)
+}
+
+/// The nodes in a response from Starling, which are decoded one at a time as they're read (or
+/// nodes which were read from somewhere else in one go).
+pub struct NodeStream {
+    /// Where the rest of the response is read from.
+    reader: StreamReader,
+    /// The number of nodes left to decode.
+    remaining: u64,
+    /// The nodes, if they were read from somewhere other than Starling.
+    parsed: Option<std::vec::IntoIter<Node>>,
+}
+impl NodeStream {
+    /// Creates a stream of nodes which have already been read from somewhere else.
+    pub(super) fn from_nodes(nodes: Vec<Node>) -> Self {
+        Self {
+            reader: StreamReader::Done,
+            remaining: 0,
+            parsed: Some(nodes.into_iter()),
+        }
+    }
+
+    /// Finishes reading the response, committing it to the cache if it's being cached.
+    fn finish(&mut self) {
+        if let StreamReader::Caching {
+            file, cache, etag, ..
+        } = std::mem::replace(&mut self.reader, StreamReader::Done)
+        {
+            if let Err(err) = cache.commit(file, &etag) {
+                warn(&format!("failed to cache nodes: {err:#}"));
+            }
+        }
+    }
+}
+impl Iterator for NodeStream {
+    type Item = Result<Node>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(nodes) = &mut self.parsed {
+            return nodes.next().map(Ok);
+        }
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = bincode::deserialize_from(&mut self.reader)
+            .with_context(|| "failed to deserialize next actions from starling")
+            .context(Failure::Starling);
+        if self.remaining == 0 && node.is_ok() {
+            self.finish();
+        }
+        Some(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if let Some(nodes) = &self.parsed {
+            return nodes.size_hint();
+        }
+        (0, usize::try_from(self.remaining).ok())
+    }
+}
+
+/// Where the nodes in a [`NodeStream`] are read from.
+enum StreamReader {
+    /// A response from Starling which isn't being cached.
+    Response(BodyReader<'static>),
+    /// A response from Starling which is copied into the cache as it's read.
+    Caching {
+        response: BodyReader<'static>,
+        file: BufWriter<File>,
+        cache: ResponseCache,
+        etag: String,
+    },
+    /// A response from Starling which was cached before.
+    Cached(BufReader<File>),
+    /// A response which has been read completely.
+    Done,
+}
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Response(response) => response.read(buf),
+            Self::Caching { response, file, .. } => {
+                let len = response.read(buf)?;
+                file.write_all(&buf[..len])?;
+                Ok(len)
+            }
+            Self::Cached(file) => file.read(buf),
+            Self::Done => Ok(0),
+        }
+    }
 }
 
 /// Skips the given node if it has one of the given completion keywords.
@@ -38,7 +205,13 @@ pub fn skip_complete(node: &Node, done_keywords: &[String]) -> bool {
         .unwrap()
         .keyword
         .as_ref()
-        .is_none_or(|k| !done_keywords.contains(k))
+        .is_none_or(|k| !is_done_keyword(k, done_keywords))
+}
+
+/// Determines whether or not the given keyword marks an item as finished, which it does if it's
+/// one of the given completion keywords. Cancelled items are always treated as finished.
+pub fn is_done_keyword(keyword: &str, done_keywords: &[String]) -> bool {
+    keyword == "CANCELLED" || done_keywords.iter().any(|k| k == keyword)
 }
 
 /// Removes any inactive timestamps from the node.
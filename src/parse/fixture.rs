@@ -0,0 +1,147 @@
+//! A stand-in for Starling in tests, which answers the requests Polaris makes with a recorded set
+//! of nodes, encoded the way each endpoint would encode them. This lets the whole fetch layer run
+//! against something that behaves like Starling on the wire.
+
+use super::node::Node;
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// The nodes recorded from a Starling session, exactly as Starling sent them in JSON.
+const RECORDED_NODES: &str = include_str!("fixtures/starling_nodes.json");
+
+/// Gets every recorded node, as Polaris should see it once it's been fetched.
+pub fn recorded_nodes() -> Vec<Node> {
+    serde_json::from_str(RECORDED_NODES).unwrap()
+}
+
+/// A Starling stand-in listening on a local port, which keeps answering requests until the tests
+/// finish.
+pub struct FixtureServer {
+    /// The address the server is listening on, as Polaris would be given it.
+    pub address: String,
+    /// The request line of every request received so far, in order.
+    requests: Arc<Mutex<Vec<String>>>,
+}
+impl FixtureServer {
+    /// Starts a server for the recorded nodes on a free port.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let nodes: Vec<Value> = serde_json::from_str(RECORDED_NODES).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let server_requests = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A failed request shows up as a failure in the test that made it
+                let _ = answer(stream, &nodes, &server_requests);
+            }
+        });
+
+        Self { address, requests }
+    }
+
+    /// Gets the request line of every request received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Reads a single request from the given connection, and answers it from the given nodes.
+fn answer(mut stream: TcpStream, nodes: &[Value], requests: &Mutex<Vec<String>>) -> io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let line = line.trim().to_string();
+
+    // The body (i.e. the node options) doesn't change the recorded responses, but it still has to
+    // be read so the connection closes cleanly
+    let mut length = None;
+    let mut chunked = false;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.trim().eq_ignore_ascii_case("chunked");
+            }
+        }
+    }
+    if chunked {
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size)?;
+            let size = usize::from_str_radix(size.trim(), 16).unwrap_or(0);
+            // Each chunk (including the last, empty one) ends with a line break
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk)?;
+            if size == 0 {
+                break;
+            }
+        }
+    } else {
+        let mut body = vec![0; length.unwrap_or(0)];
+        reader.read_exact(&mut body)?;
+    }
+    drop(reader);
+    requests.lock().unwrap().push(line.clone());
+
+    let target = line.split_whitespace().nth(1).unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let use_bincode = query.split('&').any(|param| param == "use_bincode=true");
+    let (status, body) = route(path, use_bincode, nodes);
+    write!(
+        stream,
+        "HTTP/1.1 {status} Fixture\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+/// Works out the response to a request for the given path from the given nodes, along with the
+/// status code to send it with.
+fn route(path: &str, use_bincode: bool, nodes: &[Value]) -> (u16, Vec<u8>) {
+    if path == "/index/action_items/nodes" {
+        // Starling's index only has the nodes with keywords (or timestamps, but there aren't any)
+        let items = nodes
+            .iter()
+            .filter(|node| !node["metadata"]["keyword"].is_null())
+            .cloned()
+            .collect::<Vec<_>>();
+        let body = if use_bincode {
+            let items = items
+                .into_iter()
+                .map(|node| serde_json::from_value::<Node>(node).unwrap())
+                .collect::<Vec<_>>();
+            bincode::serialize(&items).unwrap()
+        } else {
+            serde_json::to_vec(&items).unwrap()
+        };
+        return (200, body);
+    }
+    if let Some(id) = path.strip_prefix("/node/") {
+        return match nodes.iter().find(|node| node["id"] == id) {
+            Some(node) => (200, serde_json::to_vec(node).unwrap()),
+            None => (404, b"node not found".to_vec()),
+        };
+    }
+    if let Some(file) = path.strip_prefix("/root-id/") {
+        let file = urlencoding::decode(file).unwrap();
+        let root = nodes
+            .iter()
+            .find(|node| node["parent_id"].is_null() && node["path"] == *file);
+        return match root {
+            Some(node) => (200, serde_json::to_vec(&node["id"]).unwrap()),
+            None => (404, b"file not found".to_vec()),
+        };
+    }
+
+    (404, b"not found".to_vec())
+}
@@ -0,0 +1,179 @@
+use super::Goals;
+use crate::parse::{ActionItem, NodeOptions, NodeSource};
+use crate::views::Paging;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// How well the open work lines up with the goals over some dates, for periodic reviews. Work is
+/// linked to a goal if it (or a stack or node it's under) is connected to the goal's node (for
+/// tagged goals, in either direction), or is linked from the text of the goal or its subgoals.
+#[derive(Serialize, Debug)]
+pub struct Alignment {
+    /// The goals which don't have any open tasks or stacks linked to them.
+    unsupported_goals: Vec<UnsupportedGoal>,
+    /// The open tasks and stacks which aren't linked to any goal. Items under a stack listed here
+    /// aren't listed themselves.
+    unaligned_items: Vec<UnalignedItem>,
+}
+impl Alignment {
+    /// Works out the alignment between the given goals and the open tasks and stacks in the given
+    /// action items, fetching the connections of goals which are nodes of their own from the
+    /// given source.
+    pub fn build(
+        goals: &[Goals],
+        action_items: &HashMap<Uuid, ActionItem>,
+        source: &dyn NodeSource,
+    ) -> Result<Self> {
+        let mut opts = NodeOptions::default();
+        opts.connections = true;
+
+        // Everything each goal links to, along with where it came from
+        let mut goal_links = Vec::new();
+        for day in goals {
+            for (kind, goals) in &day.goals {
+                for goal in goals {
+                    let mut links = HashSet::new();
+                    goal.linked_ids(&mut links);
+                    if let Some(id) = goal.id {
+                        let node = source.node(id, opts).with_context(|| {
+                            format!("failed to get connections of goal {id} (\"{}\")", goal.text)
+                        })?;
+                        links.extend(node.connections.into_keys());
+                        links.extend(node.backlinks.into_keys());
+                    }
+                    goal_links.push((day.date, kind, goal, links));
+                }
+            }
+        }
+
+        // Each open task and stack, with the IDs of it and everything it's under (tagged goals
+        // can be action items themselves, but they aren't work towards themselves)
+        let goal_ids = goal_links
+            .iter()
+            .filter_map(|(_, _, goal, _)| goal.id)
+            .collect::<HashSet<_>>();
+        let mut work = action_items
+            .values()
+            .filter(|item| matches!(item, ActionItem::Task { .. } | ActionItem::Stack { .. }))
+            .filter(|item| !goal_ids.contains(&item.base().id))
+            .map(|item| (item, ancestry(item, action_items)))
+            .collect::<Vec<_>>();
+        work.sort_unstable_by(|(a, _), (b, _)| a.base().title.cmp(&b.base().title));
+
+        let unsupported_goals = goal_links
+            .iter()
+            .filter(|(_, _, _, links)| {
+                !work
+                    .iter()
+                    .any(|(_, ancestry)| ancestry.iter().any(|id| links.contains(id)))
+            })
+            .map(|(date, kind, goal, _)| UnsupportedGoal {
+                date: *date,
+                kind: kind.to_string(),
+                text: goal.text.clone(),
+                id: goal.id,
+            })
+            .collect();
+        let unaligned_items = work
+            .iter()
+            .filter(|(item, ancestry)| {
+                // If the parent is open work too, it'll be unaligned as well, and listed instead
+                let parent_is_work = item.base().parent_id.is_some_and(|parent_id| {
+                    matches!(
+                        action_items.get(&parent_id),
+                        Some(ActionItem::Task { .. } | ActionItem::Stack { .. })
+                    )
+                });
+                !parent_is_work
+                    && !goal_links
+                        .iter()
+                        .any(|(_, _, _, links)| ancestry.iter().any(|id| links.contains(id)))
+            })
+            .map(|(item, _)| UnalignedItem::from_action_item(item))
+            .collect();
+
+        Ok(Self {
+            unsupported_goals,
+            unaligned_items,
+        })
+    }
+
+    /// Cuts down the unsupported goals and unaligned items to only those the given paging
+    /// permits, each on its own.
+    pub fn page(&mut self, paging: &Paging) {
+        paging.apply(&mut self.unsupported_goals);
+        paging.apply(&mut self.unaligned_items);
+    }
+
+    /// Determines whether or not everything is aligned.
+    pub fn is_empty(&self) -> bool {
+        self.unsupported_goals.is_empty() && self.unaligned_items.is_empty()
+    }
+}
+
+/// A goal which doesn't have any open work linked to it.
+#[derive(Serialize, Debug)]
+struct UnsupportedGoal {
+    /// The date the goal was set for.
+    date: NaiveDate,
+    /// The type of goals this is one of (e.g. *Weekly Goals*).
+    kind: String,
+    /// The text of the goal.
+    text: String,
+    /// The ID of the goal's node, if it's a node of its own.
+    id: Option<Uuid>,
+}
+
+/// An open task or stack which isn't linked to any goal.
+#[derive(Serialize, Debug)]
+struct UnalignedItem {
+    /// The ID of the item.
+    id: Uuid,
+    /// The title of the item.
+    title: String,
+    /// The area of focus the item falls under, if any.
+    area: Option<String>,
+    /// Whether the item is a task or a stack.
+    kind: WorkKind,
+}
+impl UnalignedItem {
+    /// Converts the given task or stack into an unaligned item.
+    fn from_action_item(item: &ActionItem) -> Self {
+        let base = item.base();
+        Self {
+            id: base.id,
+            title: base.title.last().cloned().unwrap(),
+            area: base.area.clone(),
+            kind: if matches!(item, ActionItem::Stack { .. }) {
+                WorkKind::Stack
+            } else {
+                WorkKind::Task
+            },
+        }
+    }
+}
+
+/// The kinds of open work goals can be supported by.
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+enum WorkKind {
+    Task,
+    Stack,
+}
+
+/// Gets the IDs of the given item and everything it's under: any action items it's in, and the
+/// node the outermost of those is in.
+fn ancestry(item: &ActionItem, action_items: &HashMap<Uuid, ActionItem>) -> Vec<Uuid> {
+    let mut ids = vec![item.base().id];
+    let mut parent_id = item.base().parent_id;
+    while let Some(id) = parent_id {
+        ids.push(id);
+        parent_id = action_items
+            .get(&id)
+            .and_then(|parent| parent.base().parent_id);
+    }
+    ids
+}
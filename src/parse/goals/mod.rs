@@ -1,19 +1,35 @@
-//! This module extracts goals, whose format is *highly* unique to my system. If you use a
-//! different system, you will either need to modify this file's extraction systems, or just ignore
-//! this part of Polaris entirely. Deliberately, goal checks will only be run if you explicitly
-//! request them (unlike the rest of the system, which validates everything no matter what you
-//! request).
+//! This module extracts goals, which are Markdown lists in the bodies of particular nodes. Where
+//! those nodes are varies massively between people's systems, so the types of goals (e.g. *Daily
+//! Goals*, *Weekly Goals*) and where each is found are set under `goals` in the config file (see
+//! [`GoalType`]). Deliberately, goal checks will only be run if you explicitly request them
+//! (unlike the rest of the system, which validates everything no matter what you request).
 
-mod personal;
+mod alignment;
+
+pub use alignment::Alignment;
 
 use super::NodeOptions;
-use crate::parse::Node;
-use anyhow::{bail, Context, Result};
-use chrono::NaiveDate;
-use serde::Serialize;
-use std::collections::VecDeque;
+use crate::parse::{Node, NodeSource};
+use anyhow::{bail, Context, Error, Result};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    fmt::Write,
+    hash::Hash,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
 use uuid::Uuid;
 
+/// The most goal sources that are fetched at once, so a long range of dates doesn't flood the
+/// source with requests.
+const MAX_FETCH_THREADS: usize = 8;
+
 /// A list of goals for a single day.
 #[derive(Serialize, Debug)]
 pub struct Goals {
@@ -24,25 +40,176 @@ pub struct Goals {
     /// human-readable things like *Daily Goals* or *Weekly Goals*, and the values are lists of the
     /// actual goals that have been set. This is stored as a vector rather than a map to allow
     /// custom ordering.
-    goals: Vec<(String, Vec<String>)>,
+    goals: Vec<(String, Vec<Goal>)>,
 }
 impl Goals {
-    /// Extracts goals for the given date.
-    pub fn extract(date: NaiveDate, starling_addr: &str) -> Result<Self> {
-        // Get the goal types/sources for this date, then convert them into real goals
-        let goals = personal::goals_for_date(date)
+    /// Determines whether or not there are no goals set at all.
+    pub fn is_empty(&self) -> bool {
+        self.goals.iter().all(|(_, goals)| goals.is_empty())
+    }
+
+    /// Extracts the given types of goals for each date from `from` to `until` (inclusive), using
+    /// the given template to find journal files for any types which don't have a file of their
+    /// own.
+    ///
+    /// Goals which are in the same place as they were the day before (e.g. weekly goals, which
+    /// are the same all week) are only included on the first day they're in that place, so each
+    /// set of goals appears exactly once.
+    pub fn extract(
+        from: NaiveDate,
+        until: NaiveDate,
+        types: &[GoalType],
+        journal_template: &PathTemplate,
+        done_keywords: &[String],
+        source: &dyn NodeSource,
+    ) -> Result<Vec<Self>> {
+        // Get the goal types/sources for each date, skipping any that haven't moved since the day
+        // before
+        let mut last_sources = vec![None; types.len()];
+        let mut days = Vec::new();
+        for date in from.iter_days().take_while(|date| *date <= until) {
+            let mut sources = Vec::new();
+            for (ty, last_source) in types.iter().zip(&mut last_sources) {
+                let goals_source = ty.source_for(date, journal_template);
+                if let Some(goals_source) = &goals_source {
+                    if Some(goals_source) != last_source.as_ref() {
+                        sources.push((ty.name.clone(), goals_source.clone()));
+                    }
+                }
+                *last_source = goals_source;
+            }
+            days.push((date, sources));
+        }
+
+        // Convert the sources into real goals. These are independent, so they're fetched in
+        // parallel by a few threads that each take the next source until there are none left,
+        // sharing anything they have in common (e.g. several headings in the same journal file).
+        let fetcher = GoalsFetcher {
+            source,
+            done_keywords,
+            nodes: FetchCache::default(),
+            roots: FetchCache::default(),
+            tagged: FetchCache::default(),
+        };
+        let jobs = days
+            .iter()
+            .flat_map(|(_, sources)| sources)
+            .collect::<Vec<_>>();
+        let next_job = AtomicUsize::new(0);
+        let results = Mutex::new((0..jobs.len()).map(|_| None).collect::<Vec<_>>());
+        std::thread::scope(|scope| {
+            for _ in 0..MAX_FETCH_THREADS.min(jobs.len()) {
+                scope.spawn(|| loop {
+                    let idx = next_job.fetch_add(1, Ordering::Relaxed);
+                    let Some((name, goals_source)) = jobs.get(idx) else {
+                        break;
+                    };
+                    let res = goals_source
+                        .clone()
+                        .into_goals(&fetcher)
+                        .map(|goals| (name.clone(), goals));
+                    results.lock().unwrap()[idx] = Some(res);
+                });
+            }
+        });
+
+        // Every job was run, and they're in the same order as the days' sources
+        let mut results = results
+            .into_inner()
+            .unwrap()
             .into_iter()
-            .map(|(name, goals_source)| {
-                goals_source
-                    .into_goals(starling_addr)
-                    .map(|goals| (name, goals))
+            .map(Option::unwrap);
+        days.into_iter()
+            .map(|(date, sources)| {
+                let goals = results
+                    .by_ref()
+                    .take(sources.len())
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| {
+                        format!("failed to extract goals for date {date} from sources")
+                    })?;
+                Ok(Self { date, goals })
             })
-            .collect::<Result<Vec<_>>>()
-            .with_context(|| format!("failed to extract goals for date {date} from sources"))?;
+            .collect()
+    }
+}
+
+/// A single goal, from one item in a Markdown list.
+#[derive(Serialize, Clone, Debug)]
+pub struct Goal {
+    /// The text of the goal, without any checkbox.
+    text: String,
+    /// The ID of the node this goal is, if it's a node of its own (i.e. it's a tagged goal).
+    id: Option<Uuid>,
+    /// Whether or not the goal has been done, if it had a checkbox (`[ ]` or `[x]`).
+    done: Option<bool>,
+    /// The goals nested under this one (e.g. the steps of a bigger goal).
+    subgoals: Vec<Goal>,
+}
+impl Goal {
+    /// Parses the given item of a Markdown list (without the leading `- `) into a goal, without any
+    /// subgoals.
+    fn parse(item: &str) -> Self {
+        let (done, text) = match item.get(..3) {
+            Some("[ ]") => (Some(false), &item[3..]),
+            Some("[x]" | "[X]") => (Some(true), &item[3..]),
+            _ => (None, item),
+        };
+        Self {
+            text: text.trim_start().to_string(),
+            id: None,
+            done,
+            subgoals: Vec::new(),
+        }
+    }
+
+    /// Parses the Markdown list in the given body into goals, nesting items indented under other
+    /// items as their subgoals. Anything not in the list is ignored.
+    fn parse_list(body: &str) -> Vec<Self> {
+        let mut goals: Vec<Self> = Vec::new();
+        // The indentation of each goal on the path to the last one parsed
+        let mut indents: Vec<usize> = Vec::new();
+        for line in body.lines() {
+            let unindented = line.trim_start();
+            // We only want lines starting with `- ` (implicitly filters out trimmed empty lists
+            // with just `-` as well as empty ones)
+            let Some(item) = unindented.trim_end().strip_prefix("- ") else {
+                continue;
+            };
+            let indent = line.len() - unindented.len();
 
-        Ok(Self { date, goals })
+            // Go back up to the goal this one is nested under (if any), and add it there
+            while indents.last().is_some_and(|last| *last >= indent) {
+                indents.pop();
+            }
+            let mut siblings = &mut goals;
+            for _ in 0..indents.len() {
+                siblings = &mut siblings.last_mut().unwrap().subgoals;
+            }
+            siblings.push(Self::parse(item));
+            indents.push(indent);
+        }
+
+        goals
+    }
+
+    /// Adds the IDs of everything this goal links to (including its own node, if it has one) to
+    /// the given set. Links are found in the text of the goal and its subgoals by the IDs in them,
+    /// so they work for both Markdown and Org links.
+    fn linked_ids(&self, ids: &mut HashSet<Uuid>) {
+        ids.extend(self.id);
+        ids.extend(
+            self.text
+                .split(|c: char| !(c.is_ascii_hexdigit() || c == '-'))
+                .filter(|word| word.len() == 36)
+                .filter_map(|word| Uuid::parse_str(word).ok()),
+        );
+        for subgoal in &self.subgoals {
+            subgoal.linked_ids(ids);
+        }
     }
 }
+
 impl Default for Goals {
     fn default() -> Self {
         // Default to an empty goals list for today
@@ -53,6 +220,262 @@ impl Default for Goals {
     }
 }
 
+/// A type of goals, as set under `goals` in the config file, which says where goals of this type
+/// are found for any given date. These are either always in the body of the same node (with
+/// `id`), each a node of their own with a particular tag (with `tag`), or in a heading in a file
+/// which depends on the date:
+///
+/// ```json
+/// "goals": [
+///     { "name": "Daily Goals", "heading": ["Goals for Tomorrow"] },
+///     { "name": "Weekly Goals", "heading": ["Goals for Next Week"], "from_last": "sun" },
+///     { "name": "Quarterly Goals", "file": "goals/%Y-Q%q.md", "cadence": "quarter" },
+///     { "name": "Projects", "tag": "goal_2025q1", "date_property": "GOAL_DATE" },
+///     { "name": "Surfaces", "id": "9a73deb2-e702-47d0-8967-dc82de424237" }
+/// ]
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct GoalType {
+    /// The human-readable name of this type of goals.
+    pub name: String,
+    /// The ID of the node these goals are always in, in which case none of the options for files
+    /// can be set.
+    #[serde(default)]
+    id: Option<Uuid>,
+    /// The tag on the nodes which are each one of these goals, in which case none of the options
+    /// for files can be set. Each node's title is the goal, with its keyword saying whether it's
+    /// been done (if it has one), and any list in its body as subgoals.
+    #[serde(default)]
+    tag: Option<String>,
+    /// A property on tagged nodes holding the date (`YYYY-MM-DD`) each goal is for, if they're
+    /// only for particular dates. Nodes without it are goals on every date.
+    #[serde(default)]
+    date_property: Option<String>,
+    /// The template for the path of the file these goals are in, defaulting to the journal
+    /// template.
+    #[serde(default)]
+    file: Option<PathTemplate>,
+    /// The titles of the headings leading to the one these goals are in, from the top level of
+    /// the file down, which is empty to read from the root of the file.
+    #[serde(default)]
+    heading: Vec<String>,
+    /// A day of the week to read the file for the last of before the date, rather than the file
+    /// for the date itself (e.g. `sun` for weekly goals written each Sunday for the week after).
+    #[serde(default)]
+    from_last: Option<Weekday>,
+    /// How often these goals are set. For anything longer than a day, the file is the one for the
+    /// first day of the month, quarter, or year the date is in (so its template will usually
+    /// only use placeholders like `%Y`, `%q`, and `%m`).
+    #[serde(default)]
+    cadence: Cadence,
+    /// Whether or not goal extraction should fail if the heading isn't in the file, rather than
+    /// just finding no goals.
+    #[serde(default = "default_required")]
+    required: bool,
+    /// The days of the week these goals are extracted on, which is every day if this is empty.
+    #[serde(default)]
+    on: Vec<Weekday>,
+}
+impl GoalType {
+    /// The types of goals used if none are configured: headings for the day and week ahead in
+    /// each daily journal file, with the weekly ones written on the last day of each week (given
+    /// the day weeks start on).
+    pub fn defaults(week_start: Weekday) -> Vec<Self> {
+        let journal_heading = |name: &str, heading: &str, from_last| Self {
+            name: name.to_string(),
+            id: None,
+            tag: None,
+            date_property: None,
+            file: None,
+            heading: vec![heading.to_string()],
+            from_last,
+            cadence: Cadence::Day,
+            required: true,
+            on: Vec::new(),
+        };
+        vec![
+            journal_heading("Daily Goals", "Goals for Tomorrow", None),
+            journal_heading(
+                "Weekly Goals",
+                "Goals for Next Week",
+                Some(week_start.pred()),
+            ),
+        ]
+    }
+
+    /// Makes sure this type of goals doesn't mix a node ID or tag with each other or options for
+    /// files, or a `from_last` weekday with a cadence it doesn't make sense for.
+    pub fn validate(&self) -> Result<()> {
+        let has_file_options = self.file.is_some()
+            || !self.heading.is_empty()
+            || self.from_last.is_some()
+            || self.cadence != Cadence::Day;
+        if self.id.is_some() && (self.tag.is_some() || has_file_options) {
+            bail!(
+                "goals `{}` have an id, so they can't have a tag, file, heading, `from_last`, or cadence",
+                self.name
+            );
+        }
+        if self.tag.is_some() && has_file_options {
+            bail!(
+                "goals `{}` have a tag, so they can't have a file, heading, `from_last`, or cadence",
+                self.name
+            );
+        }
+        if self.date_property.is_some() && self.tag.is_none() {
+            bail!(
+                "goals `{}` have a `date_property`, which only applies to tagged goals",
+                self.name
+            );
+        }
+        if self.from_last.is_some() && self.cadence != Cadence::Day {
+            bail!(
+                "goals `{}` are set every {}, so they can't be read from the last particular weekday",
+                self.name,
+                self.cadence
+            );
+        }
+        Ok(())
+    }
+
+    /// Gets where goals of this type are found for the given date, if they're extracted on it at
+    /// all.
+    fn source_for(&self, date: NaiveDate, journal_template: &PathTemplate) -> Option<GoalsSource> {
+        if !self.on.is_empty() && !self.on.contains(&date.weekday()) {
+            return None;
+        }
+        if let Some(id) = self.id {
+            return Some(GoalsSource::Id(id));
+        }
+        if let Some(tag) = &self.tag {
+            return Some(GoalsSource::Tagged {
+                tag: tag.clone(),
+                date_property: self
+                    .date_property
+                    .as_ref()
+                    .map(|property| (property.clone(), date)),
+            });
+        }
+
+        // The file on the day itself won't have been written yet, so if the date falls on the
+        // given weekday, go back a whole week
+        let file_date = match self.from_last {
+            Some(weekday) => {
+                let days_since = (date.weekday().num_days_from_monday() + 6
+                    - weekday.num_days_from_monday())
+                    % 7
+                    + 1;
+                date - Duration::days(days_since as i64)
+            }
+            None => self.cadence.start(date),
+        };
+        Some(GoalsSource::File {
+            path: self
+                .file
+                .as_ref()
+                .unwrap_or(journal_template)
+                .render(file_date),
+            heading_path: self.heading.clone(),
+            fail_on_missing_heading: self.required,
+        })
+    }
+}
+
+/// How often a type of goals is set, which decides which file they're read from.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Cadence {
+    /// Goals are set for each day (or each week, with `from_last`), in the file for that day.
+    #[default]
+    Day,
+    /// Goals are set for each month, in the file for its first day.
+    Month,
+    /// Goals are set for each quarter of the year, in the file for its first day.
+    Quarter,
+    /// Goals are set for each year, in the file for its first day.
+    Year,
+}
+impl Cadence {
+    /// Gets the first day of the period of this cadence the given date is in.
+    fn start(&self, date: NaiveDate) -> NaiveDate {
+        let month = match self {
+            Self::Day => return date,
+            Self::Month => date.month(),
+            Self::Quarter => (date.month0() / 3) * 3 + 1,
+            Self::Year => 1,
+        };
+        NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap()
+    }
+}
+impl fmt::Display for Cadence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Day => write!(f, "day"),
+            Self::Month => write!(f, "month"),
+            Self::Quarter => write!(f, "quarter"),
+            Self::Year => write!(f, "year"),
+        }
+    }
+}
+
+/// Goal types which don't say otherwise fail if their heading is missing.
+fn default_required() -> bool {
+    true
+}
+
+/// A template for the path of a file relative to the Starling root, which can contain `strftime`
+/// placeholders for the parts of a date (e.g. `journals/%Y/%m/%d.md`).
+///
+/// Either `/` or `\` can be used as a path separator, and they will be normalised to the `/`
+/// Starling expects, so templates written for Windows-synced vaults work unchanged.
+#[derive(Clone, Debug)]
+pub struct PathTemplate(String);
+impl PathTemplate {
+    /// Renders this template for the given date.
+    pub fn render(&self, date: NaiveDate) -> String {
+        // Validated when the template was parsed, so this can't fail
+        date.format(&self.0).to_string()
+    }
+}
+impl FromStr for PathTemplate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalised = s
+            .split(['/', '\\'])
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect::<Vec<_>>()
+            .join("/");
+        if normalised.is_empty() {
+            bail!("path template must not be empty");
+        }
+        if s.starts_with(['/', '\\', '~']) || normalised.split('/').next().unwrap().ends_with(':') {
+            bail!("path template {s} must be relative to the starling root");
+        }
+
+        // Formatting will fail on the first date if any placeholders are invalid (or refer to
+        // times, which we don't have), so check now rather than when we come to use it
+        let mut rendered = String::new();
+        if write!(rendered, "{}", NaiveDate::MIN.format(&normalised)).is_err() {
+            bail!("path template {s} contains invalid placeholders (only date placeholders like `%Y`, `%m`, and `%d` are supported)");
+        }
+
+        Ok(Self(normalised))
+    }
+}
+impl<'de> Deserialize<'de> for PathTemplate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Different places goals can come from. This provides an abstraction over the final approach of
 /// extracting goals from a node with a known ID and allows for more "natural" strategies like
 /// specifying a daily journal file and a heading inside it where goals can be found.
@@ -62,7 +485,8 @@ impl Default for Goals {
 ///
 /// Additional strategies will likely be added here in future.
 #[non_exhaustive]
-pub(super) enum GoalsSource {
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum GoalsSource {
     /// Goals will be found in the body of the node with this ID.
     Id(Uuid),
     /// Goals will be found in the file with this path, inside the heading with this name.
@@ -88,44 +512,49 @@ pub(super) enum GoalsSource {
         /// and that's okay.
         fail_on_missing_heading: bool,
     },
+    /// Goals will be found as nodes of their own, each with this tag.
+    Tagged {
+        /// The tag every goal node has.
+        tag: String,
+        /// The property holding the date each goal is for, and the date goals are being found
+        /// for. Nodes with a different date in the property aren't goals for this date, but nodes
+        /// without one are goals for every date.
+        date_property: Option<(String, NaiveDate)>,
+    },
 }
 impl GoalsSource {
     /// Converts this [`GoalsSource`] into the actual goals it references.
-    fn into_goals(self, starling_addr: &str) -> Result<Vec<String>> {
-        // Helper function to get the details of the node with the given ID
-        fn get_node_details(
-            node_id: Uuid,
-            diagnostic_title: &str,
-            starling_addr: &str,
-        ) -> Result<Node> {
-            // We'll get both the children in case we need to do further traversal, and the
-            // body in case this is the last node in the path
-            let mut opts = NodeOptions::default();
-            opts.body = true;
-            opts.children = true;
-
-            let mut res = ureq::get(&format!("http://{starling_addr}/node/{node_id}"))
-                .config()
-                .http_status_as_error(false)
-                .build()
-                .query("use_bincode", "false")
-                .force_send_body()
-                .send_json(opts)?;
-            if res.status() != 200 {
-                bail!(
-                    "failed to get node details for node {node_id} (\"{diagnostic_title}\"), received status {}",
-                    res.status()
-                );
-            }
-
-            let node_details: Node = serde_json::from_reader(res.body_mut().as_reader())
-                .with_context(|| format!("failed to deserialize node details from starling for node {node_id} (\"{diagnostic_title}\")"))?;
-            Ok(node_details)
-        }
-
+    fn into_goals(self, fetcher: &GoalsFetcher) -> Result<Vec<Goal>> {
         let body = match self {
-            GoalsSource::Id(id) => get_node_details(id, "RAW ID GIVEN", starling_addr)
+            GoalsSource::Id(id) => fetcher
+                .node(id, "RAW ID GIVEN")
                 .map(|node| node.body.unwrap())?,
+            GoalsSource::Tagged { tag, date_property } => {
+                let mut goals = Vec::new();
+                for node in fetcher.tagged(&tag)? {
+                    let metadata = node.metadata.unwrap_or_default();
+                    if let Some((property, date)) = &date_property {
+                        if let Some(node_date) = metadata.properties.get(property) {
+                            let node_date = node_date.parse::<NaiveDate>().with_context(|| {
+                                format!("failed to parse {property} on goal {} as a date", node.id)
+                            })?;
+                            if node_date != *date {
+                                continue;
+                            }
+                        }
+                    }
+
+                    goals.push(Goal {
+                        text: node.title.last().cloned().unwrap_or_default(),
+                        id: Some(node.id),
+                        done: metadata
+                            .keyword
+                            .map(|keyword| fetcher.done_keywords.contains(&keyword)),
+                        subgoals: Goal::parse_list(node.body.as_deref().unwrap_or_default()),
+                    });
+                }
+                return Ok(goals);
+            }
             GoalsSource::File {
                 path,
                 heading_path,
@@ -138,31 +567,10 @@ impl GoalsSource {
                     bail!("goal file path must be relative to the starling root, but got: {path} (also should not start with `/`)");
                 }
 
-                // Get the root ID of that path (no `bincode` support on this endpoint)
-                let path_url = urlencoding::encode(&path);
-                let mut res = ureq::get(&format!("http://{starling_addr}/root-id/{path_url}"))
-                    .config()
-                    .http_status_as_error(false)
-                    .build()
-                    .call()?;
-                if res.status() != 200 {
-                    bail!(
-                        "failed to get root id for file {path}, received status {}",
-                        res.status()
-                    );
-                }
-                let root_id: String = serde_json::from_reader(res.body_mut().as_reader())
-                    .with_context(|| {
-                        format!("failed to deserialize root id from starling for file {path}")
-                    })?;
-                let root_id = Uuid::parse_str(&root_id).with_context(|| {
-                    format!("failed to parse root id {root_id} for file {path}")
-                })?;
-
-                // Now get the details of the root ID, and go through the heading path until we
-                // find the right node
-                let mut current_node =
-                    get_node_details(root_id, &format!("root of {path}"), starling_addr)?;
+                // Get the details of the root of the file, and go through the heading path until
+                // we find the right node
+                let root_id = fetcher.root_id(&path)?;
+                let mut current_node = fetcher.node(root_id, &format!("root of {path}"))?;
                 while let Some(next_title) = heading_path.pop_front() {
                     let mut next_id = None;
                     for (child_id, child_title) in current_node.children {
@@ -172,11 +580,8 @@ impl GoalsSource {
                         }
                     }
                     if let Some(next_id) = next_id {
-                        current_node = get_node_details(
-                            next_id,
-                            &format!("heading {next_title} in {path}"),
-                            starling_addr,
-                        )?;
+                        current_node =
+                            fetcher.node(next_id, &format!("heading {next_title} in {path}"))?;
                     } else if fail_on_missing_heading {
                         bail!(
                             "failed to find heading {next_title} in file {path}, which is required for goal extraction (`fail_on_missing_heading` was set to `true`)"
@@ -192,14 +597,88 @@ impl GoalsSource {
             }
         };
 
-        // Parse the body
-        Ok(body
-            .lines()
-            .map(|l| l.trim())
-            // We only want lines starting with `- ` (implicitly filters out trimmed empty lists with
-            // just `-` as well as empty ones)
-            .filter_map(|l| l.strip_prefix("- "))
-            .map(|l| l.to_string())
-            .collect::<Vec<_>>())
+        Ok(Goal::parse_list(&body))
+    }
+}
+
+/// Fetches nodes and the roots of files for goal sources, remembering everything it has fetched so
+/// sources in the same file don't fetch the same nodes twice.
+struct GoalsFetcher<'a> {
+    /// The source to fetch from.
+    source: &'a dyn NodeSource,
+    /// The keywords which mark tagged goals as done.
+    done_keywords: &'a [String],
+    /// The nodes fetched so far, keyed by ID.
+    nodes: FetchCache<Uuid, Node>,
+    /// The IDs of the roots of the files fetched so far, keyed by path.
+    roots: FetchCache<String, Uuid>,
+    /// The nodes with each tag fetched so far, keyed by tag.
+    tagged: FetchCache<String, Vec<Node>>,
+}
+impl GoalsFetcher<'_> {
+    /// Gets the details of the node with the given ID, with both its children (in case we need to
+    /// do further traversal) and its body (in case it's the last node in a heading path).
+    fn node(&self, node_id: Uuid, diagnostic_title: &str) -> Result<Node> {
+        self.nodes.get(node_id, || {
+            let mut opts = NodeOptions::default();
+            opts.body = true;
+            opts.children = true;
+
+            self.source.node(node_id, opts).with_context(|| {
+                format!("failed to get node details for node {node_id} (\"{diagnostic_title}\")")
+            })
+        })
+    }
+
+    /// Gets the ID of the root node of the file with the given path.
+    fn root_id(&self, path: &str) -> Result<Uuid> {
+        self.roots.get(path.to_string(), || {
+            self.source
+                .root_id(path)
+                .with_context(|| format!("failed to get root id for file {path}"))
+        })
+    }
+
+    /// Gets the nodes with the given tag, with their bodies (for subgoals) and metadata (for
+    /// keywords and dates).
+    fn tagged(&self, tag: &str) -> Result<Vec<Node>> {
+        self.tagged.get(tag.to_string(), || {
+            let mut opts = NodeOptions::default();
+            opts.body = true;
+            opts.metadata = true;
+
+            self.source
+                .tagged(tag, opts)
+                .with_context(|| format!("failed to get nodes tagged {tag}"))
+        })
+    }
+}
+
+/// Values fetched for goal sources, keyed by what they were fetched for. Each key has its own
+/// slot, and only one thread fetches into a slot at a time, so sources that need the same thing at
+/// once wait for a single fetch of it rather than all sending their own.
+struct FetchCache<K, V> {
+    slots: Mutex<HashMap<K, Arc<(Mutex<()>, OnceLock<V>)>>>,
+}
+impl<K, V> Default for FetchCache<K, V> {
+    fn default() -> Self {
+        Self {
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+}
+impl<K: Eq + Hash, V: Clone> FetchCache<K, V> {
+    /// Gets the value for the given key, fetching it with the given function if it hasn't been
+    /// fetched yet. If the fetch fails, nothing is stored, so the next thread to ask will try again
+    /// (and report its own error).
+    fn get(&self, key: K, fetch: impl FnOnce() -> Result<V>) -> Result<V> {
+        let slot = self.slots.lock().unwrap().entry(key).or_default().clone();
+        let (fetching, value) = &*slot;
+        let _fetching = fetching.lock().unwrap();
+        if let Some(value) = value.get() {
+            return Ok(value.clone());
+        }
+        let fetched = fetch()?;
+        Ok(value.get_or_init(|| fetched).clone())
     }
 }
@@ -0,0 +1,69 @@
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// The shortest a short ID will be, even if a shorter prefix would be unambiguous, so short IDs
+/// don't all change as soon as a few more nodes are added.
+const MIN_SHORT_ID_LEN: usize = 6;
+
+/// Resolves a reference to a node given by the user to its full ID. This can be one of the given
+/// aliases, a full ID, or a prefix of an ID (with or without hyphens) which only matches one of
+/// the IDs `ids` returns. Those are only fetched if they're needed.
+pub fn resolve_id(
+    reference: &str,
+    aliases: &BTreeMap<String, Uuid>,
+    ids: impl FnOnce() -> Result<Vec<Uuid>>,
+) -> Result<Uuid> {
+    if let Some(id) = aliases.get(reference) {
+        return Ok(*id);
+    }
+    if let Ok(id) = Uuid::parse_str(reference) {
+        return Ok(id);
+    }
+
+    let prefix = reference.replace('-', "").to_lowercase();
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("`{reference}` is neither an alias nor (part of) an id");
+    }
+    let matches = ids()?
+        .into_iter()
+        .filter(|id| id.simple().to_string().starts_with(&prefix))
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [id] => Ok(*id),
+        [] => bail!("no item has an id starting with `{reference}`"),
+        _ => bail!(
+            "`{reference}` is ambiguous, it could be any of: {}",
+            matches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Generates the shortest unambiguous prefix of each of the given IDs (without hyphens), which
+/// can be used anywhere a full ID can be, as long as no new nodes are added that share it.
+pub fn short_ids(ids: impl IntoIterator<Item = Uuid>) -> HashMap<Uuid, String> {
+    let mut ids = ids
+        .into_iter()
+        .map(|id| (id.simple().to_string(), id))
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.dedup();
+
+    // In sorted order, the IDs sharing the longest prefix with any ID are always its neighbours
+    let shared = |a: &str, b: &str| a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count();
+    (0..ids.len())
+        .map(|idx| {
+            let (simple, id) = &ids[idx];
+            let before = idx
+                .checked_sub(1)
+                .map_or(0, |prev| shared(&ids[prev].0, simple));
+            let after = ids.get(idx + 1).map_or(0, |(next, _)| shared(next, simple));
+            let len = (before.max(after) + 1).clamp(MIN_SHORT_ID_LEN, simple.len());
+            (*id, simple[..len].to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,70 @@
+use serde::Serialize;
+use std::{fmt, path::PathBuf};
+use uuid::Uuid;
+
+/// A record of an item which couldn't be processed, and was skipped because Polaris was run
+/// leniently.
+#[derive(Serialize, Clone, Debug)]
+pub struct ItemError {
+    /// The ID of the node the error came from.
+    pub id: Uuid,
+    /// The title of the node the error came from.
+    pub title: String,
+    /// The path of the file the node is in.
+    pub path: PathBuf,
+    /// The stage the error happened at.
+    pub kind: ItemErrorKind,
+    /// A description of what went wrong.
+    pub message: String,
+}
+impl fmt::Display for ItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}): {} error: {}",
+            self.path.display(),
+            self.title,
+            self.id,
+            self.kind,
+            self.message
+        )
+    }
+}
+
+/// The stages at which an item can fail to be processed.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemErrorKind {
+    /// The node couldn't be parsed into an action item at all (e.g. because it had an unknown
+    /// keyword).
+    Parse,
+    /// The action item couldn't be extracted as a daily note.
+    DailyNote,
+    /// The action item couldn't be extracted as a tickle.
+    Tickle,
+    /// The action item couldn't be extracted as a person date.
+    PersonDate,
+    /// The action item couldn't be extracted as a waiting item.
+    Waiting,
+    /// The action item couldn't be extracted as a stack.
+    Stack,
+    /// The action item couldn't be extracted as a task.
+    Task,
+    /// The action item couldn't be extracted as a holiday.
+    Holiday,
+}
+impl fmt::Display for ItemErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Parse => "parse",
+            Self::DailyNote => "daily note",
+            Self::Tickle => "tickle",
+            Self::PersonDate => "person date",
+            Self::Waiting => "waiting item",
+            Self::Stack => "stack",
+            Self::Task => "task",
+            Self::Holiday => "holiday",
+        };
+        write!(f, "{name}")
+    }
+}
@@ -0,0 +1,371 @@
+use super::node::{Format, Node, NodeMetadata, NodeOptions};
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// The keywords which mark action items, other than completion keywords.
+const ACTION_KEYWORDS: &[&str] = &["TODO", "NEXT", "HOLD", "WAIT", "NOTE", "STACK", "CANCELLED"];
+
+thread_local! {
+    /// The keywords to recognise while parsing a directory. `orgish` parses keywords without any
+    /// context (Starling reads them from its global configuration), so they're set here for the
+    /// duration of a read.
+    static KEYWORDS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// A keyword on a heading, which is one of the keywords set in [`KEYWORDS`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LocalKeyword(String);
+impl orgish::Keyword for LocalKeyword {
+    fn from_str(keyword: &str) -> Option<Self> {
+        KEYWORDS
+            .with(|keywords| keywords.borrow().contains(keyword))
+            .then(|| Self(keyword.to_string()))
+    }
+    fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// The `ID` property of a heading, if it has one. Unlike Starling, we never write IDs back, so
+/// headings without one are given a stable one instead (see [`stable_id`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LocalId(Option<Uuid>);
+impl orgish::ParseId for LocalId {
+    type Error = uuid::Error;
+
+    fn parse_id(id: Option<&str>) -> Result<Self, Self::Error> {
+        id.map(Uuid::parse_str).transpose().map(Self)
+    }
+}
+
+type LocalNode = orgish::Node<LocalKeyword, LocalId>;
+
+/// Reads every Markdown and Org file under the given directory into nodes, in the same form
+/// Starling would give them, so Polaris can run without a Starling instance. Files are parsed
+/// with `orgish`, like Starling parses them, and keywords are recognised if they're one of the
+/// usual action keywords or one of the given completion keywords.
+///
+/// Nodes which don't have an `ID` property are given one derived from their path and titles, which
+/// stays the same as long as they aren't moved or renamed. Siblings with the same title are told
+/// apart by which of them comes first. Connections aren't worked out at all.
+pub fn read_dir_nodes(
+    dir: &Path,
+    opts: NodeOptions,
+    done_keywords: &[String],
+) -> Result<Vec<Node>> {
+    let keywords = ACTION_KEYWORDS
+        .iter()
+        .map(|kw| kw.to_string())
+        .chain(done_keywords.iter().cloned())
+        .collect::<HashSet<_>>();
+    KEYWORDS.with(|kws| *kws.borrow_mut() = keywords);
+
+    let mut files = Vec::new();
+    find_files(dir, &mut files)?;
+    let mut nodes = Vec::new();
+    for (path, format) in files {
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let rel_path = path.strip_prefix(dir).unwrap_or(&path);
+        nodes.extend(
+            parse_nodes(&contents, format, rel_path, opts)
+                .with_context(|| format!("failed to parse {}", path.display()))?,
+        );
+    }
+
+    Ok(nodes)
+}
+
+/// Finds every Markdown and Org file under the given directory, skipping hidden files and
+/// directories, in a stable order.
+fn find_files(dir: &Path, files: &mut Vec<(PathBuf, Format)>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory {}", dir.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("failed to read directory {}", dir.display()))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            find_files(&path, files)?;
+            continue;
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md") => files.push((path, Format::Markdown)),
+            Some("org") => files.push((path, Format::Org)),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the given contents of the file at the given relative path into nodes, one for the file
+/// itself and one for each heading in it, in document order.
+fn parse_nodes(
+    contents: &str,
+    format: Format,
+    rel_path: &Path,
+    opts: NodeOptions,
+) -> Result<Vec<Node>> {
+    let format = match format {
+        Format::Markdown => orgish::Format::Markdown,
+        Format::Org => orgish::Format::Org,
+    };
+    let document = orgish::Document::<LocalKeyword, LocalId>::from_str(contents, format)?;
+
+    // Files without a title are named after their path, as Starling names them
+    let mut title = document.root.title().to_string();
+    if title.is_empty() {
+        title = rel_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+    }
+    let key = format!("{}\0{title}", rel_path.display());
+    let mut nodes = Vec::new();
+    build_nodes(&document.root, title, key, None, rel_path, opts, &mut nodes);
+
+    Ok(nodes)
+}
+
+/// What a node needs to know about its parent.
+struct Parent {
+    id: Uuid,
+    /// The key the parent's stable ID was (or would have been) derived from.
+    key: String,
+    /// The titles of the parent and all its ancestors.
+    title: Vec<String>,
+    /// The tags on the parent and all its ancestors.
+    tags: HashSet<String>,
+}
+
+/// Converts the given `orgish` node and all its children into nodes, which are pushed in document
+/// order, returning the ID of the given node. The key is what its stable ID will be derived from
+/// if it doesn't have an ID of its own.
+fn build_nodes(
+    node: &LocalNode,
+    title: String,
+    key: String,
+    parent: Option<&Parent>,
+    rel_path: &Path,
+    opts: NodeOptions,
+    nodes: &mut Vec<Node>,
+) -> Uuid {
+    let id = node.id().0.unwrap_or_else(|| stable_id(&key));
+    let mut full_title = parent
+        .map(|parent| parent.title.clone())
+        .unwrap_or_default();
+    full_title.push(title);
+    let tags = node.tags().iter().cloned().collect::<HashSet<_>>();
+    let parent_tags = parent.map(|parent| parent.tags.clone()).unwrap_or_default();
+
+    // Starling gives bodies without the blank lines at their ends
+    let mut body = node.body().unwrap_or_default().lines().collect::<Vec<_>>();
+    while body.last().is_some_and(|line| line.trim().is_empty()) {
+        body.pop();
+    }
+    let body = (!body.is_empty()).then(|| body.join("\n") + "\n");
+    let planning = node.planning();
+    let metadata = NodeMetadata {
+        level: node.level(),
+        priority: node.priority().0.clone(),
+        deadline: planning.deadline.clone(),
+        scheduled: planning.scheduled.clone(),
+        closed: planning.closed.clone(),
+        properties: node
+            .properties()
+            .iter()
+            .filter(|(key, _)| !key.eq_ignore_ascii_case("ID"))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        keyword: node.keyword().map(|keyword| keyword.0.clone()),
+        timestamps: node.timestamps().to_vec(),
+    };
+
+    let idx = nodes.len();
+    nodes.push(Node {
+        id,
+        title: full_title.clone(),
+        path: rel_path.to_path_buf(),
+        tags: tags.clone(),
+        parent_tags: parent_tags.clone(),
+        parent_id: parent.map(|parent| parent.id),
+        metadata: opts.metadata.then_some(metadata),
+        body: body.filter(|_| opts.body),
+        children: Vec::new(),
+        connections: HashMap::new(),
+        child_connections: HashMap::new(),
+        backlinks: HashMap::new(),
+        child_backlinks: HashMap::new(),
+    });
+
+    let this = Parent {
+        id,
+        key,
+        title: full_title,
+        tags: parent_tags.into_iter().chain(tags).collect(),
+    };
+    // How many of this node's children have had each title so far
+    let mut sibling_titles: HashMap<&str, usize> = HashMap::new();
+    for child in node.children() {
+        // Only later duplicates get an ordinal, so a title's first node keeps the ID it had before
+        // any duplicates were added
+        let ordinal = sibling_titles.entry(child.title()).or_default();
+        let mut child_key = format!("{}\0{}", this.key, child.title());
+        if *ordinal > 0 {
+            child_key.push_str(&format!("\0{ordinal}"));
+        }
+        *ordinal += 1;
+
+        let child_title = child.title().to_string();
+        let child_id = build_nodes(
+            child,
+            child_title.clone(),
+            child_key,
+            Some(&this),
+            rel_path,
+            opts,
+            nodes,
+        );
+        if opts.children {
+            nodes[idx].children.push((child_id, child_title));
+        }
+    }
+
+    id
+}
+
+/// Derives a stable ID for a node without one from the given key (which should say where it came
+/// from), with FNV-1a (see [`crate::state::content_hash`] for why).
+pub(super) fn stable_id(key: &str) -> Uuid {
+    let hash = |offset: u64| {
+        key.bytes().fold(offset, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    };
+    Uuid::from_u64_pair(hash(0xcbf29ce484222325), hash(0x84222325cbf29ce4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str, format: Format, path: &str) -> Vec<Node> {
+        KEYWORDS.with(|kws| {
+            *kws.borrow_mut() = ACTION_KEYWORDS.iter().map(|kw| kw.to_string()).collect()
+        });
+        let opts = NodeOptions {
+            metadata: true,
+            body: true,
+            children: true,
+            ..Default::default()
+        };
+        parse_nodes(contents, format, Path::new(path), opts).unwrap()
+    }
+
+    #[test]
+    fn org_files_are_parsed() {
+        let contents = "\
+#+title: Projects
+#+filetags: :work:
+
+* TODO [#2] Write report :urgent:
+SCHEDULED: <2025-01-01 Wed>
+:PROPERTIES:
+:ID: 3f2c9d1e-7a4b-4c8e-9f10-2b6d5e8a1c02
+:EFFORT: 1h
+:END:
+Some body text.
+#+begin_src
+* not a heading
+#+end_src
+** WRITING Subheading
+";
+        let nodes = parse(contents, Format::Org, "projects.org");
+        assert_eq!(nodes.len(), 3);
+
+        assert_eq!(nodes[0].title, ["Projects"]);
+        assert_eq!(nodes[0].tags, HashSet::from(["work".to_string()]));
+
+        let task = &nodes[1];
+        let metadata = task.metadata.as_ref().unwrap();
+        assert_eq!(task.title, ["Projects", "Write report"]);
+        assert_eq!(task.tags, HashSet::from(["urgent".to_string()]));
+        assert_eq!(task.parent_tags, HashSet::from(["work".to_string()]));
+        assert_eq!(metadata.level, 1);
+        assert_eq!(metadata.keyword.as_deref(), Some("TODO"));
+        assert_eq!(metadata.priority.as_deref(), Some("2"));
+        assert!(metadata.scheduled.is_some());
+        assert_eq!(
+            task.id,
+            Uuid::parse_str("3f2c9d1e-7a4b-4c8e-9f10-2b6d5e8a1c02").unwrap()
+        );
+        assert_eq!(
+            metadata.properties.get("EFFORT").map(String::as_str),
+            Some("1h")
+        );
+        assert_eq!(task.parent_id, Some(nodes[0].id));
+
+        // Unknown keywords are just part of the title
+        let sub = &nodes[2];
+        assert_eq!(
+            sub.title,
+            ["Projects", "Write report", "WRITING Subheading"]
+        );
+        assert_eq!(sub.metadata.as_ref().unwrap().keyword, None);
+        assert_eq!(task.children, [(sub.id, "WRITING Subheading".to_string())]);
+    }
+
+    #[test]
+    fn untitled_files_are_named_after_their_path() {
+        let nodes = parse(
+            "# NEXT Call Sam <2025-01-02 Thu>\n",
+            Format::Markdown,
+            "calls.md",
+        );
+        assert_eq!(nodes.len(), 2);
+
+        assert_eq!(nodes[0].title, ["calls"]);
+        let call = &nodes[1];
+        let metadata = call.metadata.as_ref().unwrap();
+        assert_eq!(call.title, ["calls", "Call Sam"]);
+        assert_eq!(metadata.keyword.as_deref(), Some("NEXT"));
+        assert_eq!(metadata.timestamps.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_sibling_titles_get_different_ids() {
+        let contents = "\
+* TODO Review
+** Notes
+* TODO Review
+** Notes
+";
+        let nodes = parse(contents, Format::Org, "reviews.org");
+        assert_eq!(nodes.len(), 5);
+
+        let ids = nodes.iter().map(|node| node.id).collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 5);
+        // Each review keeps its own notes
+        assert_eq!(nodes[1].children, [(nodes[2].id, "Notes".to_string())]);
+        assert_eq!(nodes[3].children, [(nodes[4].id, "Notes".to_string())]);
+        assert_eq!(nodes[4].parent_id, Some(nodes[3].id));
+    }
+
+    #[test]
+    fn stable_ids_stay_the_same() {
+        let contents = "* TODO Task\n";
+        let first = parse(contents, Format::Org, "tasks.org");
+        let second = parse(contents, Format::Org, "tasks.org");
+        assert_eq!(first[1].id, second[1].id);
+        assert_eq!(first[1].id, stable_id("tasks.org\0tasks\0Task"));
+    }
+}
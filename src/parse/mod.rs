@@ -1,41 +1,201 @@
 mod action_item;
+#[cfg(feature = "ical")]
+mod calendar;
+mod clock;
 mod fetch;
+#[cfg(test)]
+mod fixture;
 #[cfg(feature = "goals")]
 mod goals;
+mod ids;
+mod item_error;
+mod local;
 mod node;
 mod repeat;
+mod response_cache;
+mod source;
+mod starling;
+mod write;
 
-use anyhow::Result;
-use chrono::NaiveDate;
+use chrono::{Duration, NaiveDate, NaiveTime};
 use fetch::{prune_inactive_ts, skip_complete};
 use node::Node;
 use repeat::expand_timestamps;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 pub use action_item::*;
-pub use fetch::get_raw_action_items;
+#[cfg(feature = "ical")]
+pub use calendar::{parse_ics_date, read_calendar_nodes, unescape_ics, unfold_ics};
+pub use clock::{clock_entries, ClockEntry};
+pub use fetch::{get_raw_action_items, is_done_keyword, NodeStream};
 #[cfg(feature = "goals")]
-pub use goals::Goals;
+pub use goals::{Alignment, GoalType, Goals, PathTemplate};
+pub use ids::{resolve_id, short_ids};
+pub use item_error::{ItemError, ItemErrorKind};
 pub use node::*;
+pub use source::{LocalDir, NodeSource};
+pub use starling::{Starling, MAX_RETRIES};
+pub use write::{capture, clock_in, clock_out, mark_done, reschedule, snooze, Completion};
 
-/// Normalises the given raw nodes to a list of parsed action items, repeated until the given date.
+/// Normalises the given raw nodes to a list of parsed action items, with each one repeated until
+/// the date the given function returns for its node. Deadlines without times will be given the
+/// time `deadline_time` returns for their date. Items will be put under the first of the given
+/// area tags they're under, if any. Person dates without an `ADVANCE` property will be given the
+/// default one, if there is one.
+///
+/// Nodes which can't be parsed will be recorded in `errors` and skipped, and it's up to the caller
+/// whether or not to fail because of them. The nodes are processed one at a time, so they can be
+/// streamed in as they're fetched.
 pub fn normalize_action_items(
-    nodes: Vec<Node>,
+    nodes: impl IntoIterator<Item = Node>,
     done_keywords: &[String],
-    until: NaiveDate,
-) -> Result<HashMap<Uuid, ActionItem>> {
-    let mut map = nodes
-        .into_iter()
-        .filter(|n| skip_complete(n, done_keywords))
-        .map(prune_inactive_ts)
-        .map(|n| (n.id, expand_timestamps(&n, until).collect::<Vec<_>>(), n))
-        .map(|(id, repeats, node)| node_to_action_item(node, repeats).map(|item| (id, item)))
-        .collect::<Result<HashMap<Uuid, ActionItem>>>()?;
+    areas: &[String],
+    until: impl Fn(&Node) -> NaiveDate,
+    deadline_time: impl Fn(NaiveDate) -> NaiveTime,
+    default_advance: Option<Duration>,
+    errors: &mut Vec<ItemError>,
+) -> HashMap<Uuid, ActionItem> {
+    // Completed tasks, with their parents, and everything which has action items under it (so
+    // we can tell completed stacks apart from completed tasks)
+    let mut completed = Vec::new();
+    let mut parent_ids = HashSet::new();
+    let mut map = HashMap::new();
+    for node in nodes {
+        parent_ids.extend(node.parent_id);
+        // Completed items are dropped, but they're counted under their parents first (cancelled
+        // ones aren't progress, so they're left out)
+        if !skip_complete(&node, done_keywords) {
+            let keyword = node.metadata.as_ref().unwrap().keyword.as_deref();
+            if let (Some(parent_id), false) = (node.parent_id, keyword == Some("CANCELLED")) {
+                completed.push((node.id, parent_id));
+            }
+            continue;
+        }
+        // Tasks without a `CREATED` property were created on their earliest inactive timestamp,
+        // if they have one, so hold onto that before those are pruned
+        let earliest_inactive = node
+            .metadata
+            .as_ref()
+            .unwrap()
+            .timestamps
+            .iter()
+            .filter(|ts| !ts.active)
+            .map(|ts| ts.start.date)
+            .min();
+        let mut node = prune_inactive_ts(node);
+        if let Some(date) = earliest_inactive {
+            node.metadata
+                .as_mut()
+                .unwrap()
+                .properties
+                .entry("CREATED".to_string())
+                .or_insert_with(|| date.to_string());
+        }
+        if let Some(advance) = default_advance {
+            if node.parent_tags.contains("person_dates") {
+                node.metadata
+                    .as_mut()
+                    .unwrap()
+                    .properties
+                    .entry("ADVANCE".to_string())
+                    .or_insert_with(|| format!("{}d", advance.num_days()));
+            }
+        }
+        let id = node.id;
+        let title = node.title.last().cloned().unwrap_or_default();
+        let path = node.path.clone();
+        let repeats = expand_timestamps(&node, until(&node), &deadline_time).collect::<Vec<_>>();
+        match node_to_action_item(node, repeats, areas) {
+            Ok(item) => {
+                map.insert(id, item);
+            }
+            Err(err) => errors.push(ItemError {
+                id,
+                title,
+                path,
+                kind: ItemErrorKind::Parse,
+                message: format!("{err:#}"),
+            }),
+        }
+    }
     let ids = map.keys().copied().collect::<Vec<_>>();
     for id in ids {
         fill_action_item(id, &mut map);
     }
+    for (id, parent_id) in completed {
+        if parent_ids.contains(&id) {
+            continue;
+        }
+        if let Some(ActionItem::Stack {
+            completed_tasks, ..
+        }) = map.get_mut(&parent_id)
+        {
+            *completed_tasks += 1;
+        }
+    }
+    resolve_dependencies(&mut map);
+    disambiguate_people(&mut map);
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(map)
+    /// Creates a node with the given keyword under the given parent.
+    fn node(id: u128, keyword: &str, parent_id: Option<u128>) -> Node {
+        Node {
+            id: Uuid::from_u128(id),
+            title: vec![format!("{keyword} {id}")],
+            path: "stacks.org".into(),
+            tags: HashSet::new(),
+            parent_tags: HashSet::new(),
+            parent_id: parent_id.map(Uuid::from_u128),
+            metadata: Some(NodeMetadata {
+                level: 1,
+                keyword: Some(keyword.to_string()),
+                ..Default::default()
+            }),
+            body: None,
+            children: Vec::new(),
+            connections: HashMap::new(),
+            child_connections: HashMap::new(),
+            backlinks: HashMap::new(),
+            child_backlinks: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn only_completed_tasks_count_towards_stacks() {
+        let nodes = vec![
+            node(1, "STACK", None),
+            node(2, "TODO", Some(1)),
+            node(3, "DONE", Some(1)),
+            node(4, "CANCELLED", Some(1)),
+            // A completed stack, with its own completed task
+            node(5, "DONE", Some(1)),
+            node(6, "DONE", Some(5)),
+        ];
+        let mut errors = Vec::new();
+        let items = normalize_action_items(
+            nodes,
+            &["DONE".to_string()],
+            &[],
+            |_| NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            |_| NaiveTime::MIN,
+            None,
+            &mut errors,
+        );
+
+        assert!(errors.is_empty());
+        let Some(ActionItem::Stack {
+            completed_tasks, ..
+        }) = items.get(&Uuid::from_u128(1))
+        else {
+            panic!("stack wasn't normalised");
+        };
+        assert_eq!(*completed_tasks, 1);
+    }
 }
@@ -15,6 +15,8 @@ use uuid::Uuid;
 ///
 /// The information returned can be regulated with [`NodeOptions`].
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+// Tests play Starling's part too, which means encoding nodes
+#[cfg_attr(test, derive(Serialize))]
 pub struct Node {
     // --- Basics ---
     /// The node's unique identifier.
@@ -82,7 +84,8 @@ pub struct Node {
 
 /// Metadata about a node. This is a simplification of the representation in a [`StarlingNode`] for
 /// transmission.
-#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(test, derive(Serialize))]
 pub struct NodeMetadata {
     /// The level of this node (0 for a root node) in the hierarchhy of the document it came from.
     /// This is essentially the number of `#`s at the start of the node in Markdown (or `*`s in
@@ -112,6 +115,7 @@ pub struct NodeMetadata {
 /// doesn't include the ID of the other node, just because it's used in maps where that information
 /// is known from the key.
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(test, derive(Serialize))]
 pub struct NodeConnection {
     /// The other node's raw title.
     pub title: Vec<String>,
@@ -1,4 +1,5 @@
 use super::{node::Node, ActionItemRepeat, SimpleTimestamp};
+use crate::warnings::warn_once;
 use chrono::{Datelike, NaiveDate, NaiveTime, Utc};
 use orgish::Timestamp;
 
@@ -9,10 +10,13 @@ use orgish::Timestamp;
 /// This will treat each "primary" timestamp (i.e. in the heading) as the guide timestamp, which
 /// will control the repeating cadence. If there are multiple such timestamps, they will each be
 /// handled separately. Regardless, this will return a vector of all the nodes produced.
-pub fn expand_timestamps(
-    node: &Node,
+///
+/// Deadlines without times will be given the time `deadline_time` returns for their date.
+pub fn expand_timestamps<'a>(
+    node: &'a Node,
     until: NaiveDate,
-) -> impl Iterator<Item = ActionItemRepeat> + '_ {
+    deadline_time: &'a dyn Fn(NaiveDate) -> NaiveTime,
+) -> impl Iterator<Item = ActionItemRepeat> + 'a {
     // If we handle the two cases of having primary timestamps and not having primary timestamps
     // separately, then we get two different iterators whose types don't match. To avoid that, we
     // instead extract the timestamps and convert them all to `Some(..)`. Then, if there are none,
@@ -34,10 +38,10 @@ pub fn expand_timestamps(
             .as_ref()
             .is_some_and(|ts| ts.start.date.year() > cutoff_year)
         {
-            eprintln!(
+            warn_once(&format!(
                 "node {} has a timestamp more than two years in the future",
                 node.id
-            );
+            ));
         }
 
         RepeatData {
@@ -45,7 +49,7 @@ pub fn expand_timestamps(
             scheduled: node.metadata.as_ref().unwrap().scheduled.clone(),
             deadline: node.metadata.as_ref().unwrap().deadline.clone(),
         }
-        .repeat_until(until)
+        .repeat_until(until, deadline_time)
     })
 }
 
@@ -155,8 +159,12 @@ impl RepeatData {
     }
 
     /// Produces an iterator of individual repeat information packets until the given date, for
-    /// this repeat data.
-    fn repeat_until(self, until: NaiveDate) -> impl Iterator<Item = ActionItemRepeat> {
+    /// this repeat data. Untimed deadlines will be given the time `deadline_time` returns.
+    fn repeat_until(
+        self,
+        until: NaiveDate,
+        deadline_time: &dyn Fn(NaiveDate) -> NaiveTime,
+    ) -> impl Iterator<Item = ActionItemRepeat> + '_ {
         let mut last_repeat_opt = Some(self);
         std::iter::from_fn(move || {
             if let Some(last_repeat) = last_repeat_opt.take() {
@@ -192,7 +200,7 @@ impl RepeatData {
                             ts.start.date.and_time(
                                 ts.start
                                     .time
-                                    .unwrap_or(NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
+                                    .unwrap_or_else(|| deadline_time(ts.start.date)),
                             )
                         }),
                     })
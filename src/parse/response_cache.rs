@@ -0,0 +1,89 @@
+use super::node::NodeOptions;
+use super::starling::Starling;
+use crate::config::cache_dir;
+use crate::state::content_hash;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How many caches this process has set up, which keeps their temporary files apart.
+static NEXT_CACHE: AtomicUsize = AtomicUsize::new(0);
+
+/// Where the last response Starling gave for a particular request for nodes is cached on disk,
+/// along with the entity tag it came with, so the request can be made conditional next time. If
+/// nothing's changed, Starling can then skip sending the nodes again.
+#[derive(Debug)]
+pub struct ResponseCache {
+    /// The file the body of the response is cached in.
+    data: PathBuf,
+    /// The file the entity tag of the response is cached in.
+    etag: PathBuf,
+    /// The file a new response is written into before it replaces the cached one. This is unique
+    /// to this process and cache, since other runs (e.g. a status bar polling in the background)
+    /// can be fetching the same nodes at the same time.
+    tmp: PathBuf,
+}
+impl ResponseCache {
+    /// Gets where the response to the given request to the given Starling instance would be
+    /// cached, or `None` if there's nowhere to cache it.
+    pub fn new(starling: &Starling, opts: &NodeOptions) -> Option<Self> {
+        // Different instances, users, and options give different responses
+        let key = content_hash(&(&starling.address, &starling.token, opts)).ok()?;
+        let dir = cache_dir()?.join("nodes");
+        let n = NEXT_CACHE.fetch_add(1, Ordering::Relaxed);
+        Some(Self {
+            data: dir.join(format!("{key}.bin")),
+            etag: dir.join(format!("{key}.etag")),
+            tmp: dir.join(format!("{key}.{}-{n}.tmp", std::process::id())),
+        })
+    }
+
+    /// Gets the entity tag of the cached response, if there is one.
+    pub fn etag(&self) -> Option<String> {
+        if !self.data.exists() {
+            return None;
+        }
+        std::fs::read_to_string(&self.etag).ok()
+    }
+
+    /// Opens the cached response for reading.
+    pub fn open(&self) -> Result<BufReader<File>> {
+        let file = File::open(&self.data)
+            .with_context(|| format!("failed to open cached nodes at {}", self.data.display()))?;
+        Ok(BufReader::new(file))
+    }
+
+    /// Creates the file a new response will be written into as it's read. This won't replace the
+    /// cached response until it's committed, so a partly-read response is never used.
+    pub fn create(&self) -> Result<BufWriter<File>> {
+        let dir = self.data.parent().unwrap();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create cache directory {}", dir.display()))?;
+        let file = File::create(&self.tmp)
+            .with_context(|| format!("failed to create cached nodes at {}", self.data.display()))?;
+        Ok(BufWriter::new(file))
+    }
+
+    /// Replaces the cached response with the one that's been written to the given file (from
+    /// [`Self::create`]), recording the given entity tag for it. Both are renamed into place, so
+    /// other runs never see either of them half-written.
+    pub fn commit(&self, file: BufWriter<File>, etag: &str) -> Result<()> {
+        file.into_inner().map_err(|err| err.into_error())?.flush()?;
+        // The old tag can't be left in place in case we fail partway through
+        let _ = std::fs::remove_file(&self.etag);
+        std::fs::rename(&self.tmp, &self.data)?;
+        let etag_tmp = self.tmp.with_extension("etag.tmp");
+        std::fs::write(&etag_tmp, etag)?;
+        std::fs::rename(etag_tmp, &self.etag)?;
+        Ok(())
+    }
+}
+impl Drop for ResponseCache {
+    fn drop(&mut self) {
+        // Responses that were never committed (e.g. because reading them failed) are left behind
+        // under a name no one else will use, so they have to be cleaned up
+        let _ = std::fs::remove_file(&self.tmp);
+    }
+}
@@ -0,0 +1,306 @@
+use super::fetch::{fetch_nodes, NodeStream};
+use super::local::read_dir_nodes;
+use super::node::{Format, Node, NodeOptions};
+use super::starling::Starling;
+use crate::exit::Failure;
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Somewhere nodes can be read from, like a Starling instance or a directory of files. Everything
+/// that reads nodes goes through this, so other sources (e.g. snapshots, or fixtures for testing)
+/// can be plugged in without touching the rest of Polaris.
+pub trait NodeSource: fmt::Display + fmt::Debug + Send + Sync {
+    /// Starts reading the raw action item nodes, so they can be processed one at a time. Metadata
+    /// and children are always included, whatever the given options say.
+    fn action_items(&self, opts: NodeOptions) -> Result<NodeStream>;
+
+    /// Gets the node with the given ID, with the details the given options ask for.
+    fn node(&self, id: Uuid, opts: NodeOptions) -> Result<Node>;
+
+    /// Gets the nodes with the given IDs, in the same order, with the details the given options ask
+    /// for. By default, this gets each one with [`Self::node`], so sources which can read several
+    /// nodes at once should override it.
+    fn nodes(&self, ids: &[Uuid], opts: NodeOptions) -> Result<Vec<Node>> {
+        ids.iter().map(|id| self.node(*id, opts)).collect()
+    }
+
+    /// Gets the ID of the root node of the file with the given path, relative to the root of this
+    /// source.
+    fn root_id(&self, path: &str) -> Result<Uuid>;
+
+    /// Gets every node with the given tag, with the details the given options ask for. By
+    /// default, this looks through the action items, so from Starling, tagged nodes will only be
+    /// found if they're in its action items index (e.g. because they have a keyword).
+    fn tagged(&self, tag: &str, opts: NodeOptions) -> Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        for node in self.action_items(opts)? {
+            let node = node?;
+            if node.tags.contains(tag) {
+                nodes.push(node);
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Checks that this source is up and serving action items, failing if it takes longer than the
+    /// given timeout (where that means anything). This returns the number of action items there
+    /// are.
+    fn check(&self, _timeout: Duration) -> Result<usize> {
+        self.action_items(check_opts())?
+            .try_fold(0, |count, node| node.map(|_| count + 1))
+    }
+
+    /// Gets the Starling instance behind this source, for the things only Starling can do (like
+    /// changing items), failing if there isn't one.
+    fn starling(&self) -> Result<&Starling> {
+        bail!("this needs starling, but action items are being read from {self}")
+    }
+}
+
+impl NodeSource for Starling {
+    fn action_items(&self, opts: NodeOptions) -> Result<NodeStream> {
+        fetch_nodes(opts, self, None)
+    }
+
+    fn node(&self, id: Uuid, opts: NodeOptions) -> Result<Node> {
+        let mut res = self
+            .retry(|| {
+                self.get(&format!("node/{id}"))
+                    .config()
+                    .http_status_as_error(false)
+                    .build()
+                    .query("use_bincode", "false")
+                    .force_send_body()
+                    .send_json(&opts)
+            })
+            .with_context(|| format!("failed to connect to {self}"))
+            .context(Failure::Starling)?;
+        if res.status() != 200 {
+            return Err(anyhow!(
+                "failed to get node {id} from {self}, received status {}",
+                res.status()
+            )
+            .context(Failure::Starling));
+        }
+
+        serde_json::from_reader(res.body_mut().as_reader())
+            .with_context(|| format!("failed to deserialize node {id} from starling"))
+            .context(Failure::Starling)
+    }
+
+    fn root_id(&self, path: &str) -> Result<Uuid> {
+        // No `bincode` support on this endpoint
+        let path_url = urlencoding::encode(path);
+        let mut res = self
+            .retry(|| {
+                self.get(&format!("root-id/{path_url}"))
+                    .config()
+                    .http_status_as_error(false)
+                    .build()
+                    .call()
+            })
+            .with_context(|| format!("failed to connect to {self}"))
+            .context(Failure::Starling)?;
+        if res.status() != 200 {
+            return Err(anyhow!(
+                "failed to get root id for file {path} from {self}, received status {}",
+                res.status()
+            )
+            .context(Failure::Starling));
+        }
+        let root_id: String = serde_json::from_reader(res.body_mut().as_reader())
+            .with_context(|| format!("failed to deserialize root id from starling for file {path}"))
+            .context(Failure::Starling)?;
+        Uuid::parse_str(&root_id)
+            .with_context(|| format!("failed to parse root id {root_id} for file {path}"))
+    }
+
+    fn check(&self, timeout: Duration) -> Result<usize> {
+        fetch_nodes(check_opts(), self, Some(timeout))?
+            .try_fold(0, |count, node| node.map(|_| count + 1))
+    }
+
+    fn starling(&self) -> Result<&Starling> {
+        Ok(self)
+    }
+}
+
+/// A directory of Markdown and Org files, which are parsed directly (see [`read_dir_nodes`] for
+/// what that can't do). Every read parses the whole directory again.
+#[derive(Clone, Debug)]
+pub struct LocalDir {
+    /// The directory the files are in.
+    pub path: PathBuf,
+    /// Completion keywords to recognise (Starling would know these from its own configuration).
+    pub done_keywords: Vec<String>,
+}
+impl LocalDir {
+    /// Reads every node in the directory, with the given options.
+    fn read(&self, opts: NodeOptions) -> Result<Vec<Node>> {
+        read_dir_nodes(&self.path, opts, &self.done_keywords)
+    }
+}
+impl NodeSource for LocalDir {
+    // Like Starling's index, only nodes with a keyword or a timestamp are action items
+    fn action_items(&self, mut opts: NodeOptions) -> Result<NodeStream> {
+        opts.children = true;
+        opts.metadata = true;
+        let nodes = self
+            .read(opts)?
+            .into_iter()
+            .filter(|node| {
+                node.metadata.as_ref().is_some_and(|metadata| {
+                    metadata.keyword.is_some()
+                        || !metadata.timestamps.is_empty()
+                        || metadata.scheduled.is_some()
+                        || metadata.deadline.is_some()
+                })
+            })
+            .collect();
+        Ok(NodeStream::from_nodes(nodes))
+    }
+
+    fn node(&self, id: Uuid, opts: NodeOptions) -> Result<Node> {
+        self.read(opts)?
+            .into_iter()
+            .find(|node| node.id == id)
+            .ok_or_else(|| anyhow!("no node {id} in {self}"))
+    }
+
+    // Every read parses the whole directory, so this only reads it once
+    fn nodes(&self, ids: &[Uuid], opts: NodeOptions) -> Result<Vec<Node>> {
+        let mut nodes = self
+            .read(opts)?
+            .into_iter()
+            .map(|node| (node.id, node))
+            .collect::<HashMap<_, _>>();
+        ids.iter()
+            .map(|id| {
+                nodes
+                    .remove(id)
+                    .ok_or_else(|| anyhow!("no node {id} in {self}"))
+            })
+            .collect()
+    }
+
+    fn root_id(&self, path: &str) -> Result<Uuid> {
+        self.read(NodeOptions::default())?
+            .into_iter()
+            .find(|node| node.parent_id.is_none() && node.path == std::path::Path::new(path))
+            .map(|node| node.id)
+            .ok_or_else(|| anyhow!("no file {path} in {self}"))
+    }
+
+    // Every node is here, not just the action items
+    fn tagged(&self, tag: &str, opts: NodeOptions) -> Result<Vec<Node>> {
+        Ok(self
+            .read(opts)?
+            .into_iter()
+            .filter(|node| node.tags.contains(tag))
+            .collect())
+    }
+}
+impl fmt::Display for LocalDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dir:{}", self.path.display())
+    }
+}
+
+/// The options healthchecks fetch action items with, which leave out the bodies to be quick.
+fn check_opts() -> NodeOptions {
+    NodeOptions {
+        body: false,
+        metadata: true,
+        children: true,
+        connections: false,
+        child_connections: false,
+        conn_format: Format::Markdown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::fetch::get_raw_action_items;
+    use crate::parse::fixture::{recorded_nodes, FixtureServer};
+
+    /// Gets the details of the given fixture server as a Starling instance, without any retries
+    /// so failures show up straight away.
+    fn starling(server: &FixtureServer) -> Starling {
+        Starling::new(server.address.clone(), None, Duration::from_secs(5), 0).unwrap()
+    }
+
+    /// Gets the recorded nodes which are in Starling's action items index.
+    fn recorded_action_items() -> Vec<Node> {
+        recorded_nodes()
+            .into_iter()
+            .filter(|node| node.metadata.as_ref().unwrap().keyword.is_some())
+            .collect()
+    }
+
+    #[test]
+    fn action_items_are_streamed_from_bincode() {
+        let server = FixtureServer::start();
+        let nodes = get_raw_action_items(NodeOptions::default(), &starling(&server)).unwrap();
+
+        assert_eq!(nodes, recorded_action_items());
+        assert!(server.requests()[0].starts_with("GET /index/action_items/nodes?use_bincode=true "));
+    }
+
+    #[test]
+    fn healthchecks_count_action_items() {
+        let server = FixtureServer::start();
+        let count = starling(&server).check(Duration::from_secs(5)).unwrap();
+
+        assert_eq!(count, recorded_action_items().len());
+    }
+
+    #[test]
+    fn nodes_are_fetched_from_json() {
+        let server = FixtureServer::start();
+        let expected = recorded_nodes().remove(1);
+        let node = starling(&server)
+            .node(expected.id, NodeOptions::default())
+            .unwrap();
+
+        assert_eq!(node, expected);
+        assert!(server.requests()[0]
+            .starts_with(&format!("GET /node/{}?use_bincode=false ", expected.id)));
+    }
+
+    #[test]
+    fn root_ids_are_fetched_for_encoded_paths() {
+        let server = FixtureServer::start();
+        let root = recorded_nodes().remove(0);
+        let root_id = starling(&server).root_id("areas/home life.md").unwrap();
+
+        assert_eq!(root_id, root.id);
+        assert_eq!(
+            server.requests()[0],
+            "GET /root-id/areas%2Fhome%20life.md HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn error_statuses_are_starling_failures() {
+        let server = FixtureServer::start();
+        let starling = starling(&server);
+
+        let err = starling
+            .node(Uuid::nil(), NodeOptions::default())
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Failure>(),
+            Some(Failure::Starling)
+        ));
+        let err = starling.root_id("missing.md").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Failure>(),
+            Some(Failure::Starling)
+        ));
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(not(feature = "tls"))]
+use anyhow::bail;
+use anyhow::Result;
+use std::fmt;
+use std::time::Duration;
+use ureq::http::Response;
+use ureq::typestate::{WithBody, WithoutBody};
+use ureq::{Agent, Body, RequestBuilder};
+#[cfg(feature = "tls")]
+use {
+    anyhow::Context,
+    std::path::Path,
+    ureq::tls::{Certificate, RootCerts, TlsConfig},
+};
+
+/// How long to wait before the first retry of a request, which doubles for each retry after that.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// The longest to wait before any one retry, however many there have been.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// The most times a request can be retried, so a Starling instance that's down for good can't
+/// hold things up forever.
+pub const MAX_RETRIES: u32 = 10;
+
+/// The details needed to make requests to a Starling instance.
+#[derive(Clone, Debug)]
+pub struct Starling {
+    /// The address of the instance, as it was given (e.g. `localhost:3000` or
+    /// `https://starling.example.com`).
+    pub address: String,
+    /// The URL requests are made relative to, without a trailing slash.
+    base_url: String,
+    /// A token to send as a bearer token with every request, for instances behind an
+    /// authenticating proxy.
+    pub(super) token: Option<String>,
+    /// How long each request can take in total before it fails.
+    timeout: Duration,
+    /// How many times requests which fail in a way that might be temporary are retried.
+    retries: u32,
+    /// The agent requests are made through, which holds any TLS settings.
+    agent: Agent,
+}
+impl Starling {
+    /// Creates the details for the Starling instance at the given address, which can be a full URL
+    /// or just a host and port (in which case plain HTTP is used). Requests will time out after
+    /// the given duration, and be retried up to the given number of times if that might help.
+    pub fn new(
+        address: String,
+        token: Option<String>,
+        timeout: Duration,
+        retries: u32,
+    ) -> Result<Self> {
+        let base_url = if address.contains("://") {
+            address.trim_end_matches('/').to_string()
+        } else {
+            format!("http://{address}")
+        };
+        #[cfg(not(feature = "tls"))]
+        if base_url.starts_with("https://") {
+            bail!("connecting to starling over https requires the `tls` feature");
+        }
+
+        Ok(Self {
+            address,
+            base_url,
+            token,
+            timeout,
+            retries,
+            agent: Agent::new_with_defaults(),
+        })
+    }
+
+    /// Sets how the certificate of an instance connected to over HTTPS is checked: against the
+    /// PEM-encoded CA certificate in the given file rather than the usual roots, or not at all if
+    /// `insecure` is set (which should only ever be used for testing).
+    #[cfg(feature = "tls")]
+    pub fn with_tls(mut self, ca_path: Option<&Path>, insecure: bool) -> Result<Self> {
+        let mut tls = TlsConfig::builder().disable_verification(insecure);
+        if let Some(ca_path) = ca_path {
+            let pem = std::fs::read(ca_path).with_context(|| {
+                format!(
+                    "failed to read starling ca certificate {}",
+                    ca_path.display()
+                )
+            })?;
+            let cert = Certificate::from_pem(&pem).with_context(|| {
+                format!(
+                    "failed to parse starling ca certificate {}",
+                    ca_path.display()
+                )
+            })?;
+            tls = tls.root_certs(RootCerts::new_with_certs(&[cert]));
+        }
+        self.agent =
+            Agent::new_with_config(Agent::config_builder().tls_config(tls.build()).build());
+
+        Ok(self)
+    }
+
+    /// Starts a `GET` request to the given path on this instance.
+    pub fn get(&self, path: &str) -> RequestBuilder<WithoutBody> {
+        self.prepare(self.agent.get(&self.url(path)))
+    }
+
+    /// Starts a `POST` request to the given path on this instance.
+    pub fn post(&self, path: &str) -> RequestBuilder<WithBody> {
+        self.prepare(self.agent.post(&self.url(path)))
+    }
+
+    /// Starts a `PATCH` request to the given path on this instance.
+    pub fn patch(&self, path: &str) -> RequestBuilder<WithBody> {
+        self.prepare(self.agent.patch(&self.url(path)))
+    }
+
+    /// Sends a request with the given function, retrying it with exponential backoff if it fails
+    /// in a way that might be temporary (e.g. while Starling restarts). Only requests which are
+    /// safe to repeat should be sent like this.
+    pub fn retry(
+        &self,
+        mut send: impl FnMut() -> Result<Response<Body>, ureq::Error>,
+    ) -> Result<Response<Body>, ureq::Error> {
+        let mut attempt = 0;
+        loop {
+            let res = send();
+            let transient = match &res {
+                // These are what a proxy in front of Starling gives while it's down
+                Ok(res) => matches!(res.status().as_u16(), 502..=504),
+                Err(err) => matches!(
+                    err,
+                    ureq::Error::Io(_) | ureq::Error::Timeout(_) | ureq::Error::ConnectionFailed
+                ),
+            };
+            if !transient || attempt >= self.retries {
+                return res;
+            }
+            let backoff = RETRY_DELAY.saturating_mul(2u32.saturating_pow(attempt));
+            std::thread::sleep(backoff.min(MAX_RETRY_DELAY));
+            attempt += 1;
+        }
+    }
+
+    /// Gets the full URL of the given path on this instance.
+    fn url(&self, path: &str) -> String {
+        format!("{}/{path}", self.base_url)
+    }
+
+    /// Sets the timeout on the given request, and adds the token to it if there is one.
+    fn prepare<B>(&self, req: RequestBuilder<B>) -> RequestBuilder<B> {
+        let req = req.config().timeout_global(Some(self.timeout)).build();
+        match &self.token {
+            Some(token) => req.header("Authorization", format!("Bearer {token}")),
+            None => req,
+        }
+    }
+}
+impl fmt::Display for Starling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.address)
+    }
+}
@@ -0,0 +1,407 @@
+use super::clock::{clock_in_body, clock_out_body};
+use super::node::{Format, Node, NodeOptions};
+use super::source::NodeSource;
+use super::starling::Starling;
+use super::{get_raw_action_items, is_done_keyword};
+use crate::exit::Failure;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use orgish::Timestamp;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+/// The changes to make to a node in Starling. Anything left as `None` will be left as it is.
+#[derive(Serialize, Debug)]
+struct NodePatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyword: Option<String>,
+    /// The new `CLOSED` timestamp, in Org format (e.g. `[2025-01-01 Wed 09:00]`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    closed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheduled: Option<PatchTimestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deadline: Option<PatchTimestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamps: Option<Vec<Timestamp>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    /// Properties to set on the node, which will be merged with the existing ones.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    properties: HashMap<String, String>,
+}
+
+/// A new node to add to the end of a file in Starling.
+#[derive(Serialize, Debug)]
+struct NewNode<'a> {
+    /// The path of the file to add the node to, relative to the Starling root.
+    path: &'a Path,
+    title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyword: Option<&'a str>,
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<&'a str>,
+    properties: HashMap<String, String>,
+}
+
+/// A planning timestamp to set on a node.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+enum PatchTimestamp {
+    /// An existing timestamp which has been changed, which keeps its time and repeater.
+    Existing(Timestamp),
+    /// A brand new timestamp, in Org format (e.g. `<2025-01-01 Wed>`).
+    New(String),
+}
+
+/// What happened to an item when it was marked as done.
+#[derive(Debug)]
+pub enum Completion {
+    /// The item has been given the completion keyword and a `CLOSED` timestamp.
+    Closed {
+        /// The title of the item.
+        title: String,
+    },
+    /// The item repeats, so its repeating timestamps have been moved on to their next repeat
+    /// instead, and it keeps its keyword.
+    Repeated {
+        /// The title of the item.
+        title: String,
+        /// The date of the earliest repeating timestamp after it was moved on.
+        next: NaiveDate,
+    },
+}
+
+/// Marks the node with the given ID in Starling as done at `now`, updating it in place. Normally,
+/// this will give it the given completion keyword and a `CLOSED` timestamp, but if it has any
+/// repeating timestamps, those will just be moved on to their next repeat (as Org mode does), and
+/// the time of completion recorded in a `LAST_REPEAT` property.
+pub fn mark_done(
+    starling: &Starling,
+    id: Uuid,
+    keyword: &str,
+    done_keywords: &[String],
+    now: NaiveDateTime,
+) -> Result<Completion> {
+    let node = get_node(starling, id)?;
+    let title = node.title.last().cloned().unwrap_or_default();
+    let metadata = node.metadata.as_ref().unwrap();
+    match &metadata.keyword {
+        None => bail!("node {id} (\"{title}\") has no keyword, so it can't be marked as done"),
+        Some(kw) if is_done_keyword(kw, done_keywords) => {
+            bail!("node {id} (\"{title}\") is already marked as {kw}")
+        }
+        Some(_) => {}
+    }
+
+    let closed = now.format("[%Y-%m-%d %a %H:%M]").to_string();
+    let scheduled = metadata
+        .scheduled
+        .as_ref()
+        .map(|ts| next_repeat(ts, id, &title))
+        .transpose()?
+        .flatten();
+    let deadline = metadata
+        .deadline
+        .as_ref()
+        .map(|ts| next_repeat(ts, id, &title))
+        .transpose()?
+        .flatten();
+    let timestamps = metadata
+        .timestamps
+        .iter()
+        .map(|ts| next_repeat(ts, id, &title))
+        .collect::<Result<Vec<_>>>()?;
+    let next = scheduled
+        .iter()
+        .chain(deadline.iter())
+        .chain(timestamps.iter().flatten())
+        .map(|ts| ts.start.date)
+        .min();
+    // Timestamps are replaced all together, so any that don't repeat have to be kept as they are
+    let timestamps = timestamps.iter().any(Option::is_some).then(|| {
+        timestamps
+            .into_iter()
+            .zip(&metadata.timestamps)
+            .map(|(next, ts)| next.unwrap_or_else(|| ts.clone()))
+            .collect()
+    });
+
+    let (patch, completion) = match next {
+        Some(next) => (
+            NodePatch {
+                keyword: None,
+                closed: None,
+                scheduled: scheduled.map(PatchTimestamp::Existing),
+                deadline: deadline.map(PatchTimestamp::Existing),
+                timestamps,
+                body: None,
+                properties: HashMap::from([("LAST_REPEAT".to_string(), closed)]),
+            },
+            Completion::Repeated { title, next },
+        ),
+        None => (
+            NodePatch {
+                keyword: Some(keyword.to_string()),
+                closed: Some(closed),
+                scheduled: None,
+                deadline: None,
+                timestamps: None,
+                body: None,
+                properties: HashMap::new(),
+            },
+            Completion::Closed { title },
+        ),
+    };
+    patch_node(starling, id, &patch)?;
+
+    Ok(completion)
+}
+
+/// Moves the scheduled and/or deadline dates of the node with the given ID in Starling to the
+/// given dates, returning its title. Existing timestamps will keep their times, repeaters, and
+/// lengths (if they're ranges), and any that don't exist yet will be added as all-day ones. Any
+/// date that isn't given keeps its current value, and the deadline can't end up before the
+/// scheduled date.
+pub fn reschedule(
+    starling: &Starling,
+    id: Uuid,
+    scheduled: Option<NaiveDate>,
+    deadline: Option<NaiveDate>,
+) -> Result<String> {
+    let node = get_node(starling, id)?;
+    let title = node.title.last().cloned().unwrap_or_default();
+    let metadata = node.metadata.as_ref().unwrap();
+    let new_scheduled = scheduled.or(metadata.scheduled.as_ref().map(|ts| ts.start.date));
+    let new_deadline = deadline.or(metadata.deadline.as_ref().map(|ts| ts.start.date));
+    if let (Some(s), Some(d)) = (new_scheduled, new_deadline) {
+        if d < s {
+            bail!("node {id} (\"{title}\") would be due ({d}) before it's scheduled ({s})");
+        }
+    }
+
+    let patch = NodePatch {
+        keyword: None,
+        closed: None,
+        scheduled: scheduled.map(|date| move_timestamp(metadata.scheduled.as_ref(), date)),
+        deadline: deadline.map(|date| move_timestamp(metadata.deadline.as_ref(), date)),
+        timestamps: None,
+        body: None,
+        properties: HashMap::new(),
+    };
+    patch_node(starling, id, &patch)?;
+
+    Ok(title)
+}
+
+/// Moves the given timestamp (if there is one) so it starts on the given date, or creates a new
+/// all-day timestamp on that date if there isn't one.
+fn move_timestamp(ts: Option<&Timestamp>, date: NaiveDate) -> PatchTimestamp {
+    match ts {
+        Some(ts) => PatchTimestamp::Existing(moved(ts, date)),
+        None => PatchTimestamp::New(date.format("<%Y-%m-%d %a>").to_string()),
+    }
+}
+
+/// Snoozes the node with the given ID in Starling until the given date by moving its primary
+/// timestamp (the first active one in its heading), returning its title. This is mainly for
+/// tickles and daily notes, and the timestamp will keep its time, repeater, and length.
+pub fn snooze(starling: &Starling, id: Uuid, date: NaiveDate) -> Result<String> {
+    let node = get_node(starling, id)?;
+    let title = node.title.last().cloned().unwrap_or_default();
+    let metadata = node.metadata.as_ref().unwrap();
+    let Some(idx) = metadata.timestamps.iter().position(|ts| ts.active) else {
+        bail!("node {id} (\"{title}\") has no active timestamp to snooze");
+    };
+
+    // Timestamps are replaced all together, so the others have to be kept as they are
+    let mut timestamps = metadata.timestamps.clone();
+    timestamps[idx] = moved(&timestamps[idx], date);
+    let patch = NodePatch {
+        keyword: None,
+        closed: None,
+        scheduled: None,
+        deadline: None,
+        timestamps: Some(timestamps),
+        body: None,
+        properties: HashMap::new(),
+    };
+    patch_node(starling, id, &patch)?;
+
+    Ok(title)
+}
+
+/// Moves the given timestamp so it starts on the given date, keeping its length if it's a range.
+fn moved(ts: &Timestamp, date: NaiveDate) -> Timestamp {
+    let mut ts = ts.clone();
+    let offset = date - ts.start.date;
+    ts.start.date = date;
+    if let Some(end) = &mut ts.end {
+        end.date += offset;
+    }
+    ts
+}
+
+/// Captures a new node with the given details at the end of the file at the given path (relative
+/// to the Starling root), marking it as created at `now`. This returns the ID of the new node.
+pub fn capture(
+    starling: &Starling,
+    path: &Path,
+    title: &str,
+    keyword: Option<&str>,
+    tags: &[String],
+    body: Option<&str>,
+    now: NaiveDateTime,
+) -> Result<Uuid> {
+    let node = NewNode {
+        path,
+        title,
+        keyword,
+        tags,
+        body,
+        properties: HashMap::from([(
+            "CREATED".to_string(),
+            now.format("[%Y-%m-%d %a %H:%M]").to_string(),
+        )]),
+    };
+    let mut res = starling
+        .post("nodes")
+        .config()
+        .http_status_as_error(false)
+        .build()
+        .send_json(&node)
+        .with_context(|| format!("failed to connect to {starling}"))
+        .context(Failure::Starling)?;
+    if res.status() != 200 {
+        return Err(anyhow!(
+            "failed to add node to {} in {starling}, received status {}",
+            path.display(),
+            res.status()
+        )
+        .context(Failure::Starling));
+    }
+
+    serde_json::from_reader(res.body_mut().as_reader())
+        .with_context(|| "failed to deserialize new node id from starling")
+        .context(Failure::Starling)
+}
+
+/// Gets the next repeat of the given timestamp on the node with the given ID and title, if it's
+/// active and has a repeater.
+fn next_repeat(ts: &Timestamp, id: Uuid, title: &str) -> Result<Option<Timestamp>> {
+    if !ts.active || ts.repeater.is_none() {
+        return Ok(None);
+    }
+    ts.clone()
+        .into_next_repeat()
+        .ok()
+        .map(Some)
+        .with_context(|| format!("failed to move node {id} (\"{title}\") on to its next repeat"))
+}
+
+/// Starts a clock on the node with the given ID in Starling at `now`, by adding a `CLOCK` entry to
+/// its logbook, returning its title. Any clock that's already running on another item will be
+/// stopped first (only one thing can be clocked at a time).
+///
+/// Only action items (nodes with a keyword) can be clocked into, because those are the only nodes
+/// [`clock_out`] looks at.
+pub fn clock_in(starling: &Starling, id: Uuid, now: NaiveDateTime) -> Result<String> {
+    let node = get_node(starling, id)?;
+    let title = node.title.last().cloned().unwrap_or_default();
+    if node.metadata.as_ref().unwrap().keyword.is_none() {
+        bail!("node {id} (\"{title}\") has no keyword, so it can't be clocked into");
+    }
+
+    // Stopping the running clock might have changed this node's body, so we get it again
+    clock_out(starling, now)?;
+    let node = get_node(starling, id)?;
+    let patch = NodePatch {
+        keyword: None,
+        closed: None,
+        scheduled: None,
+        deadline: None,
+        timestamps: None,
+        body: Some(clock_in_body(node.body.as_deref(), now)),
+        properties: HashMap::new(),
+    };
+    patch_node(starling, id, &patch)?;
+
+    Ok(title)
+}
+
+/// Stops any running clocks on action items in Starling at `now`, returning the titles of the
+/// items which were clocked.
+pub fn clock_out(starling: &Starling, now: NaiveDateTime) -> Result<Vec<String>> {
+    let nodes = get_raw_action_items(
+        NodeOptions {
+            body: true,
+            metadata: true,
+            children: true,
+            connections: false,
+            child_connections: false,
+            conn_format: Format::Markdown,
+        },
+        starling,
+    )?;
+
+    let mut titles = Vec::new();
+    for node in nodes {
+        let Some(body) = node
+            .body
+            .as_deref()
+            .and_then(|body| clock_out_body(body, now))
+        else {
+            continue;
+        };
+        let patch = NodePatch {
+            keyword: None,
+            closed: None,
+            scheduled: None,
+            deadline: None,
+            timestamps: None,
+            body: Some(body),
+            properties: HashMap::new(),
+        };
+        patch_node(starling, node.id, &patch)?;
+        titles.push(node.title.last().cloned().unwrap_or_default());
+    }
+
+    Ok(titles)
+}
+
+/// Gets the metadata and body of the node with the given ID from Starling.
+fn get_node(starling: &Starling, id: Uuid) -> Result<Node> {
+    let opts = NodeOptions {
+        body: true,
+        metadata: true,
+        children: false,
+        connections: false,
+        child_connections: false,
+        conn_format: Format::Markdown,
+    };
+    starling.node(id, opts)
+}
+
+/// Applies the given changes to the node with the given ID in Starling.
+fn patch_node(starling: &Starling, id: Uuid, patch: &NodePatch) -> Result<()> {
+    let res = starling
+        .patch(&format!("node/{id}"))
+        .config()
+        .http_status_as_error(false)
+        .build()
+        .send_json(patch)
+        .with_context(|| format!("failed to connect to {starling}"))
+        .context(Failure::Starling)?;
+    if res.status() != 200 {
+        return Err(anyhow!(
+            "failed to update node {id} in {starling}, received status {}",
+            res.status()
+        )
+        .context(Failure::Starling));
+    }
+
+    Ok(())
+}
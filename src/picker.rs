@@ -0,0 +1,102 @@
+//! Interactive picking of a single item from the output of a view, through an external fuzzy
+//! finder like `fzf` or `sk`. Candidates are written to the finder's stdin one per line, and the
+//! line it prints on stdout is the one the user selected.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Presents every item in the given views to the user through the given fuzzy finder command,
+/// returning the one they selected, or `None` if they cancelled or there was nothing to pick
+/// from. Items are anything with an `id` and a `title` (items nested inside others, like the
+/// tasks in a stack, aren't offered separately).
+pub fn pick_item(views: &impl Serialize, picker: &str) -> Result<Option<Value>> {
+    let mut candidates = Vec::new();
+    collect_items(serde_json::to_value(views)?, None, &mut candidates);
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let parts = shellwords::split(picker)
+        .with_context(|| format!("failed to parse picker command `{picker}`"))?;
+    let Some((program, args)) = parts.split_first() else {
+        bail!("picker command is empty");
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run picker `{picker}`"))?;
+    {
+        // Each line ends with the index of its candidate, which is how we find it again
+        let mut stdin = child.stdin.take().unwrap();
+        for (idx, (label, _)) in candidates.iter().enumerate() {
+            writeln!(stdin, "{label}\t{idx}")?;
+        }
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("failed to run picker `{picker}`"))?;
+    // Finders exit with an error if the user cancels, which isn't a problem for us
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let selected = String::from_utf8_lossy(&output.stdout);
+    let idx = selected
+        .lines()
+        .next()
+        .and_then(|line| line.rsplit('\t').next())
+        .and_then(|idx| idx.trim().parse::<usize>().ok());
+    match idx.and_then(|idx| candidates.get(idx)) {
+        Some((_, item)) => Ok(Some(item.clone())),
+        None => bail!(
+            "picker returned an unrecognised selection: {}",
+            selected.trim()
+        ),
+    }
+}
+
+/// Recursively collects anything with an ID and a title from the given (serialised) view, labelling
+/// each one with its title, the type of section it came from, and its date, if it has one.
+pub fn collect_items(value: Value, section: Option<&str>, candidates: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            if let (Some(Value::String(_)), Some(Value::String(title))) =
+                (map.get("id"), map.get("title"))
+            {
+                let mut label = title.clone();
+                if let Some(section) = section {
+                    label.push_str(&format!(" [{section}]"));
+                }
+                let date = ["date", "scheduled", "deadline"]
+                    .iter()
+                    .find_map(|key| map.get(*key).and_then(Value::as_str));
+                if let Some(date) = date {
+                    label.push_str(&format!(" ({date})"));
+                }
+                // Tabs would confuse finding the index again
+                candidates.push((label.replace('\t', " "), Value::Object(map)));
+                return;
+            }
+
+            // Sections are tagged with their type, which is useful to tell items apart
+            let section = match map.get("type") {
+                Some(Value::String(ty)) if map.contains_key("items") => Some(ty.clone()),
+                _ => section.map(str::to_string),
+            };
+            for value in map.into_values() {
+                collect_items(value, section.as_deref(), candidates);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_items(value, section, candidates);
+            }
+        }
+        _ => {}
+    }
+}
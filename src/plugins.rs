@@ -0,0 +1,61 @@
+//! Plugins, which are external commands that produce views of their own from the action items, so
+//! personal types of items can be added without forking Polaris. These are set under `plugins`
+//! in the config file, with the name of the view each produces and the command to run for it:
+//!
+//! ```json
+//! "plugins": { "reading_list": "python3 /home/me/polaris/reading_list.py" }
+//! ```
+//!
+//! A plugin only runs when a view with its name is asked for, which can be a `plugin` view (e.g.
+//! `--view "reading_list plugin"`), or a view of another type. Each command gets all the
+//! normalised action items as a JSON array on stdin, and should print the items for its view as
+//! JSON on stdout (usually an array). These come out in a `plugin` section of the view with the
+//! plugin's name, alongside anything else in that view.
+
+use crate::parse::ActionItem;
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use uuid::Uuid;
+
+/// Runs the plugin with the given name and command over the given action items, returning the
+/// items it produced for its view.
+pub fn run_plugin(
+    name: &str,
+    command: &str,
+    action_items: &HashMap<Uuid, ActionItem>,
+) -> Result<Value> {
+    // Plugins see the items in the same order every time
+    let mut items = action_items.iter().collect::<Vec<_>>();
+    items.sort_unstable_by_key(|(id, _)| **id);
+    let input = serde_json::to_vec(&items.into_iter().map(|(_, item)| item).collect::<Vec<_>>())?;
+
+    let parts = shellwords::split(command)
+        .with_context(|| format!("failed to parse command for plugin `{name}`"))?;
+    let Some((program, args)) = parts.split_first() else {
+        bail!("command for plugin `{name}` is empty");
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run plugin `{name}`"))?;
+    // Writing everything before reading would deadlock if the plugin fills its own output pipe
+    // first, so the items are written from another thread
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("failed to run plugin `{name}`"))?;
+    // Plugins don't have to read everything they're given
+    let _ = writer.join().unwrap();
+    if !output.status.success() {
+        bail!("plugin `{name}` failed with {}", output.status);
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("failed to parse output of plugin `{name}`"))
+}
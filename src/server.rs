@@ -0,0 +1,644 @@
+//! Server mode, in which Polaris stays running and answers HTTP requests, rather than producing
+//! views once and exiting. Requests are handled one at a time, which is plenty for a personal
+//! system being scraped every so often.
+//!
+//! The endpoints are:
+//!
+//! - `GET /metrics`: workload metrics in the Prometheus text format (see [`crate::metrics`]), from
+//!   the same cached nodes as queries
+//! - `GET /ws`: a WebSocket which is sent a message for every view given to the server whenever
+//!   its data changes (and for all of them on connecting), so dashboards don't have to poll. Each
+//!   message is a JSON object with the `view` name and its `sections`, as in the usual output.
+//!   Anything clients send is ignored.
+//! - `POST /query`: produces the views defined in the request body (in the same format as the
+//!   JSON views file), responding with the usual output. These are built from the nodes fetched
+//!   for the WebSocket views, if they're recent enough, so one-off queries stay cheap.
+//!
+//! On SIGTERM or SIGINT, the server stops accepting connections, finishes any request it's in the
+//! middle of, closes the WebSocket connections, and writes what it last fetched to the completion
+//! cache before exiting, so it can be stopped cleanly as a container. Everything it logs goes to
+//! stdout as one JSON object per line, including warnings about the action items (each of which is
+//! only logged the first time it comes up).
+
+use crate::cli::parse_json_views;
+use crate::completions::update_cache;
+use crate::config::Config;
+use crate::metrics::Metrics;
+use crate::parse::Node;
+use crate::views::AllViews;
+use crate::{build_output, fetch_nodes, prepare_items, Section};
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The GUID appended to a client's key to work out the WebSocket handshake response, as fixed by
+/// RFC 6455.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// The largest request body that will be accepted, in bytes.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// How often the server checks whether it's been asked to stop while it has nothing else to do.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the server has been asked to stop. This is set from a signal handler, so it's the only
+/// thing the handler touches.
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+/// Whether the server is running, in which case warnings are logged rather than written to stderr
+/// (see [`crate::warnings`]).
+static SERVING: AtomicBool = AtomicBool::new(false);
+
+/// Listens for requests on the given address until the process is asked to stop. Problems with
+/// individual requests are reported, but don't stop the server. If views are given, they'll be
+/// rebuilt every `refresh` period and pushed to WebSocket clients when they change. Nodes
+/// fetched less than `refresh` ago are reused for queries.
+pub fn serve(
+    config: &Config,
+    listen: &str,
+    views: Option<AllViews>,
+    refresh: Duration,
+) -> Result<()> {
+    handle_signals();
+    SERVING.store(true, Ordering::SeqCst);
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("failed to listen on {listen}"))?;
+    // Accepting without blocking lets the loop notice when it's time to stop
+    listener
+        .set_nonblocking(true)
+        .context("failed to make listener non-blocking")?;
+    log("info", &format!("listening on {listen}"), None);
+    let view_names = views
+        .as_ref()
+        .map(|views| views.names().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let state = State {
+        config,
+        refresh,
+        nodes: NodeCache::default(),
+        live: views.is_some().then(Live::default),
+    };
+    let state = &state;
+    std::thread::scope(|scope| {
+        if let (Some(views), Some(live)) = (views, &state.live) {
+            scope.spawn(move || live.watch(state, views));
+        }
+        while !SHUTDOWN.load(Ordering::SeqCst) {
+            let res = match listener.accept() {
+                Ok((stream, _)) => stream
+                    .set_nonblocking(false)
+                    .context("failed to make connection blocking")
+                    .and_then(|_| handle(stream, state)),
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(POLL_INTERVAL);
+                    Ok(())
+                }
+                Err(err) => Err(err).context("failed to accept connection"),
+            };
+            if let Err(err) = res {
+                log("error", "failed to handle request", Some(&err));
+            }
+        }
+    });
+
+    log("info", "shutting down", None);
+    if let Some(live) = &state.live {
+        live.close();
+    }
+    if let Err(err) = state.nodes.flush(config, view_names.iter()) {
+        log("error", "failed to update completion cache", Some(&err));
+    }
+
+    Ok(())
+}
+
+/// Makes SIGTERM and SIGINT set [`SHUTDOWN`], rather than killing the process straight away.
+#[cfg(unix)]
+fn handle_signals() {
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+    extern "C" fn on_signal(_: i32) {
+        SHUTDOWN.store(true, Ordering::SeqCst);
+    }
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        signal(SIGINT, on_signal);
+        signal(SIGTERM, on_signal);
+    }
+}
+
+/// Signals can't be caught elsewhere, so the server just stops when the process does.
+#[cfg(not(unix))]
+fn handle_signals() {}
+
+/// Determines whether or not the server is running in this process.
+pub fn is_serving() -> bool {
+    SERVING.load(Ordering::SeqCst)
+}
+
+/// Logs the given message at the given level (e.g. `info`), along with the error that caused it,
+/// as a line of JSON on stdout.
+pub fn log(level: &str, message: &str, err: Option<&anyhow::Error>) {
+    let line = LogLine {
+        time: Local::now().to_rfc3339(),
+        level,
+        message,
+        error: err.map(|err| format!("{err:#}")),
+    };
+    if let Ok(line) = serde_json::to_string(&line) {
+        println!("{line}");
+    }
+}
+
+/// A single line of the server's logs.
+#[derive(Serialize)]
+struct LogLine<'a> {
+    /// When this was logged, in RFC 3339 format.
+    time: String,
+    /// How serious this is (`info`, `warn`, or `error`).
+    level: &'a str,
+    /// What happened.
+    message: &'a str,
+    /// The full chain of the error behind this, if there was one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Everything the server shares between requests.
+struct State<'a> {
+    /// The configuration to produce views with.
+    config: &'a Config,
+    /// How often the WebSocket views are rebuilt, which is also how long fetched nodes are reused
+    /// for.
+    refresh: Duration,
+    /// The nodes last fetched from Starling.
+    nodes: NodeCache,
+    /// The state of the WebSocket views, if any were given.
+    live: Option<Live>,
+}
+
+/// Handles a single request on the given connection.
+fn handle(mut stream: TcpStream, state: &State) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let request = Request::read(&mut stream)?;
+    match (request.method.as_str(), request.path.as_str(), &state.live) {
+        ("GET", "/metrics", _) => match metrics(state) {
+            Ok(metrics) => respond(
+                &mut stream,
+                200,
+                "text/plain; version=0.0.4",
+                &metrics.render(),
+            ),
+            Err(err) => respond(&mut stream, 500, "text/plain", &format!("{err:#}\n")),
+        },
+        (_, "/metrics", _) => respond(&mut stream, 405, "text/plain", "method not allowed\n"),
+        ("POST", "/query", _) => {
+            // Bad view definitions are the client's fault, anything after that is ours
+            let views = match parse_json_views(&request.body, state.config.week_start) {
+                Ok(views) => views,
+                Err(err) => {
+                    return respond(&mut stream, 400, "text/plain", &format!("{err:#}\n"));
+                }
+            };
+            match query(state, views) {
+                Ok(body) => respond(&mut stream, 200, "application/json", &body),
+                Err(err) => respond(&mut stream, 500, "text/plain", &format!("{err:#}\n")),
+            }
+        }
+        (_, "/query", _) => respond(&mut stream, 405, "text/plain", "method not allowed\n"),
+        ("GET", "/ws", Some(live)) => {
+            let Some(accept) = request.websocket_accept() else {
+                return respond(
+                    &mut stream,
+                    400,
+                    "text/plain",
+                    "expected a websocket upgrade\n",
+                );
+            };
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+            )?;
+            // A client that stops reading shouldn't hold up everyone else forever
+            stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+            live.connect(stream)
+        }
+        (_, "/ws", Some(_)) => respond(&mut stream, 405, "text/plain", "method not allowed\n"),
+        _ => respond(&mut stream, 404, "text/plain", "not found\n"),
+    }
+}
+
+/// Collects the workload metrics from the cached nodes.
+fn metrics(state: &State) -> Result<Metrics> {
+    let started = Instant::now();
+    let nodes = state.nodes.get(state.config, state.refresh)?;
+    Metrics::collect(state.config, nodes, started.elapsed().as_secs_f64())
+}
+
+/// Produces the given views from the cached nodes, returning the output as JSON.
+fn query(state: &State, views: AllViews) -> Result<String> {
+    let generated_at = Local::now();
+    let started = Instant::now();
+    let nodes = state.nodes.get(state.config, state.refresh)?;
+    let fetched = prepare_items(state.config, &views, nodes.into_iter().map(Ok))?;
+    let output = build_output(
+        state.config,
+        views,
+        fetched,
+        &mut None,
+        generated_at,
+        started,
+    )?;
+    Ok(serde_json::to_string(&output)?)
+}
+
+/// The nodes last fetched from Starling, so that views can be built from them without fetching
+/// everything again each time.
+#[derive(Default)]
+struct NodeCache {
+    /// When the nodes were fetched, along with the nodes themselves.
+    latest: Mutex<Option<(Instant, Vec<Node>)>>,
+}
+impl NodeCache {
+    /// Gets the cached nodes, fetching them again first if they were fetched more than `max_age`
+    /// ago (or never).
+    fn get(&self, config: &Config, max_age: Duration) -> Result<Vec<Node>> {
+        let mut latest = self.latest.lock().unwrap();
+        match &*latest {
+            Some((fetched_at, nodes)) if fetched_at.elapsed() < max_age => Ok(nodes.clone()),
+            _ => {
+                let nodes = fetch_nodes(config)?;
+                *latest = Some((Instant::now(), nodes.clone()));
+                Ok(nodes)
+            }
+        }
+    }
+
+    /// Writes the contexts and people in the cached nodes, along with the given view names, to
+    /// the completion cache. This does nothing if nothing's been fetched.
+    fn flush<'a>(
+        &self,
+        config: &Config,
+        view_names: impl Iterator<Item = &'a String>,
+    ) -> Result<()> {
+        let Some((_, nodes)) = self.latest.lock().unwrap().take() else {
+            return Ok(());
+        };
+        let fetched = prepare_items(config, &AllViews::default(), nodes.into_iter().map(Ok))?;
+        update_cache(&fetched.action_items, view_names)
+    }
+}
+
+/// The state shared between the connections to `/ws` and the thread keeping their views up to
+/// date.
+#[derive(Default)]
+struct Live {
+    /// The last message sent for each view, keyed by view name. This is locked before `clients`
+    /// whenever both are needed.
+    latest: Mutex<BTreeMap<String, String>>,
+    /// The connected WebSocket clients.
+    clients: Mutex<Vec<TcpStream>>,
+}
+impl Live {
+    /// Rebuilds the given views every refresh period until the server is asked to stop, sending
+    /// any that have changed to the connected clients. Failures are reported and retried on the
+    /// next refresh.
+    fn watch(&self, state: &State, views: AllViews) {
+        while !SHUTDOWN.load(Ordering::SeqCst) {
+            if let Err(err) = self.update(state, views.clone()) {
+                log("error", "failed to update live views", Some(&err));
+            }
+            let slept = Instant::now();
+            while slept.elapsed() < state.refresh && !SHUTDOWN.load(Ordering::SeqCst) {
+                std::thread::sleep(POLL_INTERVAL.min(state.refresh));
+            }
+        }
+    }
+
+    /// Rebuilds the given views from freshly fetched nodes, sending any that have changed since
+    /// they were last sent to the connected clients. Clients which can't be written to are
+    /// dropped.
+    fn update(&self, state: &State, views: AllViews) -> Result<()> {
+        let generated_at = Local::now();
+        let started = Instant::now();
+        // This also refreshes the nodes used for queries
+        let nodes = state.nodes.get(state.config, Duration::ZERO)?;
+        let fetched = prepare_items(state.config, &views, nodes.into_iter().map(Ok))?;
+        let output = build_output(
+            state.config,
+            views,
+            fetched,
+            &mut None,
+            generated_at,
+            started,
+        )?;
+
+        let mut latest = self.latest.lock().unwrap();
+        let mut changed = Vec::new();
+        for (view, sections) in &output.views {
+            let message = serde_json::to_string(&Update { view, sections })?;
+            if latest.get(view) != Some(&message) {
+                latest.insert(view.clone(), message.clone());
+                changed.push(message);
+            }
+        }
+        if !changed.is_empty() {
+            self.clients.lock().unwrap().retain_mut(|client| {
+                changed
+                    .iter()
+                    .all(|message| send_text(client, message).is_ok())
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Sends the latest data for every view to a newly connected client, and then adds it to the
+    /// clients which are sent updates.
+    fn connect(&self, mut stream: TcpStream) -> Result<()> {
+        // Holding this until the client has been added means no update can be missed in between
+        let latest = self.latest.lock().unwrap();
+        for message in latest.values() {
+            send_text(&mut stream, message)?;
+        }
+        self.clients.lock().unwrap().push(stream);
+
+        Ok(())
+    }
+
+    /// Tells every connected client the server is going away, and closes their connections.
+    fn close(&self) {
+        for mut client in self.clients.lock().unwrap().drain(..) {
+            // A close frame with the *going away* status code (failures don't matter, since the
+            // connection is being dropped either way)
+            let _ = client.write_all(&[0x88, 0x02, 0x03, 0xE9]);
+            let _ = client.flush();
+            let _ = client.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// A message sent to WebSocket clients with the data for a single view.
+#[derive(Serialize)]
+struct Update<'a> {
+    /// The name of the view.
+    view: &'a str,
+    /// The sections of data in the view.
+    sections: &'a [Section],
+}
+
+/// An HTTP request.
+#[derive(Debug)]
+struct Request {
+    /// The method of the request (e.g. `GET`).
+    method: String,
+    /// The path of the request, without any query string.
+    path: String,
+    /// The headers of the request, keyed by their lowercased names.
+    headers: HashMap<String, String>,
+    /// The body of the request, which will be empty if it didn't have one.
+    body: String,
+}
+impl Request {
+    /// Reads a request from the given connection.
+    fn read(stream: &mut TcpStream) -> Result<Self> {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            bail!("malformed request line: {}", line.trim());
+        };
+        let method = method.to_string();
+        let path = target.split('?').next().unwrap().to_string();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let length = match headers.get("content-length") {
+            Some(length) => length
+                .parse::<usize>()
+                .with_context(|| format!("invalid content length: {length}"))?,
+            None => 0,
+        };
+        if length > MAX_BODY_BYTES {
+            bail!("request body of {length} bytes is too large");
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        let body = String::from_utf8(body).context("request body isn't valid utf-8")?;
+
+        Ok(Self {
+            method,
+            path,
+            headers,
+            body,
+        })
+    }
+
+    /// Works out the `Sec-WebSocket-Accept` value to complete a WebSocket handshake with, or
+    /// returns `None` if this isn't a request to upgrade to a WebSocket.
+    fn websocket_accept(&self) -> Option<String> {
+        let upgrade = self.headers.get("upgrade")?;
+        if !upgrade.eq_ignore_ascii_case("websocket") {
+            return None;
+        }
+        let key = self.headers.get("sec-websocket-key")?;
+        Some(base64(&sha1(format!("{key}{WEBSOCKET_GUID}").as_bytes())))
+    }
+}
+
+/// Writes a complete response with the given status code, content type, and body to the given
+/// connection, closing it afterwards.
+fn respond(stream: &mut TcpStream, status: u16, content_type: &str, body: &str) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Sends the given message to a WebSocket client in a single text frame (server frames are never
+/// masked).
+fn send_text(stream: &mut TcpStream, message: &str) -> Result<()> {
+    let len = message.len();
+    // FIN bit and the text opcode, then the length in as few bytes as it fits in
+    let mut frame = vec![0x81];
+    if len < 126 {
+        frame.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        frame.push(126);
+        frame.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(message.as_bytes());
+    stream.write_all(&frame)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Computes the SHA-1 digest of the given data. This is only used for the WebSocket handshake,
+/// where it has no security role.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = next;
+        }
+        for (value, new) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(new);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, value) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// Encodes the given data in standard, padded base64.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | ((*byte as u32) << (16 - 8 * i))
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((bits >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a `GET` request to `/ws` with the given headers.
+    fn ws_request(headers: &[(&str, &str)]) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: "/ws".to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn websocket_accept_matches_rfc() {
+        // The example handshake from RFC 6455
+        let request = ws_request(&[
+            ("upgrade", "WebSocket"),
+            ("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="),
+        ]);
+        assert_eq!(
+            request.websocket_accept().as_deref(),
+            Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=")
+        );
+    }
+
+    #[test]
+    fn websocket_accept_needs_an_upgrade() {
+        let request = ws_request(&[("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")]);
+        assert_eq!(request.websocket_accept(), None);
+        let request = ws_request(&[("upgrade", "h2c"), ("sec-websocket-key", "x")]);
+        assert_eq!(request.websocket_accept(), None);
+    }
+
+    #[test]
+    fn sha1_matches_known_digests() {
+        let hex = |digest: [u8; 20]| {
+            digest
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            hex(sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        // Long enough to need a second block for the padding
+        assert_eq!(
+            hex(sha1(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+    }
+
+    #[test]
+    fn base64_is_padded() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,77 @@
+//! Tracking of which items have been emitted before, so that Polaris can be run incrementally and
+//! only produce items which are new or have changed since a previous run. The state of each run is
+//! kept in a JSON file as the hashes of the items in each view.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// The items emitted in each view over a run, as hashes of their contents.
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct State {
+    views: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// A record of the items emitted in the previous run and this one, which is used to cut views down
+/// to only the items that are new or changed.
+#[derive(Debug)]
+pub struct Changes {
+    previous: State,
+    current: State,
+}
+impl Changes {
+    /// Loads the state of the previous run from the given file. If it doesn't exist yet, everything
+    /// will be treated as new.
+    pub fn load(path: &Path) -> Result<Self> {
+        let previous = if path.exists() {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read state file {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse state file {}", path.display()))?
+        } else {
+            State::default()
+        };
+
+        Ok(Self {
+            previous,
+            current: State::default(),
+        })
+    }
+
+    /// Records the given items as being in the given view on this run, and returns only those
+    /// which weren't in it on the previous run (including any that have changed since).
+    pub fn filter<T: Serialize>(&mut self, view: &str, items: Vec<T>) -> Result<Vec<T>> {
+        let previous = self.previous.views.get(view);
+        let current = self.current.views.entry(view.to_string()).or_default();
+        let mut changed = Vec::new();
+        for item in items {
+            let hash = content_hash(&item)?;
+            if previous.is_none_or(|previous| !previous.contains(&hash)) {
+                changed.push(item);
+            }
+            current.insert(hash);
+        }
+
+        Ok(changed)
+    }
+
+    /// Saves the state of this run to the given file, for the next run to compare against.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string(&self.current)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("failed to write state file {}", path.display()))
+    }
+}
+
+/// Hashes the contents of the given item (or anything else serialisable). This uses FNV-1a over
+/// the item's JSON form rather than the standard library's hasher, because the hashes need to stay
+/// the same between runs (and versions of Rust).
+pub fn content_hash<T: Serialize>(item: &T) -> Result<String> {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in serde_json::to_vec(item)? {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Ok(format!("{hash:016x}"))
+}
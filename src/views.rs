@@ -1,11 +1,18 @@
 use crate::{
-    extractors::{DailyNote, Event, PersonDate, Stack, Task, Tickle, Waiting},
+    cli::ClockTime,
+    extractors::{
+        sort, DailyNote, Event, GroupBy, Groupable, PersonDate, SortKey, Sortable, Stack, Task,
+        Tickle, Waiting,
+    },
+    filter_expr::FilterExpr,
     parse::{Priority, SimpleTimestamp},
 };
-use anyhow::{bail, Error};
-use chrono::{NaiveDate, NaiveDateTime};
-use clap::{Parser, Subcommand, ValueEnum};
-use serde::Deserialize;
+use anyhow::{anyhow, bail, Error};
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, Weekday};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
 
 /// A single "view" over data. Polaris will filter data according to this view, which can contain
 /// exactly one type of item (e.g. events, tasks, etc.) and a set of filters to apply to that type.
@@ -48,12 +55,98 @@ pub enum View {
     /// date, and will produce the list of these tasks, organised by context (if a task has
     /// multiple contexts, it will appear in each context's list).
     TargetContexts(TargetContextsFilter),
-    /// Produces a list of the goals for the given day, based on the goals source specified
-    /// internally (this part of the code is designed to be forked for your personal setup)
+    /// Tasks sorted into the four quadrants of an Eisenhower matrix, by whether or not they're
+    /// urgent (due soon) and important (high priority). The usual task filters can be used to
+    /// choose which tasks are included.
+    Eisenhower(EisenhowerFilter),
+    /// One or more tasks picked at random from those matching the usual task filters, favouring
+    /// those with higher priorities and closer deadlines, for when it's hard to decide what to
+    /// start on.
+    Pick(PickFilter),
+    /// A board with an entry for every stack, with its items organised into columns by their
+    /// state: non-actionable (`NEXT`), actionable (`TODO`), waiting (`WAIT`), and recently
+    /// completed.
+    Board(BoardFilter),
+    /// The items completed on each day (or in each week) of a date range, by their `CLOSED`
+    /// timestamps, for seeing what's actually been finished.
+    Completed(CompletedFilter),
+    /// How many items (and how much estimated work) were completed each week over a past date
+    /// range, with a forecast of which upcoming deadlines can't be met if work carries on at the
+    /// same rate.
+    Velocity(VelocityFilter),
+    /// A timeline of stacks and their tasks laid out by their scheduled and deadline dates, for
+    /// drawing as a Gantt chart (optionally with Mermaid source for one).
+    Timeline(TimelineFilter),
+    /// Tasks, stacks, and waiting items whose deadlines have already passed, with how many days
+    /// overdue each one is, most overdue first.
+    Overdue(OverdueFilter),
+    /// Items under an inbox tag which haven't been processed yet: those with no keyword, no
+    /// timestamps, and no parent stack.
+    Inbox(InboxFilter),
+    /// The item which is clocked in right now, and how much time was tracked on each item on a
+    /// given day, from the `CLOCK` entries in their logbooks.
+    Clock(ClockFilter),
+    /// How much time was tracked over a date range from the `CLOCK` entries in the logbooks of
+    /// all items (including completed ones), broken down by task, stack, and context.
+    TimeReport(TimeReportFilter),
+    /// Gaps of free time within the working hours of each day in a date range, between timed
+    /// events (and optionally timed tasks), which are at least a minimum length. Holidays have no
+    /// working hours.
+    FreeSlots(FreeSlotsFilter),
+    /// The intervals of time the user is busy on each day in a date range, based on timed events
+    /// (and optionally timed tasks), for sharing availability or feeding to schedulers.
+    Busy(BusyFilter),
+    /// Pairs of consecutive timed events in different locations (from their `LOCATION`
+    /// properties) without enough time between them to travel from one to the other, using the
+    /// travel times in the configuration file.
+    Travel(TravelFilter),
+    /// A comparison of the estimated effort of the tasks scheduled or due on each day in a date
+    /// range against the time available that day, showing how far over capacity each day is.
+    /// Capacity comes from `daily_capacity` in the configuration file, or from the working hours
+    /// if that isn't set.
+    Capacity(CapacityFilter),
+    /// Action items of any type whose title or body contains all the terms in the given query,
+    /// ranked by how well they match. Terms can be quoted to search for whole phrases.
+    Search(SearchFilter),
+    /// A day-by-day agenda over a date range, merging events, tasks with timestamps or
+    /// scheduled/deadline dates on each day, daily notes, tickles, person dates, and holidays into
+    /// a single chronological list for each day.
+    Agenda(AgendaFilter),
+    /// An agenda for a single week (in the same format as [`View::Agenda`]), with one entry for
+    /// each of its seven days.
+    Week(WeekFilter),
+    /// A calendar grid for a single month, as a list of weeks of days, with counts of the events,
+    /// tasks, and deadlines on each day, as well as highlights of particularly important items.
+    Month(MonthFilter),
+    /// Produces a list of the goals for each of the given days, of each of the types set under
+    /// `goals` in the config file
     #[cfg(feature = "goals")]
     Goals(GoalsFilter),
+    /// Compares the goals for the given days with the open tasks and stacks, listing the goals
+    /// with no work linked to them and the work not linked to any goal
+    #[cfg(feature = "goals")]
+    Alignment(GoalsFilter),
+    /// Whatever the plugin with the same name as this view produces (see [`crate::plugins`]).
+    /// Plugins only run for views with their names, which can also be views of other types that
+    /// they add their items to
+    #[cfg(feature = "plugins")]
+    Plugin(PluginFilter),
 }
 impl View {
+    /// Sets the day weeks start on in this view to the given one, if this view cares about weeks
+    /// and doesn't already have a day of its own.
+    pub fn default_week_start(&mut self, week_start: Weekday) {
+        match self {
+            Self::Week(filter) => {
+                filter.week_start.get_or_insert(week_start);
+            }
+            Self::Month(filter) => {
+                filter.week_start.get_or_insert(week_start);
+            }
+            _ => {}
+        }
+    }
+
     /// Validates the order of dates passed to this view. For instance, if this is a
     /// [`View::Events`], this will make sure the `until` date is after the `from` date. This will
     /// similarly ensure deadline dates are after scheduled dates.
@@ -63,26 +156,87 @@ impl View {
     /// things like long notification times on person dates). Note that not all views will involve
     /// a filter on dates, so this may return [`None`] in that case.
     pub fn validate(&self) -> Result<Option<NaiveDate>, Error> {
+        if let Self::TargetContexts(filter) = &self {
+            if filter.tasks_filter.group_by.is_some() {
+                bail!("target contexts are already grouped by context, and can't be grouped again");
+            }
+        }
+        if let Self::Eisenhower(filter) = &self {
+            if filter.tasks_filter.group_by.is_some() {
+                bail!("eisenhower matrices are already grouped into quadrants");
+            }
+        }
+        if let Self::Pick(filter) = &self {
+            if filter.tasks_filter.group_by.is_some() {
+                bail!("picked tasks can't be grouped");
+            }
+        }
+
         match &self {
-            Self::Events(EventsFilter { from, until }) => {
+            Self::Events(EventsFilter {
+                from,
+                until,
+                area: _,
+                group_by,
+                sorting,
+                paging: _,
+            }) => {
+                validate_grouping::<Event>(*group_by)?;
+                validate_sorting::<Event>(sorting)?;
                 if from.is_some_and(|f| *until < f) {
                     bail!("`until` date must be after `from` date");
                 }
                 Ok(Some(*until))
             }
-            Self::DailyNotes(DailyNotesFilter { from, until }) => {
+            Self::DailyNotes(DailyNotesFilter {
+                from,
+                until,
+                area: _,
+                group_by,
+                sorting,
+                paging: _,
+            }) => {
+                validate_grouping::<DailyNote>(*group_by)?;
+                validate_sorting::<DailyNote>(sorting)?;
                 if from.is_some_and(|f| *until < f) {
                     bail!("`until` date must be after `from` date");
                 }
                 Ok(Some(*until))
             }
-            Self::Tickles(TicklesFilter { until }) => Ok(Some(*until)),
-            Self::Dates(DatesFilter { until }) => Ok(Some(*until)),
+            Self::Tickles(TicklesFilter {
+                until,
+                area: _,
+                group_by,
+                sorting,
+                paging: _,
+            }) => {
+                validate_grouping::<Tickle>(*group_by)?;
+                validate_sorting::<Tickle>(sorting)?;
+                Ok(Some(*until))
+            }
+            Self::Dates(DatesFilter {
+                until,
+                area: _,
+                group_by,
+                sorting,
+                paging: _,
+            }) => {
+                validate_grouping::<PersonDate>(*group_by)?;
+                validate_sorting::<PersonDate>(sorting)?;
+                Ok(Some(*until))
+            }
             Self::Waits(WaitsFilter {
                 scheduled,
                 deadline,
                 planning_match: _,
+                stale_before: _,
+                area: _,
+                group_by,
+                sorting,
+                paging: _,
             }) => {
+                validate_grouping::<Waiting>(*group_by)?;
+                validate_sorting::<Waiting>(sorting)?;
                 if deadline.is_some_and(|d| scheduled.is_some_and(|s| d < s)) {
                     bail!("`deadline` date must be after `scheduled` date");
                 }
@@ -95,13 +249,22 @@ impl View {
                 deadline,
                 planning_match: _,
                 timestamp_match: _,
+                stalled: _,
+                filter: _,
+                area: _,
+                group_by,
+                sorting,
+                paging: _,
             }) => {
+                validate_grouping::<Stack>(*group_by)?;
+                validate_sorting::<Stack>(sorting)?;
                 if deadline.is_some_and(|d| scheduled.is_some_and(|s| d < s)) {
                     bail!("`deadline` date must be after `scheduled` date");
                 }
                 if from.is_some_and(|f| until.is_some_and(|u| u < f)) {
                     bail!("`until` date must be after `from` date");
                 }
+                validate_open_range(*from, *until)?;
                 let sd = scheduled.or(*deadline);
                 let fu = until.or(*from);
 
@@ -116,10 +279,20 @@ impl View {
                 parent_timestamp_match: _,
                 planning_match: _,
                 next_tasks: _,
+                hide_blocked: _,
                 contexts: _,
                 min_priority: _,
                 max_priority: _,
                 people: _,
+                age_from,
+                older_than,
+                newer_than,
+                filter: _,
+                area: _,
+                // Tasks can be grouped and sorted any way
+                group_by: _,
+                sorting: _,
+                paging: _,
             })
             | Self::TargetContexts(TargetContextsFilter {
                 tasks_filter:
@@ -132,12 +305,22 @@ impl View {
                         parent_timestamp_match: _,
                         planning_match: _,
                         next_tasks: _,
+                        hide_blocked: _,
                         contexts: _,
                         min_priority: _,
                         max_priority: _,
                         people: _,
+                        age_from,
+                        older_than,
+                        newer_than,
+                        filter: _,
+                        area: _,
+                        group_by: _,
+                        sorting: _,
+                        paging: _,
                     },
                 first_context_only: _,
+                by_area: _,
             }) => {
                 if deadline.is_some_and(|d| scheduled.is_some_and(|s| d < s)) {
                     bail!("`deadline` date must be after `scheduled` date");
@@ -145,13 +328,216 @@ impl View {
                 if from.is_some_and(|f| until.is_some_and(|u| u < f)) {
                     bail!("`until` date must be after `from` date");
                 }
+                validate_open_range(*from, *until)?;
+                if newer_than.is_some_and(|n| older_than.is_some_and(|o| n <= o)) {
+                    bail!("`newer_than` must be greater than `older_than`");
+                }
+                if (older_than.is_some() || newer_than.is_some()) && age_from.is_none() {
+                    bail!("`older_than` and `newer_than` need an `age_from` date to measure from");
+                }
                 let sd = scheduled.or(*deadline);
                 let fu = until.or(*from);
 
                 Ok(sd.max(fu))
             }
+            Self::Eisenhower(filter) => {
+                // Validate the task filters as we would for a normal task view, and make sure we
+                // expand far enough to see every urgent deadline
+                let last_date = Self::Tasks(filter.tasks_filter.clone()).validate()?;
+                Ok(last_date.max(Some(filter.urgent_until())))
+            }
+            Self::Pick(filter) => {
+                if filter.count == 0 {
+                    bail!("`count` must be at least one");
+                }
+                let last_date = Self::Tasks(filter.tasks_filter.clone()).validate()?;
+                Ok(last_date.max(Some(filter.date)))
+            }
+            Self::Board(_) => Ok(None),
+            Self::Completed(CompletedFilter {
+                from,
+                until,
+                weekly: _,
+                include_cancelled: _,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                Ok(Some(*until))
+            }
+            Self::Velocity(VelocityFilter {
+                from,
+                until,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                Ok(Some(*until))
+            }
+            Self::TimeReport(TimeReportFilter {
+                from,
+                until,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                Ok(Some(*until))
+            }
+            Self::Timeline(TimelineFilter {
+                from,
+                until,
+                mermaid: _,
+                paging: _,
+            }) => {
+                if from.is_some_and(|f| until.is_some_and(|u| u < f)) {
+                    bail!("`until` date must be after `from` date");
+                }
+                validate_open_range(*from, *until)?;
+                Ok(*until)
+            }
+            Self::Overdue(OverdueFilter { date, paging: _ }) => Ok(Some(*date)),
+            Self::Inbox(_) => Ok(None),
+            Self::Clock(ClockFilter { date }) => Ok(*date),
+            Self::FreeSlots(FreeSlotsFilter {
+                from,
+                until,
+                day_start,
+                day_end,
+                min_minutes: _,
+                include_tasks: _,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                match (day_start, day_end) {
+                    (Some(start), Some(end)) if end <= start => {
+                        bail!("`day_end` must be after `day_start`")
+                    }
+                    (Some(_), None) | (None, Some(_)) => {
+                        bail!("`day_start` and `day_end` must be given together")
+                    }
+                    _ => {}
+                }
+                Ok(Some(*until))
+            }
+            Self::Busy(BusyFilter {
+                from,
+                until,
+                include_tasks: _,
+                granularity,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                if *granularity == Some(0) {
+                    bail!("`granularity` must be at least one minute");
+                }
+                Ok(Some(*until))
+            }
+            Self::Travel(TravelFilter {
+                from,
+                until,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                Ok(Some(*until))
+            }
+            Self::Capacity(CapacityFilter {
+                from,
+                until,
+                hours,
+                over_only: _,
+                spread: _,
+                include_events: _,
+                weekly: _,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                if hours.is_some_and(|hours| !(0.0..=24.0).contains(&hours)) {
+                    bail!("`hours` must be between 0 and 24");
+                }
+                Ok(Some(*until))
+            }
+            Self::Agenda(AgendaFilter {
+                from,
+                until,
+                travel: _,
+                paging: _,
+            }) => {
+                if until < from {
+                    bail!("`until` date must be after `from` date");
+                }
+                Ok(Some(*until))
+            }
+            Self::Week(filter) => Ok(Some(filter.days().1)),
+            Self::Month(filter) => {
+                let (_, last) = filter.month();
+                Ok(Some(last.week(filter.week_start()).last_day()))
+            }
+            Self::Search(filter) => {
+                if filter.terms()?.is_empty() {
+                    bail!("search query must contain at least one term");
+                }
+                Ok(None)
+            }
             #[cfg(feature = "goals")]
-            Self::Goals(GoalsFilter { date }) => Ok(Some(*date)),
+            Self::Goals(filter) | Self::Alignment(filter) => {
+                if filter.until() < filter.date {
+                    bail!("`until` date must be after `date`");
+                }
+                Ok(Some(filter.until()))
+            }
+            #[cfg(feature = "plugins")]
+            Self::Plugin(_) => Ok(None),
+        }
+    }
+
+    /// Gets the first date in this view, if it has one. Together with the last date from
+    /// [`Self::validate`], this gives the range of dates the view covers.
+    pub fn first_date(&self) -> Option<NaiveDate> {
+        match &self {
+            Self::Events(filter) => filter.from,
+            Self::DailyNotes(filter) => filter.from,
+            Self::Stacks(filter) => filter.from,
+            Self::Tasks(filter) => filter.from,
+            Self::TargetContexts(filter) => filter.tasks_filter.from,
+            Self::Eisenhower(filter) => filter.tasks_filter.from,
+            Self::Pick(filter) => filter.tasks_filter.from,
+            Self::Completed(filter) => Some(filter.from),
+            Self::Velocity(filter) => Some(filter.from),
+            Self::Clock(filter) => filter.date,
+            Self::TimeReport(filter) => Some(filter.from),
+            Self::Timeline(filter) => filter.from,
+            Self::FreeSlots(filter) => Some(filter.from),
+            Self::Busy(filter) => Some(filter.from),
+            Self::Travel(filter) => Some(filter.from),
+            Self::Capacity(filter) => Some(filter.from),
+            Self::Agenda(filter) => Some(filter.from),
+            Self::Week(filter) => Some(filter.days().0),
+            Self::Month(filter) => {
+                let (first, _) = filter.month();
+                Some(first.week(filter.week_start()).first_day())
+            }
+            #[cfg(feature = "goals")]
+            Self::Goals(filter) | Self::Alignment(filter) => Some(filter.date),
+            #[cfg(feature = "plugins")]
+            Self::Plugin(_) => None,
+            Self::Tickles(_)
+            | Self::Dates(_)
+            | Self::Waits(_)
+            | Self::Board(_)
+            | Self::Overdue(_)
+            | Self::Inbox(_)
+            | Self::Search(_) => None,
         }
     }
 }
@@ -165,6 +551,20 @@ pub struct EventsFilter {
     /// The date at which to stop showing items (inclusive).
     #[arg(short, long)]
     until: NaiveDate,
+    /// If given, only events in this area of focus will be shown.
+    #[arg(long)]
+    area: Option<String>,
+    /// Groups the items by the given property, producing a map of group names to items instead
+    /// of a flat list. Items without the property will be grouped under an empty key.
+    #[arg(long)]
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub sorting: Sorting,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
 }
 impl EventsFilter {
     /// Checks if the given event matches this filter or not.
@@ -178,6 +578,10 @@ impl EventsFilter {
                     .date
                     >= from
             })
+            && self
+                .area
+                .as_ref()
+                .is_none_or(|area| ev.area.as_ref() == Some(area))
     }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
@@ -189,10 +593,29 @@ pub struct DailyNotesFilter {
     /// The date at which to stop showing items (inclusive).
     #[arg(short, long)]
     until: NaiveDate,
+    /// If given, only daily notes in this area of focus will be shown.
+    #[arg(long)]
+    area: Option<String>,
+    /// Groups the items by the given property, producing a map of group names to items instead
+    /// of a flat list. Items without the property will be grouped under an empty key.
+    #[arg(long)]
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub sorting: Sorting,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
 }
 impl DailyNotesFilter {
     pub fn matches(&self, dn: &DailyNote) -> bool {
-        dn.date <= self.until && self.from.is_none_or(|from| dn.date >= from)
+        dn.date <= self.until
+            && self.from.is_none_or(|from| dn.date >= from)
+            && self
+                .area
+                .as_ref()
+                .is_none_or(|area| dn.area.as_ref() == Some(area))
     }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
@@ -202,10 +625,28 @@ pub struct TicklesFilter {
     /// for tickles).
     #[arg(short, long)]
     until: NaiveDate,
+    /// If given, only tickles in this area of focus will be shown.
+    #[arg(long)]
+    area: Option<String>,
+    /// Groups the items by the given property, producing a map of group names to items instead
+    /// of a flat list. Items without the property will be grouped under an empty key.
+    #[arg(long)]
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub sorting: Sorting,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
 }
 impl TicklesFilter {
     pub fn matches(&self, t: &Tickle) -> bool {
         t.date <= self.until
+            && self
+                .area
+                .as_ref()
+                .is_none_or(|area| t.area.as_ref() == Some(area))
     }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
@@ -219,10 +660,28 @@ pub struct DatesFilter {
     /// this will show it if the `until` date is on or after the 3rd of January.
     #[arg(short, long)]
     until: NaiveDate,
+    /// If given, only dates in this area of focus will be shown.
+    #[arg(long)]
+    area: Option<String>,
+    /// Groups the items by the given property, producing a map of group names to items instead
+    /// of a flat list. Items without the property will be grouped under an empty key.
+    #[arg(long)]
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub sorting: Sorting,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
 }
 impl DatesFilter {
     pub fn matches(&self, d: &PersonDate) -> bool {
         d.notify_date <= self.until
+            && self
+                .area
+                .as_ref()
+                .is_none_or(|area| d.area.as_ref() == Some(area))
     }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
@@ -244,6 +703,25 @@ pub struct WaitsFilter {
     #[arg(short = 'm', long = "match", default_value = "all")]
     #[serde(default)]
     planning_match: PlanningMatchType,
+    /// If given, only stale items will be shown: those sent before this date with neither a
+    /// scheduled nor a deadline date, which nothing will ever resurface (e.g. to find things
+    /// delegated more than a month ago, set this to a month before the present date).
+    #[arg(long)]
+    stale_before: Option<NaiveDate>,
+    /// If given, only waiting items in this area of focus will be shown.
+    #[arg(long)]
+    area: Option<String>,
+    /// Groups the items by the given property, producing a map of group names to items instead
+    /// of a flat list. Items without the property will be grouped under an empty key.
+    #[arg(long)]
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub sorting: Sorting,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
 }
 impl WaitsFilter {
     pub fn matches(&self, w: &Waiting) -> bool {
@@ -260,6 +738,13 @@ impl WaitsFilter {
         ) && (self.planning_match != PlanningMatchType::ScheduledOrDeadline
             || w.scheduled.is_some()
             || w.deadline.is_some())
+            && self
+                .stale_before
+                .is_none_or(|stale_before| w.is_stale(stale_before))
+            && self
+                .area
+                .as_ref()
+                .is_none_or(|area| w.area.as_ref() == Some(area))
     }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
@@ -267,7 +752,8 @@ pub struct StacksFilter {
     /// The date from which to show stacks with timestamps.
     #[arg(short, long)]
     from: Option<NaiveDate>,
-    /// The date at which to stop showing stacks with timestamps (inclusive).
+    /// The date at which to stop showing stacks with timestamps (inclusive). This is required if
+    /// `from` is given.
     #[arg(short, long)]
     until: Option<NaiveDate>,
     /// How we should match on the stack's timestamp.
@@ -291,6 +777,30 @@ pub struct StacksFilter {
     #[arg(short = 'm', long = "match", default_value = "all")]
     #[serde(default)]
     planning_match: PlanningMatchType,
+    /// Whether or not to only show stalled stacks: those with no actionable tasks of their own
+    /// (e.g. because they're only waiting on others, or on their substacks), which need a next
+    /// action deciding on.
+    #[arg(long)]
+    #[serde(default)]
+    stalled: bool,
+    /// A filter expression for conditions that can't be expressed with the other options (e.g.
+    /// `priority >= high && has:deadline`). See the `filter_expr` module for the syntax.
+    #[arg(long)]
+    filter: Option<FilterExpr>,
+    /// If given, only stacks in this area of focus will be shown.
+    #[arg(long)]
+    area: Option<String>,
+    /// Groups the items by the given property, producing a map of group names to items instead
+    /// of a flat list. Items without the property will be grouped under an empty key.
+    #[arg(long)]
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub sorting: Sorting,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
 }
 impl StacksFilter {
     pub fn matches(&self, p: &Stack) -> bool {
@@ -306,6 +816,12 @@ impl StacksFilter {
             || p.scheduled.is_some()
             || p.deadline.is_some())
             && timestamp_matches(&p.timestamp, self.from, self.until, self.timestamp_match)
+            && (!self.stalled || p.actionable_tasks.is_empty())
+            && self
+                .area
+                .as_ref()
+                .is_none_or(|area| p.area.as_ref() == Some(area))
+            && self.filter.as_ref().is_none_or(|f| f.matches(p))
     }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
@@ -315,7 +831,8 @@ pub struct TasksFilter {
     #[arg(short, long)]
     from: Option<NaiveDate>,
     /// The date at which to stop showing tasks with timestamps (inclusive). This will apply to
-    /// both the task's own timestamp, and to its parent's, if present (i.e. both must match).
+    /// both the task's own timestamp, and to its parent's, if present (i.e. both must match). This
+    /// is required if `from` is given.
     #[arg(short, long)]
     until: Option<NaiveDate>,
     /// How we should match on the task's own timestamp.
@@ -343,10 +860,16 @@ pub struct TasksFilter {
     #[arg(short = 'm', long = "planning_match", default_value = "all")]
     #[serde(default)]
     planning_match: PlanningMatchType,
-    /// Whether or not to show non-actionable tasks with the `NEXT` keyword.
+    /// Whether or not to show non-actionable tasks with the `NEXT` keyword. Tasks on hold (with
+    /// the `HOLD` keyword) are never shown.
     #[arg(short, long)]
     #[serde(default)]
     next_tasks: bool,
+    /// Whether or not to hide tasks which are blocked by other incomplete items (through `DEPENDS`
+    /// or `BLOCKS` properties).
+    #[arg(long)]
+    #[serde(default)]
+    hide_blocked: bool,
     /// The contexts we have "available". Specifying these will filter to only tasks which have
     /// all their required contexts present in this list (tasks with no contexts will not be
     /// shown unless an empty list is provided). If this is not specified, tasks will not be
@@ -364,10 +887,42 @@ pub struct TasksFilter {
     /// empty list is provided). If this is not specified, tasks will not be filtered by their
     /// required people.
     ///
-    /// Note that elements in this list should be the names of people, not their IDs as
-    /// Starling nodes.
+    /// Elements in this list can be either the names of people or their IDs as Starling nodes
+    /// (which are more robust, as names can be ambiguous and change). If several people share a
+    /// name, the start of each one's ID will be appended to it to tell them apart (e.g. `Sam
+    /// (1a2b3c4d)`), and that's the name that should be used here.
     #[arg(short, long)]
     people: Option<Vec<String>>,
+    /// The date the ages of tasks are measured from (usually the present date). If this isn't
+    /// given, tasks won't have ages, and they can't be filtered by them.
+    #[arg(long)]
+    age_from: Option<NaiveDate>,
+    /// Only show tasks created more than this many days before `age_from` (tasks without a
+    /// creation date won't be shown).
+    #[arg(long)]
+    older_than: Option<u32>,
+    /// Only show tasks created fewer than this many days before `age_from` (tasks without a
+    /// creation date won't be shown).
+    #[arg(long)]
+    newer_than: Option<u32>,
+    /// A filter expression for conditions that can't be expressed with the other options (e.g.
+    /// `priority >= high && !context:office`). See the `filter_expr` module for the syntax.
+    #[arg(long)]
+    filter: Option<FilterExpr>,
+    /// If given, only tasks in this area of focus will be shown.
+    #[arg(long)]
+    area: Option<String>,
+    /// Groups the items by the given property, producing a map of group names to items instead
+    /// of a flat list. Items without the property will be grouped under an empty key.
+    #[arg(long)]
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub sorting: Sorting,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
 }
 impl TasksFilter {
     pub fn matches(&self, t: &Task) -> bool {
@@ -384,8 +939,11 @@ impl TasksFilter {
             || t.scheduled.is_some()
             || t.deadline.is_some()) &&
         // -- The rest --
-        // Either we allow non-actionable tasks, or this task must be actionable
-        (self.next_tasks || t.can_start) &&
+        // Either we allow non-actionable tasks, or this task must be actionable (held tasks are
+        // never shown)
+        !t.held && (self.next_tasks || t.can_start) &&
+        // Either we allow blocked tasks, or this task must not be blocked
+        (!self.hide_blocked || t.blocked_by.is_empty()) &&
         // Either we aren't filtering by contexts, or we're showing only tasks with no contexts, or
         // we're showing tasks with contexts where we have all their contexts
         (self.contexts.is_none() || (self.contexts.as_ref().is_some_and(|c| c.is_empty()) && t.contexts.is_empty()) || (t.contexts.iter().all(|c| {
@@ -395,9 +953,13 @@ impl TasksFilter {
         self.min_priority.is_none_or(|min_p| t.priority >= min_p) &&
          self.max_priority.is_none_or(|max_p| t.priority <= max_p) &&
         // Filtering by people is the same as filtering by contexts
-        (self.people.is_none() || (self.people.as_ref().is_some_and(|p| p.is_empty()) && t.people.is_empty()) || (t.people.iter().all(|(_id, p)| {
-            self.people.as_ref().unwrap().contains(p)
+        (self.people.is_none() || (self.people.as_ref().is_some_and(|p| p.is_empty()) && t.people.is_empty()) || (t.people.iter().all(|p| {
+            self.people.as_ref().unwrap().iter().any(|query| matches_person(p, query))
         }) && !t.people.is_empty())) &&
+        // Either we aren't filtering by age, or the task is old/new enough (which requires it to
+        // have an age)
+        self.older_than.is_none_or(|o| self.age(t).is_some_and(|age| age > o as i64)) &&
+        self.newer_than.is_none_or(|n| self.age(t).is_some_and(|age| age < n as i64)) &&
         // Make sure both the task's own timestamp and the parent timestamp match
         timestamp_matches(&t.timestamp, self.from, self.until, self.timestamp_match) &&
         timestamp_matches(
@@ -405,7 +967,15 @@ impl TasksFilter {
             self.from,
             self.until,
             self.parent_timestamp_match,
-        )
+        ) &&
+        self.area.as_ref().is_none_or(|area| t.area.as_ref() == Some(area)) &&
+        self.filter.as_ref().is_none_or(|f| f.matches(t))
+    }
+
+    /// Works out how many days old the given task is as of this filter's `age_from` date, if the
+    /// task has a creation date and the filter has that date.
+    pub fn age(&self, task: &Task) -> Option<i64> {
+        Some((self.age_from? - task.created?).num_days())
     }
 
     /// Creates a new filter for tasks that are relevant to determining the target contexts that
@@ -413,6 +983,18 @@ impl TasksFilter {
     pub fn for_target_contexts(filter: &TargetContextsFilter) -> Self {
         filter.tasks_filter.clone()
     }
+
+    /// Creates a new filter for the tasks that should be sorted into the matrix for the given
+    /// [`EisenhowerFilter`].
+    pub fn for_eisenhower(filter: &EisenhowerFilter) -> Self {
+        filter.tasks_filter.clone()
+    }
+
+    /// Creates a new filter for the tasks that should be picked from for the given
+    /// [`PickFilter`].
+    pub fn for_pick(filter: &PickFilter) -> Self {
+        filter.tasks_filter.clone()
+    }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
 pub struct TargetContextsFilter {
@@ -424,13 +1006,470 @@ pub struct TargetContextsFilter {
     #[arg(long, default_value = "false")]
     #[serde(default)]
     pub(crate) first_context_only: bool,
+    /// If true, tasks will be split up by their area of focus first, and then by context within
+    /// each area. Tasks without an area will be put under an empty key.
+    #[arg(long, default_value = "false")]
+    #[serde(default)]
+    pub(crate) by_area: bool,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct EisenhowerFilter {
+    #[clap(flatten)]
+    #[serde(flatten)]
+    tasks_filter: TasksFilter,
+    /// The date urgency is measured from (usually the present date).
+    #[arg(long)]
+    date: NaiveDate,
+    /// Tasks due within this many days of `date` (including those already overdue) are urgent.
+    #[arg(long, default_value_t = 7)]
+    #[serde(default = "default_urgent_within")]
+    urgent_within: u32,
+    /// The minimum priority for a task to be considered important.
+    #[arg(long, default_value = "high")]
+    #[serde(default = "default_important_priority")]
+    pub important_priority: Priority,
+}
+impl EisenhowerFilter {
+    /// Gets the last date on which a deadline makes a task urgent.
+    pub fn urgent_until(&self) -> NaiveDate {
+        self.date + chrono::Duration::days(self.urgent_within as i64)
+    }
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct PickFilter {
+    #[clap(flatten)]
+    #[serde(flatten)]
+    tasks_filter: TasksFilter,
+    /// The date deadlines are measured from (usually the present date).
+    #[arg(long)]
+    pub date: NaiveDate,
+    /// The number of tasks to pick.
+    #[arg(long, default_value_t = 1)]
+    #[serde(default = "default_pick_count")]
+    pub count: usize,
+    /// A seed for the random picking, so the same tasks will be picked each time. If this isn't
+    /// given, different tasks will be picked each time.
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+fn default_pick_count() -> usize {
+    1
+}
+fn default_urgent_within() -> u32 {
+    7
+}
+fn default_important_priority() -> Priority {
+    Priority::High
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct BoardFilter {
+    /// Items completed on or after this date will be shown in the done column of their stack (this
+    /// uses their `CLOSED` timestamps). If this isn't provided, no completed items will be shown.
+    #[arg(long)]
+    pub done_since: Option<NaiveDate>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct CompletedFilter {
+    /// The first day to show completed items from (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day to show completed items from (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    /// Whether or not to collect items into weeks, rather than individual days.
+    #[arg(long)]
+    #[serde(default)]
+    pub weekly: bool,
+    /// Whether or not to also show items which were cancelled, rather than completed.
+    #[arg(long)]
+    #[serde(default)]
+    pub include_cancelled: bool,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct VelocityFilter {
+    /// The first day to measure completed items from (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day to measure completed items from (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    // Paging applies to the deadline forecast, after the averages have been worked out.
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct TimeReportFilter {
+    /// The first day to total tracked time from (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day to total tracked time from (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct TimelineFilter {
+    /// If given, only stacks which end on or after this date will be shown.
+    #[arg(short, long)]
+    pub from: Option<NaiveDate>,
+    /// If given, only stacks which start on or before this date will be shown.
+    #[arg(short, long)]
+    pub until: Option<NaiveDate>,
+    /// Whether or not to also produce Mermaid `gantt` source for the timeline.
+    #[arg(long)]
+    #[serde(default)]
+    pub mermaid: bool,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct OverdueFilter {
+    /// The date to measure from (usually the present date). Items with deadlines strictly before
+    /// this date are overdue.
+    #[arg(short, long)]
+    pub date: NaiveDate,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct ClockFilter {
+    /// The day to total up tracked time for, which defaults to the present date.
+    #[arg(short, long)]
+    pub date: Option<NaiveDate>,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct InboxFilter {
+    /// The file (relative to the Starling root) whose top-level items are the inbox. This defaults
+    /// to `inbox_file` in the configuration file.
+    #[arg(long)]
+    pub file: Option<String>,
+    /// The tag of the headings items are captured under, which is used to find the inbox if there
+    /// isn't a file for it. This only works when reading from a directory, not from Starling.
+    #[arg(long, default_value = "inbox")]
+    #[serde(default = "default_inbox_tag")]
+    pub tag: String,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+fn default_inbox_tag() -> String {
+    "inbox".to_string()
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct FreeSlotsFilter {
+    /// The first day to find free slots on (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day to find free slots on (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    /// The time the working day starts at on each day, as `HH:MM`. This (along with `day_end`)
+    /// overrides the working hours in the configuration file, and if neither is set, the working
+    /// day will be from 09:00 to 17:00.
+    #[arg(long)]
+    pub day_start: Option<ClockTime>,
+    /// The time the working day ends at on each day, as `HH:MM`.
+    #[arg(long)]
+    pub day_end: Option<ClockTime>,
+    /// The minimum length of a free slot, in minutes. Shorter gaps won't be shown.
+    #[arg(long, default_value_t = 30)]
+    #[serde(default = "default_min_minutes")]
+    pub min_minutes: u32,
+    /// Whether or not tasks with timed timestamps should block out time as well as events.
+    #[arg(long)]
+    #[serde(default)]
+    pub include_tasks: bool,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct BusyFilter {
+    /// The first day to show busy times on (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day to show busy times on (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    /// Whether or not tasks with timed timestamps should count as busy time as well as events.
+    #[arg(long)]
+    #[serde(default)]
+    pub include_tasks: bool,
+    /// If given, busy intervals will be widened to start and end on multiples of this many
+    /// minutes (e.g. `15` to round to the nearest quarter-hour).
+    #[arg(long)]
+    pub granularity: Option<u32>,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct CapacityFilter {
+    /// The first day to plan capacity for (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day to plan capacity for (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    /// The number of hours available for tasks on each working day, overriding the configuration.
+    #[arg(long)]
+    pub hours: Option<f64>,
+    /// Whether or not to only show days which are over capacity.
+    #[arg(long)]
+    #[serde(default)]
+    pub over_only: bool,
+    /// Whether or not to spread the load of each task with a deadline evenly over the days from
+    /// when it's scheduled (or today, if it isn't) to when it's due, rather than counting it all
+    /// on one day.
+    #[arg(long)]
+    #[serde(default)]
+    pub spread: bool,
+    /// Whether or not to take the time spent in timed events out of each day's capacity. If
+    /// working hours are set, only the parts of events within them will count.
+    #[arg(long)]
+    #[serde(default)]
+    pub include_events: bool,
+    /// Whether or not to also total up each week in the range, in a separate section.
+    #[arg(long)]
+    #[serde(default)]
+    pub weekly: bool,
+    // Paging applies to the days shown, after any weekly totals have been worked out.
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct TravelFilter {
+    /// The first day to check for travel conflicts on (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day to check for travel conflicts on (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+fn default_min_minutes() -> u32 {
+    30
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct SearchFilter {
+    /// The query to search for. This is split into terms as a shell would, so phrases can be
+    /// searched for by quoting them (e.g. `budget "quarterly report"`). Matching is
+    /// case-insensitive.
+    #[arg(short, long)]
+    query: String,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+impl SearchFilter {
+    /// Splits the query into its lowercased terms and phrases.
+    pub fn terms(&self) -> Result<Vec<String>, Error> {
+        let terms = shellwords::split(&self.query)
+            .map_err(|_| anyhow!("mismatched quotes in search query"))?
+            .into_iter()
+            .map(|term| term.trim().to_lowercase())
+            .filter(|term| !term.is_empty())
+            .collect();
+        Ok(terms)
+    }
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct AgendaFilter {
+    /// The first day of the agenda (inclusive).
+    #[arg(short, long)]
+    pub from: NaiveDate,
+    /// The last day of the agenda (inclusive).
+    #[arg(short, long)]
+    pub until: NaiveDate,
+    /// Whether or not to add blocks of travel before events in a different location to the one
+    /// before them, using the travel times in the configuration file.
+    #[arg(long)]
+    #[serde(default)]
+    pub travel: bool,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct WeekFilter {
+    /// Any date in the week to show.
+    #[arg(short, long)]
+    date: NaiveDate,
+    /// The day of the week weeks start on, if it's different to the usual one.
+    #[arg(long)]
+    #[serde(default)]
+    week_start: Option<Weekday>,
+    /// Whether or not to add blocks of travel before events in a different location to the one
+    /// before them, using the travel times in the configuration file.
+    #[arg(long)]
+    #[serde(default)]
+    pub travel: bool,
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+impl WeekFilter {
+    /// Gets the first and last days of the week this filter covers.
+    pub fn days(&self) -> (NaiveDate, NaiveDate) {
+        let week = self.date.week(self.week_start.unwrap_or(Weekday::Mon));
+        (week.first_day(), week.last_day())
+    }
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+pub struct MonthFilter {
+    /// Any date in the month to show.
+    #[arg(short, long)]
+    date: NaiveDate,
+    /// The day of the week weeks start on, which determines the columns of the grid, if it's
+    /// different to the usual one.
+    #[arg(long)]
+    #[serde(default)]
+    week_start: Option<Weekday>,
+    // Paging applies to the weeks (rows) of the grid.
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+impl MonthFilter {
+    /// Gets the day of the week weeks start on in the grid.
+    pub fn week_start(&self) -> Weekday {
+        self.week_start.unwrap_or(Weekday::Mon)
+    }
+
+    /// Gets the first and last days of the month this filter covers.
+    pub fn month(&self) -> (NaiveDate, NaiveDate) {
+        let first = self.date.with_day(1).unwrap();
+        let last = first
+            .checked_add_months(Months::new(1))
+            .unwrap()
+            .pred_opt()
+            .unwrap();
+        (first, last)
+    }
 }
 #[derive(Parser, Debug, Clone, Deserialize)]
 #[cfg(feature = "goals")]
 pub struct GoalsFilter {
-    /// The date for which goals should be extracted.
+    /// The date for which goals should be extracted, or the first of them if `until` is given.
     #[arg(short, long)]
     pub date: NaiveDate,
+    /// The last date for which goals should be extracted (inclusive), to extract goals for each
+    /// day in a range (e.g. for a weekly review).
+    #[arg(short, long)]
+    #[serde(default)]
+    pub until: Option<NaiveDate>,
+    // For goal views, paging applies to the days goals were extracted for. For alignment views,
+    // it applies to the unsupported goals and unaligned items separately.
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub paging: Paging,
+}
+#[cfg(feature = "goals")]
+impl GoalsFilter {
+    /// Gets the last date goals should be extracted for.
+    pub fn until(&self) -> NaiveDate {
+        self.until.unwrap_or(self.date)
+    }
+}
+#[derive(Parser, Debug, Clone, Deserialize)]
+#[cfg(feature = "plugins")]
+pub struct PluginFilter {}
+
+/// Options for showing only part of a view's results, which are applied after sorting (e.g. to
+/// show the five most urgent tasks).
+#[derive(Args, Debug, Clone, Default, Deserialize)]
+pub struct Paging {
+    /// The maximum number of items to show. If not present, all matching items will be shown.
+    #[arg(long)]
+    #[serde(default)]
+    limit: Option<usize>,
+    /// The number of items to skip from the start of the sorted results.
+    #[arg(long, default_value_t = 0)]
+    #[serde(default)]
+    offset: usize,
+}
+impl Paging {
+    /// Cuts down the given sorted items to only those this paging permits.
+    pub fn apply<T>(&self, items: &mut Vec<T>) {
+        items.drain(..self.offset.min(items.len()));
+        if let Some(limit) = self.limit {
+            items.truncate(limit);
+        }
+    }
+}
+
+/// Options for sorting a view's results by particular properties, rather than in the usual order
+/// for its type of item.
+#[derive(Args, Debug, Clone, Default, Deserialize)]
+pub struct Sorting {
+    /// A list of properties to sort by, each with an optional direction (e.g. `priority desc` and
+    /// then `deadline asc`). Items without a property will go after those with it, and ties on
+    /// each property are broken by the ones after it. If this isn't given, items will be sorted
+    /// in the usual order for their type.
+    #[arg(long)]
+    #[serde(default)]
+    sort: Vec<SortKey>,
+}
+impl Sorting {
+    /// Sorts the given items by the keys asked for, or by the default keys for their type. The
+    /// items should already be in a fixed order (e.g. by ID), which is kept for any ties.
+    pub fn apply<T: Sortable>(&self, items: &mut Vec<T>) {
+        if self.sort.is_empty() {
+            sort(items, T::DEFAULT_SORT);
+        } else {
+            sort(items, &self.sort);
+        }
+    }
+}
+
+/// Makes sure a timestamp range isn't open-ended. Repeating timestamps are only expanded up to a
+/// buffer after the last date in the views, so a range with no end would silently be missing any
+/// repeats past that. Requiring an `until` date means expansion always covers the whole range.
+fn validate_open_range(from: Option<NaiveDate>, until: Option<NaiveDate>) -> Result<(), Error> {
+    if from.is_some() && until.is_none() {
+        bail!("a `from` date requires an `until` date, as repeats can't be expanded indefinitely");
+    }
+    Ok(())
+}
+
+/// Makes sure items of the given type can be grouped in the requested way, if there is one.
+fn validate_grouping<T: Groupable>(group_by: Option<GroupBy>) -> Result<(), Error> {
+    if let Some(by) = group_by {
+        if !T::GROUPINGS.contains(&by) {
+            bail!("the items in this view can't be grouped by {by}");
+        }
+    }
+    Ok(())
+}
+
+/// Makes sure items of the given type can be sorted in the requested way, if there is one.
+fn validate_sorting<T: Sortable>(sorting: &Sorting) -> Result<(), Error> {
+    for SortKey { by, .. } in &sorting.sort {
+        if !T::SORTINGS.contains(by) {
+            bail!("the items in this view can't be sorted by {by}");
+        }
+    }
+    Ok(())
+}
+
+/// Determines whether or not the given person (as an ID and name) is the one referred to by the
+/// given query, which can be either their name or their ID.
+pub fn matches_person((id, name): &(Uuid, String), query: &str) -> bool {
+    name == query || Uuid::parse_str(query).is_ok_and(|query_id| query_id == *id)
 }
 
 /// Determines whether or not a date on an item meets an imposed cutoff (e.g. its deadline is
@@ -534,7 +1573,7 @@ impl Default for TimestampMatch {
 }
 
 /// An aggregation of the views provided by their data types. Each view has its name associated.
-#[derive(Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct AllViews {
     pub events: Vec<(String, EventsFilter)>,
     pub daily_notes: Vec<(String, DailyNotesFilter)>,
@@ -544,13 +1583,47 @@ pub struct AllViews {
     pub stacks: Vec<(String, StacksFilter)>,
     pub tasks: Vec<(String, TasksFilter)>,
     pub target_contexts: Vec<(String, TargetContextsFilter)>,
+    pub eisenhowers: Vec<(String, EisenhowerFilter)>,
+    pub picks: Vec<(String, PickFilter)>,
+    pub boards: Vec<(String, BoardFilter)>,
+    pub completeds: Vec<(String, CompletedFilter)>,
+    pub velocities: Vec<(String, VelocityFilter)>,
+    pub timelines: Vec<(String, TimelineFilter)>,
+    pub overdues: Vec<(String, OverdueFilter)>,
+    pub inboxes: Vec<(String, InboxFilter)>,
+    pub clocks: Vec<(String, ClockFilter)>,
+    pub time_reports: Vec<(String, TimeReportFilter)>,
+    pub free_slots: Vec<(String, FreeSlotsFilter)>,
+    pub busies: Vec<(String, BusyFilter)>,
+    pub travels: Vec<(String, TravelFilter)>,
+    pub capacities: Vec<(String, CapacityFilter)>,
+    pub searches: Vec<(String, SearchFilter)>,
+    pub agendas: Vec<(String, AgendaFilter)>,
+    pub weeks: Vec<(String, WeekFilter)>,
+    pub months: Vec<(String, MonthFilter)>,
     #[cfg(feature = "goals")]
     pub goals: Vec<(String, GoalsFilter)>,
+    #[cfg(feature = "goals")]
+    pub alignments: Vec<(String, GoalsFilter)>,
+    #[cfg(feature = "plugins")]
+    pub plugins: Vec<(String, PluginFilter)>,
 
     /// The latest date across all the views, if there is one (the user might have specified only
     /// non-date filters). This will be used to define when to stop expanding repeating timestamps
     /// (after a buffer is added).
     pub last_date: Option<NaiveDate>,
+    /// The range of dates each view covers, keyed by view name.
+    pub ranges: BTreeMap<String, DateRange>,
+}
+
+/// The range of dates a view covers, either end of which may be missing if the view doesn't limit
+/// its items in that direction.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct DateRange {
+    /// The first date in the view.
+    pub from: Option<NaiveDate>,
+    /// The last date in the view.
+    pub until: Option<NaiveDate>,
 }
 impl AllViews {
     /// Returns all the view names in this aggregation.
@@ -565,10 +1638,31 @@ impl AllViews {
             .chain(self.waits.iter().map(|(name, _)| name))
             .chain(self.stacks.iter().map(|(name, _)| name))
             .chain(self.tasks.iter().map(|(name, _)| name))
-            .chain(self.target_contexts.iter().map(|(name, _)| name));
+            .chain(self.target_contexts.iter().map(|(name, _)| name))
+            .chain(self.eisenhowers.iter().map(|(name, _)| name))
+            .chain(self.picks.iter().map(|(name, _)| name))
+            .chain(self.boards.iter().map(|(name, _)| name))
+            .chain(self.completeds.iter().map(|(name, _)| name))
+            .chain(self.velocities.iter().map(|(name, _)| name))
+            .chain(self.timelines.iter().map(|(name, _)| name))
+            .chain(self.overdues.iter().map(|(name, _)| name))
+            .chain(self.inboxes.iter().map(|(name, _)| name))
+            .chain(self.clocks.iter().map(|(name, _)| name))
+            .chain(self.time_reports.iter().map(|(name, _)| name))
+            .chain(self.free_slots.iter().map(|(name, _)| name))
+            .chain(self.busies.iter().map(|(name, _)| name))
+            .chain(self.travels.iter().map(|(name, _)| name))
+            .chain(self.capacities.iter().map(|(name, _)| name))
+            .chain(self.searches.iter().map(|(name, _)| name))
+            .chain(self.agendas.iter().map(|(name, _)| name))
+            .chain(self.weeks.iter().map(|(name, _)| name))
+            .chain(self.months.iter().map(|(name, _)| name));
         #[cfg(feature = "goals")]
-        return iter.chain(self.goals.iter().map(|(name, _)| name));
-        #[cfg(not(feature = "goals"))]
-        return iter;
+        let iter = iter
+            .chain(self.goals.iter().map(|(name, _)| name))
+            .chain(self.alignments.iter().map(|(name, _)| name));
+        #[cfg(feature = "plugins")]
+        let iter = iter.chain(self.plugins.iter().map(|(name, _)| name));
+        iter
     }
 }
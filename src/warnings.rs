@@ -0,0 +1,31 @@
+//! Warnings about problems which don't stop Polaris from producing its output. These usually go
+//! to stderr, but server mode promises one JSON object per line on stdout, so while it's running
+//! they're logged that way instead.
+
+use std::collections::BTreeSet;
+use std::sync::{Mutex, PoisonError};
+
+/// The warnings which have already been given through [`warn_once`].
+static GIVEN: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Gives the given warning.
+pub fn warn(message: &str) {
+    #[cfg(feature = "web")]
+    if crate::server::is_serving() {
+        crate::server::log("warn", message, None);
+        return;
+    }
+    eprintln!("{message}");
+}
+
+/// Gives the given warning, unless it's already been given. This is for warnings about the action
+/// items themselves, which server mode would otherwise repeat every time it refreshes.
+pub fn warn_once(message: &str) {
+    let new = GIVEN
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(message.to_string());
+    if new {
+        warn(message);
+    }
+}